@@ -1,9 +1,7 @@
-use std::{
-    ffi::OsString, marker::PhantomData, ops::Range, os::windows::ffi::OsStringExt, ptr::NonNull,
-};
+use std::{marker::PhantomData, ops::Range, ptr::NonNull};
 
 use pelite::{
-    pe::{Pe, Va},
+    pe64::{Pe, Va},
     Align,
 };
 use rayon::{
@@ -32,6 +30,20 @@ pub struct Fd4StepTables<'a> {
 }
 
 impl<'a> Fd4StepTables<'a> {
+    pub fn contains<S: AsRef<str>>(&self, name: S) -> bool {
+        self.inner
+            .binary_search_by_key(&name.as_ref(), |(name, _)| name.as_ref())
+            .is_ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     pub fn by_name<S: AsRef<str>>(&self, name: S) -> Option<Fd4StepFunction> {
         match self
             .inner
@@ -126,7 +138,7 @@ impl<'a> Fd4StepTables<'a> {
                     return None;
                 }
 
-                let name = OsString::from_wide(name).to_string_lossy().into_owned();
+                let name = String::from_utf16_lossy(name);
 
                 Some((name, program.derva::<Va>(fn_src).ok()?))
             })
@@ -190,7 +202,7 @@ impl<'a> Fd4StepTables<'a> {
                     return None;
                 }
 
-                let name = OsString::from_wide(name).to_string_lossy().into_owned();
+                let name = String::from_utf16_lossy(name);
 
                 Some((name, fn_dst))
             })
@@ -1,3 +1,4 @@
 pub mod fd4_step;
+pub mod patterns;
 pub mod pe;
 pub mod rtti;
@@ -0,0 +1,327 @@
+//! Byte patterns for game functions and data that can't be found through RTTI or FD4 step tables.
+//!
+//! The patterns are kept here, rather than next to the hooks that search for them, so
+//! `me3 analyze` can check them against a new build of a game without launching it.
+
+use me3_mod_protocol::Game;
+use strum::VariantArray;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub name: &'static str,
+
+    /// The pattern, in the syntax of [`regex::bytes::Regex`].
+    pub regex: &'static str,
+
+    /// The section searched, or `None` if the whole image is.
+    pub section: Option<&'static str>,
+
+    /// The games the pattern is searched in.
+    pub games: &'static [Game],
+
+    /// Whether the first match is used, so the pattern should match exactly once, rather than
+    /// the most common or the last of its matches.
+    pub unique: bool,
+
+    /// The pattern this one is searched for in place of when it doesn't match, so only a miss of
+    /// both breaks the hook that uses them.
+    pub fallback_for: Option<&'static Pattern>,
+
+    /// Why the pattern is known not to match, so `me3 analyze` doesn't report a miss as a
+    /// problem.
+    pub known_broken: Option<&'static str>,
+}
+
+/// Matches:
+/// call   ??
+/// mov    rsi,QWORD PTR [rsp+0x30]
+/// mov    rbx,QWORD PTR [rsp+0x38]
+/// add    rsp,0x20
+/// pop    rdi
+/// jmp    GetSystemAllocator
+pub const SYSTEM_ALLOCATOR: Pattern = Pattern {
+    name: "system allocator getter",
+    regex: r"(?s-u)\xe8.{4}\x48\x8b\x74\x24\x30\x48\x8b\x5c\x24\x38\x48\x83\xc4\x20\x5f\xe9(.{4})",
+    section: Some(".text"),
+    games: &[Game::DarkSouls3, Game::Sekiro, Game::EldenRing],
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches the end of the `CSMemoryImp` constructor, which stores its vtable.
+pub const CS_MEMORY_VTABLE: Pattern = Pattern {
+    name: "CSMemoryImp vtable",
+    regex: r"(?s-u)\xe8.{4}\x90\x48\x8d\x05(.{4})\x48\x89\x03\xc6\x83.\x02\x00\x00\x00\x48\x8b\xc3\x48\x83\xc4.\x5b\xc3",
+    section: None,
+    games: &[Game::DarkSouls3, Game::Sekiro, Game::EldenRing],
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches storing the first heap allocator in the allocator table.
+pub const FIRST_ALLOCATOR: Pattern = Pattern {
+    name: "first allocator",
+    regex: r"(?s-u)\x48\x89\x05(.{4})\x4c\x8b\xc0\xba\x08\x00\x00\x00\x8d\x4a\x08",
+    section: None,
+    games: &[Game::DarkSouls3, Game::Sekiro, Game::EldenRing],
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches storing the last heap allocator in the allocator table, the last match of which is
+/// used.
+pub const LAST_ALLOCATOR_DS3: Pattern = Pattern {
+    name: "last allocator",
+    regex: r"(?s-u)\x48\x89\x05(.{4})\x4c\x8b\xc0\xba\x08\x00\x00\x00\x8d\x4a\x78",
+    section: None,
+    games: &[Game::DarkSouls3],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// See [`LAST_ALLOCATOR_DS3`].
+pub const LAST_ALLOCATOR_SEKIRO: Pattern = Pattern {
+    name: "last allocator",
+    regex: r"(?s-u)\x48\x89\x05(.{4})\x4c\x8b\xc0\xba\x08\x00\x00\x00\x8d\x4a\x70",
+    section: None,
+    games: &[Game::Sekiro],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// See [`LAST_ALLOCATOR_DS3`].
+pub const LAST_ALLOCATOR: Pattern = Pattern {
+    name: "last allocator",
+    regex: r"(?s-u)\x48\x89\x3d(.{4})\xc7\x44\x24\x20\xff\xff\xff\xff\x45\x33\xc9\x4c\x8b\xc7\x48\x8d\x15.{4}",
+    section: None,
+    games: &[Game::EldenRing],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches a call to the debug allocator getter.
+pub const DEBUG_ALLOCATOR_DS3: Pattern = Pattern {
+    name: "debug allocator getter",
+    regex: r"(?s-u)\x48\x8b\x1d.{4}\x48\x8b\x0d.{4}\xe8(.{4})\x48\x8b\xd0\x45\x33\xc0\x48\x8b\xcb\xe8.{4}\xe8.{4}",
+    section: Some(".text"),
+    games: &[Game::DarkSouls3],
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches calls to the debug allocator getter, the most common target of which is used.
+pub const DEBUG_ALLOCATOR_SEKIRO: Pattern = Pattern {
+    name: "debug allocator getter",
+    regex: r"(?s-u)\xe8(.{4})\x48\x89\x44\x24.\x4c\x8b\xc0\xba\x08\x00\x00\x00\xb9\x90\x00\x00\x00\xe8.{4}",
+    section: Some(".text"),
+    games: &[Game::Sekiro],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// "TitleStep::STEP_BeginLogo" as a UTF-16 string.
+pub const TITLE_STEP_NAME: Pattern = Pattern {
+    name: "TitleStep::STEP_BeginLogo name",
+    regex: r"(?s-u)T\x00i\x00t\x00l\x00e\x00S\x00t\x00e\x00p\x00:\x00:\x00S\x00T\x00E\x00P\x00_\x00B\x00e\x00g\x00i\x00n\x00L\x00o\x00g\x00o\x00",
+    section: Some(".rdata"),
+    games: &[Game::EldenRing, Game::ArmoredCore6, Game::Nightreign],
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches:
+/// rex push rbp
+/// push   rsi
+/// push   rdi
+/// lea    rbp,[rsp+??]
+/// sub    rsp,??
+/// mov    QWORD PTR [rbp+??],-2
+/// mov    QWORD PTR [rsp+??],rbx
+/// mov    rdi,rcx
+/// mov    BYTE PTR [rip+??],0x1
+pub const SPRJ_TITLE_STEP: Pattern = Pattern {
+    name: "TitleStep::STEP_BeginLogo",
+    regex: r"(?s-u)\x40\x55\x56\x57\x48\x8d\x6c\x24.\x48\x81\xec.{4}\x48\xc7\x45.\xfe\xff\xff\xff\x48\x89\x9c\x24.{4}\x48\x8b\xf9\xc6\x05.{4}\x01",
+    section: Some(".text"),
+    games: &[Game::DarkSouls3, Game::Sekiro],
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches callsites for the boolean DLSystemProperty getter, the most common target of which is
+/// used.
+///
+/// In Dark Souls 3, Sekiro and ER, the getter takes in a reference to a DLString,
+/// in later games, it was changed to a nul terminated UTF-16 string pointer.
+///
+/// The patterns match loading the pointer to the `std::map` containing the property names
+/// and their values loaded in RCX, the queried property name in RDX and true/false in R8B
+/// as the default value in the case of the property missing from the map.
+pub const BOOL_PROPERTY_GETTER_DS3: Pattern = Pattern {
+    name: "boolean property getter",
+    regex: r"(?s-u)(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x88\x05.{4}\x48\x83\x7c\x24\x48\x08\x72.)|(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x0f\xb6\xd8\x48\x83\x7c\x24\x48\x08\x72.)",
+    section: Some(".text"),
+    games: &[Game::DarkSouls3],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// See [`BOOL_PROPERTY_GETTER_DS3`].
+pub const BOOL_PROPERTY_GETTER_SEKIRO: Pattern = Pattern {
+    name: "boolean property getter",
+    regex: r"(?s-u)(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x88\x05.{4}\x48\x83\x7c\x24\x50\x08\x72.)|(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x0f\xb6\xd8\x48\x83\x7c\x24\x50\x08\x72.)",
+    section: Some(".text"),
+    games: &[Game::Sekiro, Game::EldenRing],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// See [`BOOL_PROPERTY_GETTER_DS3`].
+pub const BOOL_PROPERTY_GETTER: Pattern = Pattern {
+    name: "boolean property getter",
+    regex: r"(?s-u)(?:(?:\x45\x33\xc0)|(?:\x41\xb0\x01))\x48\x8d\x15.{4}\x48\x8b\x0d.{4}\xe8(.{4})",
+    section: Some(".text"),
+    games: &[Game::ArmoredCore6, Game::Nightreign],
+    unique: false,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Matches:
+/// lea    rcx,[rsp+0x30]
+/// call   ??
+/// test   al,al
+/// je     ??
+///
+/// Calls are checked to be calls to the function writing the regulation to the savefile.
+///
+/// The leading `(s?-u)` is a misspelled `(?s-u)` and matches the literal text "-u", so the
+/// pattern never matches and the oversized regulation fix it's used for isn't applied. It's kept
+/// as is, and marked known broken, until the fix is tested with the pattern matching.
+pub const REGULATION_WRITE_CALL: Pattern = Pattern {
+    name: "savefile regulation writer calls",
+    regex: r"(s?-u)\x48\x8d\x4c\x24.\xe8(.{4})\x84\xc0\x74.",
+    section: Some(".text"),
+    games: &[Game::DarkSouls3, Game::Sekiro],
+    unique: false,
+    fallback_for: None,
+    known_broken: Some("its flags group is misspelled, so it never matches"),
+};
+
+/// Matches:
+/// mov    reg,QWORD PTR [rbp+??]
+/// mov    QWORD PTR [rsp+0x28],reg
+/// mov    QWORD PTR [rsp+0x20],reg
+/// mov    r9,QWORD PTR [rip+??]
+/// mov    rdx,reg
+/// mov    rcx,reg
+/// call   MountEbl
+/// movzx  ebx,al
+/// cmp    QWORD PTR [rbp/rsp+??],0x8
+pub const MOUNT_EBL: Pattern = Pattern {
+    name: "MountEbl",
+    regex: r"(?s-u)\x48\x8b\x45.\x48\x89\x44\x24\x28[\x48|\x4c]\x89[\x44\x4c\x54\x5c\x64\x6c\x74\x7c]\x24\x20\x4c\x8b\x0d.{4}[\x48|\x49]\x8b[\xd0-\xd7][\x48|\x49]\x8b[\xc8-\xcf]\xe8(.{4})\x0f\xb6\xd8(?:(?:\x48\x83\x7d.\x08)|(?:\x48\x83\x7c\x24.\x08))\x72.",
+    section: Some(".text"),
+    games: Game::VARIANTS,
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+/// Not nearly as reliable as [`MOUNT_EBL`] (matches an entire function as-is), but works on all
+/// current game builds.
+///
+/// Matches:
+/// push   rbx
+/// sub    rsp,0x30
+/// mov    rax,QWORD PTR [rsp+0x70]
+/// mov    r10,r9
+/// mov    r9,QWORD PTR [rsp+0x60]
+/// mov    r11,r8
+/// mov    QWORD PTR [rsp+0x28],rax
+/// mov    rcx,rdx
+/// mov    rax,QWORD PTR [rsp+0x68]
+/// mov    r8,r10
+/// mov    rdx,r11
+/// mov    QWORD PTR [rsp+0x20],rax
+/// call   MountEbl
+/// add    rsp,0x30
+/// pop    rbx
+/// ret
+pub const MOUNT_EBL_ALT: Pattern = Pattern {
+    name: "MountEbl (fallback)",
+    regex: r"(?s-u)\x53\x48\x83\xec\x30\x48\x8b\x44\x24\x70\x4d\x8b\xd1\x4c\x8b\x4c\x24\x60\x4d\x8b\xd8\x48\x89\x44\x24\x28\x48\x8b\xca\x48\x8b\x44\x24\x68\x4d\x8b\xc2\x49\x8b\xd3\x48\x89\x44\x24\x20\xe8(.{4})\x48\x83\xc4\x30\x5b\xc3",
+    section: Some(".text"),
+    games: Game::VARIANTS,
+    unique: true,
+    fallback_for: Some(&MOUNT_EBL),
+    known_broken: None,
+};
+
+/// Matches:
+/// call   WwiseOpenFileByName
+/// cmp    eax,0x1
+/// je     ??
+/// add    reg,0x38
+/// cmp    QWORD PTR [rbp+0x0],0x8
+pub const WWISE_OPEN_FILE: Pattern = Pattern {
+    name: "WwiseOpenFileByName",
+    regex: r"(?s-u)\xe8(.{4})\x83\xf8\x01(?:(?:\x74.)|(?:\x0f\x84.{4}))[\x48-\x4f]\x83[\xc0-\xc7]\x38[\x48-\x4f]\x83(?:(?:\x7d.)|(?:\xbd.{4}))\x08",
+    section: Some(".text"),
+    games: Game::VARIANTS,
+    unique: true,
+    fallback_for: None,
+    known_broken: None,
+};
+
+pub const ALL: &[Pattern] = &[
+    SYSTEM_ALLOCATOR,
+    CS_MEMORY_VTABLE,
+    FIRST_ALLOCATOR,
+    LAST_ALLOCATOR_DS3,
+    LAST_ALLOCATOR_SEKIRO,
+    LAST_ALLOCATOR,
+    DEBUG_ALLOCATOR_DS3,
+    DEBUG_ALLOCATOR_SEKIRO,
+    TITLE_STEP_NAME,
+    SPRJ_TITLE_STEP,
+    BOOL_PROPERTY_GETTER_DS3,
+    BOOL_PROPERTY_GETTER_SEKIRO,
+    BOOL_PROPERTY_GETTER,
+    REGULATION_WRITE_CALL,
+    MOUNT_EBL,
+    MOUNT_EBL_ALT,
+    WWISE_OPEN_FILE,
+];
+
+/// The patterns searched in `game`.
+pub fn for_game(game: Game) -> impl Iterator<Item = &'static Pattern> {
+    ALL.iter()
+        .filter(move |pattern| pattern.games.contains(&game))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::bytes::Regex;
+
+    use super::ALL;
+
+    #[test]
+    fn patterns_compile() {
+        for pattern in ALL {
+            assert!(Regex::new(pattern.regex).is_ok(), "{}", pattern.name);
+        }
+    }
+}
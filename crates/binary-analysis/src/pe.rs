@@ -1,6 +1,6 @@
 use std::mem::MaybeUninit;
 
-use pelite::pe::{headers::SectionHeader, Pe};
+use pelite::pe64::{headers::SectionHeader, Pe};
 
 pub fn section<'a, P, S>(program: P, name: S) -> Result<&'a SectionHeader, S>
 where
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, ffi::CStr, marker::PhantomData, ops::Range, ptr};
 
-use pelite::pe::{
+use pelite::pe64::{
     msvc::{
         RTTIBaseClassDescriptor, RTTIClassHierarchyDescriptor, RTTICompleteObjectLocator,
         TypeDescriptor, PMD,
@@ -1,10 +1,24 @@
+use analyze::AnalyzeArgs;
+use assets::AssetsCommands;
+use bug_report::BugReportArgs;
+use cache::CacheCommands;
 use clap::*;
 use launch::LaunchArgs;
+use logs::LogsArgs;
 use profile::ProfileCommands;
+use reload_native::ReloadNativeArgs;
+use trace::TraceCommands;
 
+pub mod analyze;
+pub mod assets;
+pub mod bug_report;
+pub mod cache;
 pub mod info;
 pub mod launch;
+pub mod logs;
 pub mod profile;
+pub mod reload_native;
+pub mod trace;
 
 #[cfg(target_os = "windows")]
 pub mod windows;
@@ -23,6 +37,37 @@ pub enum Commands {
     #[clap(subcommand, disable_version_flag = true)]
     Profile(ProfileCommands),
 
+    /// Show one or more log files as a single, filtered timeline.
+    #[clap(disable_version_flag = true)]
+    Logs(LogsArgs),
+
+    /// Finalize a native in a running game and load it again from its rebuilt DLL.
+    ///
+    /// The game must have been launched with `--shadow-copy-natives`.
+    #[clap(disable_version_flag = true)]
+    ReloadNative(ReloadNativeArgs),
+
+    /// Query the asset requests recorded by `me3 launch --trace-assets`.
+    #[clap(subcommand, disable_version_flag = true)]
+    Trace(TraceCommands),
+
+    /// Look up files in a game's archives, and find package files that don't override any.
+    #[clap(subcommand, disable_version_flag = true)]
+    Assets(AssetsCommands),
+
+    /// Check a game executable for the classes, FD4 steps and byte patterns me3 hooks, without
+    /// launching it.
+    #[clap(disable_version_flag = true)]
+    Analyze(AnalyzeArgs),
+
+    /// Manage the archive headers boot boost caches.
+    #[clap(subcommand, disable_version_flag = true)]
+    Cache(CacheCommands),
+
+    /// Bundle logs, the profile and environment details into a zip file to attach to a bug report.
+    #[clap(disable_version_flag = true)]
+    BugReport(BugReportArgs),
+
     #[cfg(target_os = "windows")]
     #[clap(hide = true)]
     AddToPath,
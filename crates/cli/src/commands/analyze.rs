@@ -0,0 +1,397 @@
+use std::{fs, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::eyre;
+use me3_binary_analysis::{
+    fd4_step::Fd4StepTables,
+    patterns::{self, Pattern},
+    rtti,
+};
+use pelite::pe64::{Pe, PeFile, PeObject};
+use regex::bytes::Regex;
+
+use crate::{output::OutputBuilder, Game};
+
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Path of the game executable (e.g. "Game/eldenring.exe").
+    #[clap(value_hint = clap::ValueHint::FilePath)]
+    exe: PathBuf,
+
+    /// Game the executable is from, if it can't be told by its file name.
+    #[clap(short, long, value_enum)]
+    game: Option<Game>,
+}
+
+/// Something me3 looks up in a game, by any of its names, in the games it's looked up in.
+struct Lookup {
+    names: &'static [&'static str],
+    games: &'static [me3_mod_protocol::Game],
+}
+
+/// RTTI classes whose vtables me3 hooks.
+const CLASSES: &[Lookup] = {
+    use me3_mod_protocol::Game::*;
+
+    &[
+        Lookup {
+            names: &["CS::CSMemoryImp", "NS_SPRJ::CSMemoryImp"],
+            games: &[DarkSouls3, Sekiro, EldenRing],
+        },
+        Lookup {
+            names: &["CS::CSGraphicsImp"],
+            games: &[EldenRing],
+        },
+        Lookup {
+            names: &["DLEBL::DLEncryptedBinderLightUtility"],
+            games: &[DarkSouls3, Sekiro, EldenRing, ArmoredCore6, Nightreign],
+        },
+        Lookup {
+            names: &["DLMOW::IOHookBlocking"],
+            games: &[DarkSouls3, Sekiro, EldenRing, ArmoredCore6, Nightreign],
+        },
+    ]
+};
+
+/// FD4 steps me3 hooks or loads natives after.
+const STEPS: &[Lookup] = {
+    use me3_mod_protocol::Game::*;
+
+    &[
+        Lookup {
+            names: &["CSFileStep::STEP_Init", "SprjFileStep::STEP_Init"],
+            games: &[DarkSouls3, Sekiro, EldenRing, ArmoredCore6, Nightreign],
+        },
+        Lookup {
+            names: &["CSRegulationStep::STEP_Idle"],
+            games: &[EldenRing, ArmoredCore6, Nightreign],
+        },
+    ]
+};
+
+#[tracing::instrument(err, skip_all)]
+pub fn analyze(args: AnalyzeArgs) -> color_eyre::Result<()> {
+    let image =
+        fs::read(&args.exe).map_err(|e| eyre!("failed to read {}: {e}", args.exe.display()))?;
+
+    let file = PeFile::from_bytes(&image)
+        .map_err(|e| eyre!("failed to read {}: {e}", args.exe.display()))?;
+
+    let game = args.game.or_else(|| {
+        let file_name = args.exe.file_name()?;
+
+        Game::value_variants().iter().copied().find(|game| {
+            game.launcher()
+                .file_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+        })
+    });
+
+    let is_used =
+        |games: &[me3_mod_protocol::Game]| game.is_none_or(|game| games.contains(&game.0));
+
+    let classes = rtti::classes(file)?;
+    let steps = Fd4StepTables::from_static_initializers(file)?;
+
+    let mut problems = 0;
+
+    let mut output = OutputBuilder::new("Executable analysis");
+    output.property("Executable", args.exe.display());
+
+    match game {
+        Some(game) => output.property("Game", game.0),
+        None => output.property("Game", "Unknown, checking what me3 looks up in every game"),
+    }
+
+    output.property("RTTI classes", classes.len());
+    output.property("FD4 steps", steps.len());
+
+    output.section("RTTI classes", |builder| {
+        for lookup in CLASSES.iter().filter(|lookup| is_used(lookup.games)) {
+            let found = lookup
+                .names
+                .iter()
+                .find(|name| classes.contains_key(**name));
+
+            problems += usize::from(found.is_none());
+            builder.property(lookup.names.join(" / "), found_status(found));
+        }
+    });
+
+    output.section("FD4 steps", |builder| {
+        for lookup in STEPS.iter().filter(|lookup| is_used(lookup.games)) {
+            let found = lookup.names.iter().find(|name| steps.contains(**name));
+
+            problems += usize::from(found.is_none());
+            builder.property(lookup.names.join(" / "), found_status(found));
+        }
+    });
+
+    let pattern_counts = patterns::ALL
+        .iter()
+        .filter(|pattern| is_used(pattern.games))
+        .map(|pattern| (pattern, count_matches(file, pattern)))
+        .collect::<Vec<_>>();
+
+    output.section("Patterns", |builder| {
+        for (pattern, count) in &pattern_counts {
+            let name = match game {
+                Some(_) => pattern.name.to_owned(),
+                None => format!("{} ({})", pattern.name, game_names(pattern)),
+            };
+
+            let (status, is_problem) = pattern_status(pattern, count, &pattern_counts);
+
+            problems += usize::from(is_problem);
+            builder.property(name, status);
+        }
+    });
+
+    println!("{}", output.build());
+
+    if problems > 0 {
+        return Err(eyre!(
+            "{problems} lookups failed, the hooks that use them may be broken"
+        ));
+    }
+
+    Ok(())
+}
+
+fn found_status(found: Option<&&str>) -> String {
+    match found {
+        Some(name) => format!("Found as {name}"),
+        None => "Not found".to_owned(),
+    }
+}
+
+fn game_names(pattern: &Pattern) -> String {
+    pattern
+        .games
+        .iter()
+        .map(|game| game.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Describes the matches of `pattern`, and whether they break the hook that uses it in a way
+/// that isn't already known.
+fn pattern_status(
+    pattern: &Pattern,
+    count: &Result<usize, String>,
+    pattern_counts: &[(&Pattern, Result<usize, String>)],
+) -> (String, bool) {
+    let (status, is_problem) = match count {
+        Ok(1) => ("1 match".to_owned(), false),
+        Ok(0) => match matched_alternative(pattern, pattern_counts) {
+            Some(alternative) => (
+                format!("No matches, but {} matched", alternative.name),
+                false,
+            ),
+            None => ("No matches".to_owned(), true),
+        },
+        Ok(count) if pattern.unique => (format!("{count} matches, expected one"), true),
+        Ok(count) => (format!("{count} matches"), false),
+        Err(e) => (e.clone(), true),
+    };
+
+    match pattern.known_broken {
+        Some(reason) if is_problem => (format!("{status}, known broken: {reason}"), false),
+        _ => (status, is_problem),
+    }
+}
+
+/// A pattern that matched among the alternatives of `pattern`, which are the pattern it's a
+/// fallback for and the other fallbacks for that pattern.
+fn matched_alternative<'a>(
+    pattern: &Pattern,
+    pattern_counts: &[(&'a Pattern, Result<usize, String>)],
+) -> Option<&'a Pattern> {
+    let primary = pattern.fallback_for.unwrap_or(pattern);
+
+    pattern_counts
+        .iter()
+        .find(|(other, count)| {
+            *other != pattern
+                && other.fallback_for.unwrap_or(other) == primary
+                && count.as_ref().is_ok_and(|count| *count > 0)
+        })
+        .map(|(other, _)| *other)
+}
+
+/// Counts the matches of `pattern` in the section it's searched in.
+fn count_matches(file: PeFile, pattern: &Pattern) -> Result<usize, String> {
+    let bytes = match pattern.section {
+        Some(name) => file
+            .section_headers()
+            .by_name(name)
+            .ok_or_else(|| format!("Section {name} is missing"))
+            .and_then(|section| file.get_section_bytes(section).map_err(|e| e.to_string()))?,
+        None => file.image(),
+    };
+
+    let regex = Regex::new(pattern.regex).map_err(|e| e.to_string())?;
+
+    Ok(regex.find_iter(bytes).count())
+}
+
+#[cfg(test)]
+mod tests {
+    use me3_binary_analysis::patterns::{Pattern, MOUNT_EBL, MOUNT_EBL_ALT, REGULATION_WRITE_CALL};
+    use pelite::pe64::PeFile;
+
+    use super::{count_matches, pattern_status};
+
+    /// The function [`MOUNT_EBL_ALT`] matches, calling MountEbl at a zero offset.
+    const MOUNT_EBL_THUNK: &[u8] = b"\x53\x48\x83\xec\x30\x48\x8b\x44\x24\x70\x4d\x8b\xd1\x4c\x8b\x4c\x24\x60\x4d\x8b\xd8\x48\x89\x44\x24\x28\x48\x8b\xca\x48\x8b\x44\x24\x68\x4d\x8b\xc2\x49\x8b\xd3\x48\x89\x44\x24\x20\xe8\x00\x00\x00\x00\x48\x83\xc4\x30\x5b\xc3";
+
+    /// Builds a 64-bit PE image with a single `.text` section holding `text`.
+    fn pe_with_text(text: &[u8]) -> Vec<u8> {
+        const HEADERS_SIZE: u32 = 0x200;
+        const TEXT_RVA: u32 = 0x1000;
+
+        let text_size = (text.len() as u32).next_multiple_of(0x200);
+
+        let mut image = vec![0; HEADERS_SIZE as usize];
+
+        // DOS header, pointing at the NT headers right after it.
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+
+        let mut headers = Vec::new();
+
+        // File header.
+        headers.extend_from_slice(b"PE\0\0");
+        headers.extend_from_slice(&0x8664u16.to_le_bytes());
+        headers.extend_from_slice(&1u16.to_le_bytes());
+        headers.extend_from_slice(&[0; 12]);
+        headers.extend_from_slice(&0xF0u16.to_le_bytes());
+        headers.extend_from_slice(&0x22u16.to_le_bytes());
+
+        // Optional header.
+        let optional_header = headers.len();
+        headers.resize(optional_header + 0xF0, 0);
+
+        let mut put = |offset: usize, bytes: &[u8]| {
+            headers[optional_header + offset..][..bytes.len()].copy_from_slice(bytes);
+        };
+
+        put(0x00, &0x20Bu16.to_le_bytes());
+        put(0x18, &0x1_4000_0000u64.to_le_bytes());
+        put(0x20, &0x1000u32.to_le_bytes());
+        put(0x24, &0x200u32.to_le_bytes());
+        put(0x30, &6u16.to_le_bytes());
+        put(
+            0x38,
+            &(TEXT_RVA + text_size.next_multiple_of(0x1000)).to_le_bytes(),
+        );
+        put(0x3C, &HEADERS_SIZE.to_le_bytes());
+        put(0x44, &3u16.to_le_bytes());
+        put(0x6C, &16u32.to_le_bytes());
+
+        // Section header.
+        headers.extend_from_slice(b".text\0\0\0");
+        headers.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        headers.extend_from_slice(&TEXT_RVA.to_le_bytes());
+        headers.extend_from_slice(&text_size.to_le_bytes());
+        headers.extend_from_slice(&HEADERS_SIZE.to_le_bytes());
+        headers.extend_from_slice(&[0; 12]);
+        headers.extend_from_slice(&0x6000_0020u32.to_le_bytes());
+
+        image[0x40..0x40 + headers.len()].copy_from_slice(&headers);
+
+        image.extend_from_slice(text);
+        image.resize((HEADERS_SIZE + text_size) as usize, 0xCC);
+
+        image
+    }
+
+    fn status(
+        pattern: &Pattern,
+        pattern_counts: &[(&Pattern, Result<usize, String>)],
+    ) -> (String, bool) {
+        let (_, count) = pattern_counts
+            .iter()
+            .find(|(other, _)| *other == pattern)
+            .unwrap();
+
+        pattern_status(pattern, count, pattern_counts)
+    }
+
+    #[test]
+    fn fallback_matches_are_enough() {
+        let mut text = vec![0xCC; 0x10];
+        text.extend_from_slice(MOUNT_EBL_THUNK);
+
+        let image = pe_with_text(&text);
+        let file = PeFile::from_bytes(&image).unwrap();
+
+        let pattern_counts = [MOUNT_EBL, MOUNT_EBL_ALT]
+            .iter()
+            .map(|pattern| (pattern, count_matches(file, pattern)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(pattern_counts[0].1, Ok(0));
+        assert_eq!(pattern_counts[1].1, Ok(1));
+
+        assert_eq!(
+            status(&MOUNT_EBL, &pattern_counts),
+            (
+                "No matches, but MountEbl (fallback) matched".to_owned(),
+                false
+            )
+        );
+        assert_eq!(
+            status(&MOUNT_EBL_ALT, &pattern_counts),
+            ("1 match".to_owned(), false)
+        );
+    }
+
+    #[test]
+    fn missing_alternatives_are_problems() {
+        let image = pe_with_text(&[0xCC; 0x10]);
+        let file = PeFile::from_bytes(&image).unwrap();
+
+        let pattern_counts = [MOUNT_EBL, MOUNT_EBL_ALT]
+            .iter()
+            .map(|pattern| (pattern, count_matches(file, pattern)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            status(&MOUNT_EBL, &pattern_counts),
+            ("No matches".to_owned(), true)
+        );
+        assert_eq!(
+            status(&MOUNT_EBL_ALT, &pattern_counts),
+            ("No matches".to_owned(), true)
+        );
+
+        let missing_section = Pattern {
+            section: Some(".rdata"),
+            ..MOUNT_EBL
+        };
+
+        assert_eq!(
+            count_matches(file, &missing_section),
+            Err("Section .rdata is missing".to_owned())
+        );
+    }
+
+    #[test]
+    fn known_broken_patterns_are_not_problems() {
+        let image = pe_with_text(&[0xCC; 0x10]);
+        let file = PeFile::from_bytes(&image).unwrap();
+
+        let count = count_matches(file, &REGULATION_WRITE_CALL);
+        let pattern_counts = [(&REGULATION_WRITE_CALL, count)];
+
+        assert_eq!(
+            status(&REGULATION_WRITE_CALL, &pattern_counts),
+            (
+                "No matches, known broken: its flags group is misspelled, so it never matches"
+                    .to_owned(),
+                false
+            )
+        );
+    }
+}
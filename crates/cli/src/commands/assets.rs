@@ -0,0 +1,277 @@
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, OptionExt};
+use me3_mod_host_assets::{
+    bhd5::{Bhd5Format, CachedArchives},
+    package_files::package_files,
+};
+
+use crate::{
+    commands::profile::installed_game_dir, config::Config, db::DbContext, output::OutputBuilder,
+    Game,
+};
+
+#[derive(Subcommand, Debug)]
+#[command(flatten_help = true)]
+pub enum AssetsCommands {
+    /// List the files in a game's archives, using the archive headers cached by boot boost.
+    Ls(AssetsLsArgs),
+
+    /// Check whether a file is in a game's archives.
+    Find(AssetsFindArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AssetsLsArgs {
+    #[arg(value_enum)]
+    game: Game,
+
+    /// Only list files whose path starts with this prefix (e.g. "chr/c2010").
+    prefix: Option<String>,
+
+    /// List of archive paths, one per line, to name files by.
+    ///
+    /// Archives only store hashes of their paths, so files are only counted without one.
+    #[clap(long, value_hint = clap::ValueHint::FilePath)]
+    names: Option<PathBuf>,
+
+    #[clap(flatten)]
+    packages: PackageCheckArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct AssetsFindArgs {
+    /// Path of the file, as packages override it (e.g. "chr/c0000.anibnd.dcx").
+    path: String,
+
+    /// Game to search, if the profile given with --profile doesn't support one.
+    #[clap(short, long, value_enum)]
+    game: Option<Game>,
+
+    #[clap(flatten)]
+    packages: PackageCheckArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct PackageCheckArgs {
+    /// Also flag files of this profile's packages that aren't in the game's archives, which
+    /// usually means their path has a typo.
+    #[clap(short, long)]
+    profile: Option<String>,
+}
+
+/// The archives of an installed game, with the headers boot boost cached for them.
+struct GameArchives {
+    game_dir: PathBuf,
+    archives: CachedArchives,
+}
+
+impl GameArchives {
+    fn load(config: &Config, game: Game) -> color_eyre::Result<Self> {
+        let game_dir = installed_game_dir(config, game)
+            .ok_or_else(|| eyre!("{} was not found in any Steam library", game.0.name()))?;
+
+        let cache_dir = config
+            .cache_dir()
+            .ok_or_eyre("no cache directory, archive headers can't be cached")?;
+
+        let archives = CachedArchives::load(&game_dir, &cache_dir, Bhd5Format::for_game(game.0))
+            .map_err(|e| eyre!("failed to read {}: {e}", game_dir.display()))?;
+
+        if archives.archives.is_empty() {
+            return Err(eyre!(
+                "no archive headers are cached for {name}, run `me3 cache warm {name}` or launch it \
+                 once with boot boost enabled",
+                name = game.0.name()
+            ));
+        }
+
+        Ok(Self { game_dir, archives })
+    }
+
+    /// The archive `path` is in, or "game directory" if it's loose in the game directory.
+    fn find(&self, path: &str) -> Option<&str> {
+        if self.game_dir.join(path).is_file() {
+            return Some("game directory");
+        }
+
+        self.archives
+            .find(path)
+            .map(|(archive, _)| archive.name.as_str())
+    }
+
+    fn properties(&self, builder: &mut OutputBuilder) {
+        builder.property("Game directory", self.game_dir.display());
+
+        for archive in &self.archives.archives {
+            builder.property(
+                &archive.name,
+                format!("{} files", archive.bhd5.files().count()),
+            );
+        }
+
+        for name in &self.archives.uncached {
+            builder.property(name, "Not cached, launch the game with boot boost enabled");
+        }
+    }
+}
+
+impl PackageCheckArgs {
+    /// Adds a section listing package files that aren't in the game's archives.
+    fn check(
+        &self,
+        db: &DbContext,
+        config: &Config,
+        archives: &GameArchives,
+        output: &mut OutputBuilder,
+    ) -> color_eyre::Result<()> {
+        let Some(profile) = &self.profile else {
+            return Ok(());
+        };
+
+        let profile = db.profiles.load(config.resolve_profile(profile)?)?;
+        let (_, _, packages) = profile.compile()?;
+
+        let mut missing = Vec::new();
+
+        for package in &packages {
+            for (vfs_path, _) in package_files(package)? {
+                if !is_patch(&vfs_path) && archives.find(&vfs_path).is_none() {
+                    missing.push((vfs_path, package.id()));
+                }
+            }
+        }
+
+        output.section("Package files not in the game's archives", |builder| {
+            if missing.is_empty() {
+                builder.property("Status", "None");
+            }
+
+            for (vfs_path, package) in &missing {
+                builder.property(vfs_path, package);
+            }
+        });
+
+        Ok(())
+    }
+
+    fn game(&self, db: &DbContext, config: &Config) -> color_eyre::Result<Option<Game>> {
+        let Some(profile) = &self.profile else {
+            return Ok(None);
+        };
+
+        let profile = db.profiles.load(config.resolve_profile(profile)?)?;
+        Ok(profile.supported_game().map(Game))
+    }
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn ls(db: DbContext, config: Config, args: AssetsLsArgs) -> color_eyre::Result<()> {
+    let archives = GameArchives::load(&config, args.game)?;
+
+    let mut output = OutputBuilder::new("Game archives");
+    archives.properties(&mut output);
+
+    if let Some(names_path) = &args.names {
+        let names = fs::read_to_string(names_path)
+            .map_err(|e| eyre!("failed to read {}: {e}", names_path.display()))?;
+
+        let prefix = args.prefix.as_deref().map(normalize).unwrap_or_default();
+
+        let files = names
+            .lines()
+            .map(normalize)
+            .filter(|path| !path.is_empty() && path.starts_with(&prefix))
+            .collect::<BTreeSet<_>>();
+
+        output.section("Files", |builder| {
+            for path in &files {
+                if let Some(archive) = archives.find(path) {
+                    builder.property(path, archive);
+                }
+            }
+        });
+    } else if args.prefix.is_some() {
+        output.property(
+            "Note",
+            "Archives only store path hashes, pass --names to list files by path",
+        );
+    }
+
+    args.packages.check(&db, &config, &archives, &mut output)?;
+
+    println!("{}", output.build());
+
+    Ok(())
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn find(db: DbContext, config: Config, args: AssetsFindArgs) -> color_eyre::Result<()> {
+    let game = match args.game {
+        Some(game) => game,
+        None => args
+            .packages
+            .game(&db, &config)?
+            .ok_or_eyre("no game given, pass --game or a profile that supports one")?,
+    };
+
+    let archives = GameArchives::load(&config, game)?;
+    let path = normalize(&args.path);
+
+    let mut output = OutputBuilder::new("Game archives");
+    archives.properties(&mut output);
+
+    let found = archives.find(&path);
+
+    output.section(&path, |builder| match found {
+        Some(archive) => builder.property("Found in", archive),
+        None => builder.property("Found in", "Not found in the game's archives"),
+    });
+
+    args.packages.check(&db, &config, &archives, &mut output)?;
+
+    println!("{}", output.build());
+
+    if found.is_none() {
+        return Err(eyre!("{path} is not in the game's archives"));
+    }
+
+    Ok(())
+}
+
+/// Lowercase, with `/` separators and without a leading `/`, like package file paths.
+fn normalize(path: &str) -> String {
+    path.trim()
+        .replace('\\', "/")
+        .trim_start_matches('/')
+        .to_lowercase()
+}
+
+/// Text and WEM patches are merged into binders and soundbanks rather than overriding a file of
+/// their own.
+fn is_patch(vfs_path: &str) -> bool {
+    vfs_path.ends_with(".msgbnd.json")
+        || vfs_path.ends_with(".fmg.xml")
+        || (vfs_path.ends_with(".wem") && vfs_path.contains(".bnk/"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_patch, normalize};
+
+    #[test]
+    fn paths_are_normalized_like_package_files() {
+        assert_eq!(normalize("/Chr\\c0000.anibnd.dcx "), "chr/c0000.anibnd.dcx");
+        assert_eq!(normalize("regulation.bin"), "regulation.bin");
+    }
+
+    #[test]
+    fn patches_are_not_flagged() {
+        assert!(is_patch("msg/engus/item_dlc02.msgbnd.json"));
+        assert!(is_patch("msg/engus/item_dlc02.msgbnd/weaponname.fmg.xml"));
+        assert!(is_patch("sd/enus/cs_main.bnk/12345.wem"));
+        assert!(!is_patch("msg/engus/item_dlc02.msgbnd.dcx"));
+        assert!(!is_patch("sd/wem/12/12345.wem"));
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    fs::{self, File},
+    io::{self, IsTerminal, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use clap::{ArgAction, Args};
+use color_eyre::eyre::bail;
+use me3_mod_protocol::{
+    native::Native,
+    package::{Package, WithPackageSource},
+};
+use sha2::{Digest, Sha256};
+use tracing::info;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    config::Config,
+    db::{profile::Profile, DbContext},
+    output::OutputBuilder,
+    Game,
+};
+
+mod redact;
+
+pub use self::redact::Redactor;
+
+#[derive(Args, Debug)]
+pub struct BugReportArgs {
+    /// Name of the profile (or path to it) the problem occurred with.
+    #[clap(short, long)]
+    profile: Option<String>,
+
+    /// Game the problem occurred in, if the profile doesn't specify one.
+    #[clap(short, long, hide_possible_values = false)]
+    #[arg(value_enum)]
+    game: Option<Game>,
+
+    /// Number of recent log files of the profile to include.
+    #[clap(long, default_value_t = 1)]
+    logs: usize,
+
+    /// Path to write the report to [default: me3-bug-report-<time>.zip]
+    #[clap(short, long, value_hint = clap::ValueHint::FilePath)]
+    output: Option<PathBuf>,
+
+    /// Show the redacted contents of the report and ask before writing it.
+    #[clap(long, action = ArgAction::SetTrue)]
+    review: bool,
+}
+
+/// Files making up a bug report, without any mod content.
+#[derive(Debug, Default)]
+pub struct BugReport {
+    files: Vec<(String, String)>,
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn bug_report(db: DbContext, config: Config, args: BugReportArgs) -> color_eyre::Result<()> {
+    let profile = args
+        .profile
+        .as_deref()
+        .map(|name| db.profiles.load(config.resolve_profile(name)?))
+        .transpose()?;
+
+    let game = args
+        .game
+        .or_else(|| profile.as_ref()?.supported_game().map(Game));
+
+    let mut report = BugReport::default();
+
+    report.add("environment.txt", environment(&config, game));
+
+    for (index, path) in config
+        .known_dirs
+        .config_dirs()
+        .map(|dir| dir.join("me3.toml"))
+        .enumerate()
+    {
+        if let Ok(contents) = fs::read_to_string(&path) {
+            report.add(
+                format!("config/{index}-me3.toml"),
+                format!("# {}\n{contents}", path.display()),
+            );
+        }
+    }
+
+    if let Some(profile) = &profile {
+        report.add_profile(profile);
+
+        for log in db.logs.recent(profile.name(), args.logs) {
+            for path in [log.to_path_buf(), log.with_extension("overflow.log")] {
+                if let (Ok(contents), Some(name)) = (fs::read(&path), path.file_name()) {
+                    report.add(
+                        format!("logs/{}", name.to_string_lossy()),
+                        String::from_utf8_lossy(&contents),
+                    );
+                }
+            }
+        }
+    }
+
+    let report = report.redact(&Redactor::from_env());
+
+    let output = args.output.unwrap_or_else(|| {
+        let now = Local::now().format("%Y-%m-%d_%H-%M-%S");
+        PathBuf::from(format!("me3-bug-report-{now}.zip"))
+    });
+
+    if args.review && !report.review(&output)? {
+        info!("bug report discarded");
+        return Ok(());
+    }
+
+    report.write_zip(File::create(&output)?)?;
+
+    println!(
+        "Wrote bug report to {}, please check it before sharing it.",
+        output.display()
+    );
+
+    Ok(())
+}
+
+impl BugReport {
+    pub fn add(&mut self, name: impl Into<String>, contents: impl Into<String>) {
+        self.files.push((name.into(), contents.into()));
+    }
+
+    /// Adds the profile, its compiled load order and a listing of the files it loads.
+    fn add_profile(&mut self, profile: &Profile) {
+        let contents = fs::read_to_string(profile.path())
+            .unwrap_or_else(|e| format!("failed to read {}: {e}", profile.path().display()));
+
+        self.add(format!("profile/{}.me3", profile.name()), contents);
+
+        match profile.compile() {
+            Ok((natives, early_natives, packages)) => {
+                self.add(
+                    "profile/load-order.txt",
+                    load_order(&natives, &early_natives, &packages),
+                );
+                self.add(
+                    "profile/files.txt",
+                    file_listing(early_natives.iter().chain(&natives), &packages),
+                );
+            }
+            Err(e) => self.add(
+                "profile/load-order.txt",
+                format!("failed to compile load order: {e:?}"),
+            ),
+        }
+    }
+
+    /// Applies `redactor` to the names and contents of all files.
+    pub fn redact(self, redactor: &Redactor) -> Self {
+        let files = self
+            .files
+            .into_iter()
+            .map(|(name, contents)| (redactor.redact(&name), redactor.redact(&contents)))
+            .collect();
+
+        Self { files }
+    }
+
+    /// Prints the report and asks whether to write it to `output`.
+    fn review(&self, output: &Path) -> color_eyre::Result<bool> {
+        if !io::stdin().is_terminal() {
+            bail!("--review needs an interactive terminal");
+        }
+
+        for (name, contents) in &self.files {
+            println!("{}", OutputBuilder::new(name).build());
+            println!("{contents}");
+        }
+
+        print!("Write bug report to {}? [y/N] ", output.display());
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+    }
+
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> color_eyre::Result<()> {
+        let mut zip = ZipWriter::new(writer);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        for (name, contents) in &self.files {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(contents.as_bytes())?;
+        }
+
+        zip.finish()?;
+
+        Ok(())
+    }
+}
+
+fn environment(config: &Config, game: Option<Game>) -> String {
+    let format_path = |path: Option<Box<Path>>| match path {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => "<none>".to_owned(),
+    };
+
+    let mut output = OutputBuilder::new("me3");
+
+    output.property("Version", env!("CARGO_PKG_VERSION"));
+    output.property(
+        "Commit",
+        option_env!("BUILD_COMMIT_ID").unwrap_or("unknown"),
+    );
+    output.property(
+        "Platform",
+        format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+    );
+    output.property("Profile directory", format_path(config.profile_dir()));
+    output.property("Logs directory", format_path(config.log_dir()));
+    output.property("Cache directory", format_path(config.cache_dir()));
+    output.property(
+        "Windows binaries",
+        format_path(config.windows_binaries_dir()),
+    );
+
+    let steam = config.steam_dir();
+
+    output.section("Steam", |builder| match &steam {
+        Ok(steam) => builder.property("Path", steam.path().to_string_lossy()),
+        Err(e) => builder.property("Error", e),
+    });
+
+    if let (Some(game), Ok(steam)) = (game, &steam) {
+        output.section(game.0.title(), |builder| {
+            builder.property("App ID", game.app_id());
+
+            match steam.find_app(game.app_id()) {
+                Ok(Some((app, library))) => {
+                    builder.property("Install directory", library.resolve_app_dir(&app).display());
+                    builder.property(
+                        "Build ID",
+                        app.build_id
+                            .map_or_else(|| "unknown".to_owned(), |id| id.to_string()),
+                    );
+                }
+                Ok(None) => builder.property("Status", "Not installed"),
+                Err(e) => builder.property("Error", e),
+            }
+
+            #[cfg(target_os = "linux")]
+            compat_tool(builder, steam, game);
+        });
+    }
+
+    output.build()
+}
+
+/// Describes the Proton version the game is set to run with.
+#[cfg(target_os = "linux")]
+fn compat_tool(builder: &mut OutputBuilder, steam: &steamlocate::SteamDir, game: Game) {
+    use crate::commands::launch::strategy::compat_tool::CompatTools;
+
+    let name = steam.compat_tool_mapping().ok().and_then(|mapping| {
+        mapping
+            .get(&game.app_id())
+            .or_else(|| mapping.get(&0))
+            .and_then(|tool| tool.name.clone())
+    });
+
+    let Some(name) = name else {
+        builder.property("Compatibility tool", "<default>");
+        return;
+    };
+
+    let version = CompatTools::new(steam.clone())
+        .find(&name)
+        .and_then(|tool| fs::read_to_string(tool.install_path.join("version")).ok());
+
+    builder.property(
+        "Compatibility tool",
+        match version {
+            Some(version) => format!("{name} ({})", version.trim()),
+            None => name,
+        },
+    );
+}
+
+fn load_order(natives: &[Native], early_natives: &[Native], packages: &[Package]) -> String {
+    let mut output = String::new();
+
+    for (heading, natives) in [("Early natives", early_natives), ("Natives", natives)] {
+        output += &format!("{heading}:\n");
+
+        for native in natives {
+            let optional = if native.optional { " (optional)" } else { "" };
+            output += &format!("  {}{optional}\n", native.source().display());
+        }
+    }
+
+    output += "Packages:\n";
+
+    for package in packages {
+        output += &format!("  {}\n", package.source().display());
+    }
+
+    output
+}
+
+/// Lists the hashes of natives and the files contained in packages.
+fn file_listing<'a>(natives: impl Iterator<Item = &'a Native>, packages: &[Package]) -> String {
+    let mut output = String::from("Natives:\n");
+
+    for native in natives {
+        let path: &Path = native.source();
+
+        let hash = File::open(path).and_then(|mut file| {
+            let mut hasher = Sha256::new();
+            io::copy(&mut file, &mut hasher)?;
+            Ok(hasher.finalize())
+        });
+
+        match hash {
+            Ok(hash) => output += &format!("  {} sha256:{hash:x}\n", path.display()),
+            Err(e) => output += &format!("  {} ({e})\n", path.display()),
+        }
+    }
+
+    for package in packages {
+        let root: &Path = package.source();
+
+        output += &format!("Package {}:\n", root.display());
+
+        let mut files = Vec::new();
+        list_files(root, &mut files);
+        files.sort();
+
+        for (path, size) in files {
+            let path = path.strip_prefix(root).unwrap_or(&path);
+            output += &format!("  {} ({size} bytes)\n", path.display());
+        }
+    }
+
+    output
+}
+
+fn list_files(dir: &Path, files: &mut Vec<(PathBuf, u64)>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            list_files(&entry.path(), files);
+        } else {
+            files.push((entry.path(), metadata.len()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Read},
+        path::Path,
+    };
+
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use me3_mod_protocol::package::Package;
+
+    use super::{file_listing, BugReport, Redactor};
+
+    #[test]
+    fn report_is_redacted_and_zipped() -> color_eyre::Result<()> {
+        let mut report = BugReport::default();
+        report.add("logs/alice.log", "loaded /home/alice/mods/a.dll");
+
+        let redactor = Redactor::new(Some(Path::new("/home/alice")), Some("alice"));
+        let report = report.redact(&redactor);
+
+        let mut buf = Cursor::new(Vec::new());
+        report.write_zip(&mut buf)?;
+
+        let mut zip = zip::ZipArchive::new(buf)?;
+        let mut contents = String::new();
+        zip.by_name("logs/alice.log")?
+            .read_to_string(&mut contents)?;
+
+        assert_eq!(contents, "loaded ~/mods/a.dll");
+
+        Ok(())
+    }
+
+    #[test]
+    fn packages_are_listed_without_contents() -> color_eyre::Result<()> {
+        let temp_dir = assert_fs::TempDir::new()?;
+        temp_dir.child("mod/regulation.bin").write_str("secret")?;
+
+        let listing = file_listing(
+            std::iter::empty(),
+            &[Package::new(temp_dir.child("mod").to_path_buf())],
+        );
+
+        assert!(listing.contains("regulation.bin (6 bytes)"));
+        assert!(!listing.contains("secret"));
+
+        Ok(())
+    }
+}
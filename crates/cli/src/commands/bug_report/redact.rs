@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use directories::BaseDirs;
+
+/// Scrubs the user's home directory and user name out of text that's about to be shared.
+#[derive(Debug, Default)]
+pub struct Redactor {
+    replacements: Vec<(String, String)>,
+}
+
+impl Redactor {
+    /// Redacts the home directory and user name of the current user.
+    pub fn from_env() -> Self {
+        let home = BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok()
+            .or_else(|| {
+                home.as_ref()
+                    .and_then(|home| home.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+            });
+
+        Self::new(home.as_deref(), user.as_deref())
+    }
+
+    pub fn new(home: Option<&Path>, user: Option<&str>) -> Self {
+        let mut replacements = Vec::new();
+
+        if let Some(home) = home.map(|home| home.to_string_lossy()) {
+            let home = home.trim_end_matches(['/', '\\']);
+
+            if !home.is_empty() {
+                let backslashed = home.replace('/', "\\");
+
+                // Paths show up with either separator (Proton maps the home directory into the
+                // Windows filesystem), and with escaped backslashes in JSON or debug output.
+                replacements.push((backslashed.replace('\\', "\\\\"), "~".to_owned()));
+                replacements.push((backslashed, "~".to_owned()));
+                replacements.push((home.replace('\\', "/"), "~".to_owned()));
+            }
+        }
+
+        if let Some(user) = user.filter(|user| !user.is_empty()) {
+            // Only redact the user name as a whole path component, it may well be a common word.
+            for sep in ["/", "\\"] {
+                replacements.push((format!("{sep}{user}{sep}"), format!("{sep}<user>{sep}")));
+            }
+        }
+
+        Self { replacements }
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(text.to_owned(), |text, (from, to)| {
+                replace_ignore_ascii_case(&text, from, to)
+            })
+    }
+}
+
+/// Windows paths are case insensitive, so the same directory may be spelled differently.
+fn replace_ignore_ascii_case(text: &str, from: &str, to: &str) -> String {
+    // Lowercasing ASCII characters doesn't change any byte offsets.
+    let haystack = text.to_ascii_lowercase();
+    let needle = from.to_ascii_lowercase();
+
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for (start, _) in haystack.match_indices(&needle) {
+        output.push_str(&text[last..start]);
+        output.push_str(to);
+        last = start + needle.len();
+    }
+
+    output.push_str(&text[last..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Redactor;
+
+    #[test]
+    fn redacts_home_directory() {
+        let redactor = Redactor::new(Some(Path::new("/home/alice")), Some("alice"));
+
+        assert_eq!(
+            redactor.redact("loaded /home/alice/mods/a.dll"),
+            "loaded ~/mods/a.dll"
+        );
+        assert_eq!(
+            redactor.redact(r"loaded Z:\home\alice\mods\a.dll"),
+            r"loaded Z:~\mods\a.dll"
+        );
+    }
+
+    #[test]
+    fn redacts_windows_home_directory_in_any_case() {
+        let redactor = Redactor::new(Some(Path::new(r"C:\Users\Alice")), Some("Alice"));
+
+        assert_eq!(redactor.redact(r"c:\users\alice\mods"), r"~\mods");
+        assert_eq!(
+            redactor.redact(r#"{"path":"C:\\Users\\Alice\\mods"}"#),
+            r#"{"path":"~\\mods"}"#
+        );
+    }
+
+    #[test]
+    fn redacts_user_name_in_other_paths() {
+        let redactor = Redactor::new(Some(Path::new("/home/alice")), Some("alice"));
+
+        assert_eq!(
+            redactor.redact("/mnt/alice/games and alice herself"),
+            "/mnt/<user>/games and alice herself"
+        );
+    }
+}
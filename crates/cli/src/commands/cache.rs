@@ -0,0 +1,101 @@
+use std::{fs, path::PathBuf};
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, OptionExt};
+use me3_mod_host_assets::bhd5::{
+    rsa::{find_pem_keys, RsaPublicKey},
+    warm_cache, WarmStatus,
+};
+
+use crate::{commands::profile::installed_game_dir, config::Config, output::OutputBuilder, Game};
+
+#[derive(Subcommand, Debug)]
+#[command(flatten_help = true)]
+pub enum CacheCommands {
+    /// Decrypt a game's archive headers into the boot boost cache, so the first launch after
+    /// installing or updating the game doesn't have to.
+    Warm(CacheWarmArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CacheWarmArgs {
+    #[arg(value_enum)]
+    game: Game,
+
+    /// RSA public key, in PEM format, to decrypt archive headers with if the keys embedded in the
+    /// game's executable don't decrypt them. Can be given more than once.
+    #[clap(long = "key", value_hint = clap::ValueHint::FilePath)]
+    keys: Vec<PathBuf>,
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn warm(config: Config, args: CacheWarmArgs) -> color_eyre::Result<()> {
+    let game = args.game;
+
+    let game_dir = installed_game_dir(&config, game)
+        .ok_or_else(|| eyre!("{} was not found in any Steam library", game.0.name()))?;
+
+    let cache_dir = config
+        .cache_dir()
+        .ok_or_eyre("no cache directory, archive headers can't be cached")?;
+
+    fs::create_dir_all(&cache_dir)
+        .map_err(|e| eyre!("failed to create {}: {e}", cache_dir.display()))?;
+
+    let mut keys = Vec::new();
+
+    for path in &args.keys {
+        let pem = fs::read_to_string(path)
+            .map_err(|e| eyre!("failed to read {}: {e}", path.display()))?;
+
+        let key = RsaPublicKey::from_pem(&pem)
+            .map_err(|e| eyre!("failed to read {}: {e}", path.display()))?;
+
+        keys.push(key);
+    }
+
+    let exe_path = game_dir.join(game.launcher().file_name().unwrap_or_default());
+
+    let exe =
+        fs::read(&exe_path).map_err(|e| eyre!("failed to read {}: {e}", exe_path.display()))?;
+
+    keys.extend(
+        find_pem_keys(&exe)
+            .into_iter()
+            .filter_map(|pem| RsaPublicKey::from_pem(pem).ok()),
+    );
+
+    let statuses = warm_cache(&game_dir, &cache_dir, &keys)
+        .map_err(|e| eyre!("failed to cache archive headers: {e}"))?;
+
+    let mut output = OutputBuilder::new("Archive header cache");
+    output.property("Game directory", game_dir.display());
+    output.property("Cache directory", cache_dir.display());
+    output.property("Keys", keys.len());
+
+    output.section("Archives", |builder| {
+        for (name, status) in &statuses {
+            let status = match status {
+                WarmStatus::Cached => "Already cached".to_owned(),
+                WarmStatus::Decrypted => "Decrypted and cached".to_owned(),
+                WarmStatus::NoKey => "No key decrypts this header, pass one with --key".to_owned(),
+                WarmStatus::Failed(e) => format!("Failed to decrypt: {e}"),
+            };
+
+            builder.property(name, status);
+        }
+    });
+
+    println!("{}", output.build());
+
+    let undecrypted = statuses
+        .iter()
+        .filter(|(_, status)| matches!(status, WarmStatus::NoKey | WarmStatus::Failed(_)))
+        .count();
+
+    if undecrypted > 0 {
+        return Err(eyre!("{undecrypted} archive headers couldn't be decrypted"));
+    }
+
+    Ok(())
+}
@@ -1,11 +1,13 @@
+pub mod control;
 mod named_pipe;
+mod session;
 pub mod steam;
 pub mod strategy;
 
 use std::{
     fmt::Debug,
-    fs::File,
-    io::{BufRead, BufReader},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
     process::Stdio,
     sync::{
@@ -20,24 +22,28 @@ use clap::{
 };
 use color_eyre::eyre::{bail, eyre, OptionExt};
 use me3_env::{CommandExt, LauncherVars, TelemetryVars};
-use me3_launcher_attach_protocol::AttachConfig;
+use me3_launcher_attach_protocol::{event::HostEvent, AttachConfig};
+use me3_mod_host_assets::scan_index::{self, ScanIndex};
 use me3_mod_protocol::{native::Native, package::Package};
 use normpath::PathExt;
 use serde::{Deserialize, Serialize};
 use steamlocate::{Library, SteamDir};
 use tempfile::NamedTempFile;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     commands::{
         launch::{
+            control::ControlPipe,
             named_pipe::NamedPipe,
+            session::SessionSummary,
             strategy::{compat_tool::CompatTools, LaunchStrategy},
         },
         profile::ProfileOptions,
     },
     config::Config,
     db::{profile::Profile, DbContext},
+    native_check::check_natives,
     Game,
 };
 
@@ -139,10 +145,21 @@ pub struct LaunchArgs {
     #[clap(short('d'), long("diagnostics"), action = ArgAction::SetTrue)]
     diagnostics: bool,
 
+    /// Log filter directives for the launcher and mod host (e.g. "info,me3_mod_host=debug").
+    ///
+    /// While the game is running, a new filter can be typed into the terminal. An empty line
+    /// restores this one.
+    #[clap(long("log-filter"))]
+    log_filter: Option<String>,
+
     /// Suspend the game until a debugger is attached.
     #[clap(long("suspend"), action = ArgAction::SetTrue)]
     suspend: bool,
 
+    /// Record every asset the game requests and the files served instead, for `me3 trace`.
+    #[clap(long("trace-assets"), action = ArgAction::SetTrue)]
+    trace_assets: bool,
+
     /// Name of a profile in the me3 profile dir, or path to a ModProfile (TOML or JSON).
     #[arg(
             short('p'),
@@ -308,6 +325,7 @@ impl LaunchArgs {
             disable_arxan: profile_options.disable_arxan.unwrap_or(false),
             mem_patch: !profile_options.no_mem_patch.unwrap_or(false),
             skip_steam_init: opts.skip_steam_init.unwrap_or(false),
+            shadow_copy_natives: profile_options.shadow_copy_natives.unwrap_or(false),
         })
     }
 }
@@ -415,7 +433,7 @@ pub fn launch(
         profile,
         game_options,
         profile_options: _profile_options,
-        attach_config,
+        mut attach_config,
     } = args.parse_with_context(&db, &config)?;
 
     let bins_dir = config
@@ -456,9 +474,20 @@ pub fn launch(
             })
         })?;
 
+    check_natives_before_launch(&attach_config, game_executable.as_ref().parent())?;
+    build_scan_indexes(&attach_config);
+
     let launch_strategy = create_launch_strategy(&config, &game, &game_executable, &attach_config)?;
     let mut injector_command = launch_strategy.build_command(&launcher_path, vec![])?;
 
+    let log_file_path = db.logs.create_log_file(profile.name())?;
+
+    info!(?log_file_path, "created log file");
+
+    if args.trace_assets {
+        attach_config.asset_trace = Some(log_file_path.with_extension("trace"));
+    }
+
     let attach_config_dir = config.cache_dir().unwrap_or(Box::from(Path::new(".")));
     std::fs::create_dir_all(&attach_config_dir)?;
     let attach_config_file = NamedTempFile::new_in(&attach_config_dir)?;
@@ -469,9 +498,8 @@ pub fn launch(
     let mut monitor_pipe = NamedPipe::create()?;
     info!(path = ?monitor_pipe.path(), "monitor pipe created");
 
-    let log_file_path = db.logs.create_log_file(profile.name())?;
-
-    info!(?log_file_path, "created log file");
+    let mut control_pipe = NamedPipe::create_outbound()?;
+    info!(path = ?control_pipe.path(), "control pipe created");
 
     let _ = std::fs::copy(tmp_log_file_path, &log_file_path);
     let launcher_vars = LauncherVars {
@@ -486,11 +514,22 @@ pub fn launch(
     #[cfg(not(target_os = "windows"))]
     let monitor_pipe_path = monitor_pipe.path().normalize()?.into_path_buf();
 
+    #[cfg(target_os = "windows")]
+    let control_pipe_path = control_pipe.path().normalize_virtually()?.into_path_buf();
+
+    #[cfg(not(target_os = "windows"))]
+    let control_pipe_path = control_pipe.path().normalize()?.into_path_buf();
+
+    let log_filter = args.log_filter.clone().or_else(|| profile.log_filter());
+
     let telemetry_vars = TelemetryVars {
         enabled: config.options.crash_reporting.unwrap_or_default(),
         log_file_path: log_file_path.normalize()?.into_path_buf(),
         monitor_pipe_path,
         trace_id: me3_telemetry::trace_id(),
+        log_format: config.options.log_format.unwrap_or_default(),
+        log_filter: log_filter.clone(),
+        control_pipe_path: Some(control_pipe_path),
     };
 
     injector_command
@@ -513,6 +552,35 @@ pub fn launch(
 
     let monitor_thread_running = running.clone();
 
+    let session_file = config
+        .cache_dir()
+        .map(|cache_dir| control::session_file(&cache_dir, profile.name()));
+
+    std::thread::spawn({
+        let session_file = session_file.clone();
+
+        move || {
+            control_pipe.disable_cleanup(true);
+
+            let Ok(control_pipe) = control_pipe.into_file().open() else {
+                return;
+            };
+
+            let control_pipe = Arc::new(ControlPipe::new(control_pipe));
+
+            if let Some(session_file) = session_file {
+                let control_pipe = control_pipe.clone();
+                std::thread::spawn(move || {
+                    control::forward_session_commands(&control_pipe, &session_file)
+                });
+            }
+
+            control::forward_log_filters(&control_pipe, log_filter.unwrap_or_default());
+        }
+    });
+
+    let mut event_log = OpenOptions::new().append(true).open(&log_file_path)?;
+
     let monitor_thread = std::thread::spawn(move || {
         monitor_pipe.disable_cleanup(true);
 
@@ -524,6 +592,7 @@ pub fn launch(
         let mut reader = BufReader::new(monitor_pipe);
 
         let mut exit_code = None;
+        let mut summary = SessionSummary::default();
 
         while monitor_thread_running.load(Ordering::Relaxed) {
             exit_code = exit_code.or_else(|| {
@@ -540,7 +609,18 @@ pub fn launch(
                 error!(%error, "couldn't read log line from game");
             }
 
-            if !line.is_empty() {
+            if let Some(event) = HostEvent::from_monitor_line(&line) {
+                match event {
+                    Ok(event) => {
+                        let _ = writeln!(event_log, "event: {event}");
+
+                        if let Some(progress) = summary.record(&event) {
+                            eprintln!("{progress}");
+                        }
+                    }
+                    Err(error) => warn!(%error, "received malformed event from game"),
+                }
+            } else if !line.is_empty() {
                 eprint!("{line}");
             } else if exit_code.is_some() {
                 break;
@@ -548,21 +628,92 @@ pub fn launch(
         }
 
         let _ = launcher_proc.kill();
+
+        summary
     });
 
     ctrlc::set_handler(move || {
         running.store(false, Ordering::Relaxed);
     })?;
 
-    let _ = monitor_thread.join();
+    let summary = monitor_thread
+        .join()
+        .map_err(|_| eyre!("monitor thread panicked"))?;
+
+    if let Some(session_file) = session_file {
+        let _ = std::fs::remove_file(session_file);
+    }
+
+    eprint!("{}", summary.render());
+
+    if let Some(asset_trace) = &attach_config.asset_trace
+        && asset_trace.is_file()
+    {
+        eprintln!(
+            "Asset trace written to {}, query it with `me3 trace`",
+            asset_trace.display()
+        );
+    }
 
     if args.diagnostics {
         open::that_detached(&*log_file_path)?;
     }
 
+    summary.result()
+}
+
+/// Reports problems with natives that would otherwise only show up once the game loads them,
+/// refusing to launch if a required native can't be loaded.
+fn check_natives_before_launch(
+    attach_config: &AttachConfig,
+    game_dir: Option<&Path>,
+) -> color_eyre::Result<()> {
+    let natives = attach_config
+        .early_natives
+        .iter()
+        .chain(&attach_config.natives);
+
+    let mut fatal = 0;
+
+    for report in check_natives(natives, game_dir) {
+        let path = report.native.path.display();
+
+        for problem in &report.problems {
+            if problem.is_fatal() && !report.native.optional {
+                error!(%path, %problem, "native can't be loaded");
+                fatal += 1;
+            } else {
+                warn!(%path, %problem, "native may not load");
+            }
+        }
+    }
+
+    if fatal > 0 {
+        bail!("found {fatal} problems with required natives, see `me3 profile check` for details");
+    }
+
     Ok(())
 }
 
+/// Builds the scan indexes of packages that changed since the last launch, so the game only has
+/// to load them.
+fn build_scan_indexes(attach_config: &AttachConfig) {
+    let Some(cache_path) = &attach_config.cache_path else {
+        return;
+    };
+
+    let index_dir = cache_path.join(scan_index::INDEX_DIR);
+
+    for package in &attach_config.packages {
+        let index_path = index_dir.join(scan_index::index_file_name(package));
+        let source_dir = scan_index::source_dir(package);
+
+        if let Err(e) = ScanIndex::load_or_build(&index_path, &source_dir) {
+            warn!(path = %source_dir.display(), error = %e, "failed to index package");
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use clap::Parser;
@@ -596,6 +747,7 @@ mod tests {
                 start_online: None,
                 disable_arxan: None,
                 no_mem_patch: None,
+                shadow_copy_natives: None,
             },
         );
     }
@@ -613,6 +765,7 @@ mod tests {
             "--skip-steam-init",
             "--online",
             "--no-mem-patch",
+            "--shadow-copy-natives",
         ]);
 
         let Commands::Launch(launch_args) = cli.command else {
@@ -635,6 +788,7 @@ mod tests {
                 start_online: Some(true),
                 disable_arxan: Some(true),
                 no_mem_patch: Some(true),
+                shadow_copy_natives: Some(true),
             },
         );
     }
@@ -652,6 +806,7 @@ mod tests {
             "--skip-steam-init=false",
             "--online=false",
             "--no-mem-patch=false",
+            "--shadow-copy-natives=false",
         ]);
 
         let Commands::Launch(launch_args) = cli.command else {
@@ -674,6 +829,7 @@ mod tests {
                 start_online: Some(false),
                 disable_arxan: Some(false),
                 no_mem_patch: Some(false),
+                shadow_copy_natives: Some(false),
             },
         );
     }
@@ -691,6 +847,7 @@ mod tests {
             "--skip-steam-init=true",
             "--online=true",
             "--no-mem-patch=true",
+            "--shadow-copy-natives=true",
         ]);
 
         let Commands::Launch(launch_args) = cli.command else {
@@ -713,6 +870,7 @@ mod tests {
                 start_online: Some(true),
                 disable_arxan: Some(true),
                 no_mem_patch: Some(true),
+                shadow_copy_natives: Some(true),
             },
         );
     }
@@ -0,0 +1,179 @@
+//! Commands sent to a running game session, either typed into the terminal of `me3 launch` or sent
+//! by other me3 processes.
+//!
+//! Every `me3 launch` advertises a pipe that accepts [`ControlMessage`] lines in a session file
+//! named after its profile, and forwards what it receives to the launcher's control pipe.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use color_eyre::eyre::{bail, eyre, Context};
+use me3_launcher_attach_protocol::control::ControlMessage;
+use tracing::{info, warn};
+
+use crate::commands::launch::named_pipe::NamedPipe;
+
+/// The launcher's end of the control pipe, shared by everything that sends commands to it.
+pub struct ControlPipe(Mutex<File>);
+
+impl ControlPipe {
+    pub fn new(file: File) -> Self {
+        Self(Mutex::new(file))
+    }
+
+    pub fn send(&self, message: &ControlMessage) -> io::Result<()> {
+        let mut file = self.0.lock().unwrap();
+
+        file.write_all(message.to_control_line().as_bytes())?;
+        file.flush()
+    }
+}
+
+/// Writes the log filters typed into the terminal to the launcher's control pipe.
+pub fn forward_log_filters(control_pipe: &ControlPipe, launch_filter: String) {
+    let stdin = io::stdin();
+
+    if !stdin.is_terminal() {
+        return;
+    }
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        let filter = match line.trim() {
+            "" => launch_filter.clone(),
+            filter => filter.to_owned(),
+        };
+
+        if control_pipe
+            .send(&ControlMessage::LogFilter { filter })
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+/// Path of the session file advertising the command pipe of the game launched with `profile`.
+pub fn session_file(cache_dir: &Path, profile: &str) -> PathBuf {
+    cache_dir.join("sessions").join(profile)
+}
+
+/// Accepts commands on a pipe advertised in `session_file` and forwards them to the launcher.
+///
+/// A new pipe is created for every connection, so `session_file` is rewritten each time.
+pub fn forward_session_commands(control_pipe: &ControlPipe, session_file: &Path) {
+    if let Some(sessions_dir) = session_file.parent()
+        && let Err(error) = fs::create_dir_all(sessions_dir)
+    {
+        warn!(%error, "failed to create sessions directory");
+        return;
+    }
+
+    loop {
+        let (pipe, pipe_path) = match NamedPipe::create() {
+            Ok(pipe) => pipe.into_parts(),
+            Err(error) => {
+                warn!(%error, "failed to create session command pipe");
+                return;
+            }
+        };
+
+        if let Err(error) = fs::write(session_file, pipe_path.to_string_lossy().as_bytes()) {
+            warn!(%error, ?session_file, "failed to write session file");
+            return;
+        }
+
+        let Ok(pipe) = pipe.open() else {
+            continue;
+        };
+
+        for line in BufReader::new(pipe).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            let message = match ControlMessage::from_control_line(&line) {
+                Ok(message) => message,
+                Err(error) => {
+                    warn!(%error, "received malformed session command");
+                    continue;
+                }
+            };
+
+            info!(?message, "forwarding session command");
+
+            if control_pipe.send(&message).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Sends `message` to the game launched with `profile`, or the only running game if there's no
+/// profile given.
+pub fn send_to_session(
+    cache_dir: &Path,
+    profile: Option<&str>,
+    message: &ControlMessage,
+) -> color_eyre::Result<()> {
+    let session_file = match profile {
+        Some(profile) => session_file(cache_dir, profile),
+        None => {
+            let sessions = fs::read_dir(cache_dir.join("sessions"))
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| entry.path())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            match <[_; 1]>::try_from(sessions) {
+                Ok([session_file]) => session_file,
+                Err(sessions) if sessions.is_empty() => bail!("no game launched by me3 is running"),
+                Err(sessions) => {
+                    let names = sessions
+                        .iter()
+                        .filter_map(|path| path.file_name())
+                        .map(|name| name.to_string_lossy())
+                        .collect::<Vec<_>>();
+
+                    bail!(
+                        "more than one game is running, select one with --profile ({})",
+                        names.join(", ")
+                    )
+                }
+            }
+        }
+    };
+
+    let pipe_path = fs::read_to_string(&session_file).wrap_err_with(|| {
+        eyre!(
+            "no game launched with profile {} is running",
+            session_file
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        )
+    })?;
+
+    let mut pipe = match NamedPipe::connect(Path::new(pipe_path.trim())) {
+        Ok(pipe) => pipe,
+        Err(e) => {
+            // The session ended without cleaning up after itself.
+            let _ = fs::remove_file(&session_file);
+            return Err(e).wrap_err("the game session is no longer running");
+        }
+    };
+
+    pipe.write_all(message.to_control_line().as_bytes())?;
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 mod unix;
 mod windows;
 
-use std::{fs::File, io};
+use std::{fs::File, io, path::Path};
 
 use tempfile::NamedTempFile;
 #[cfg(unix)]
@@ -11,12 +11,31 @@ use windows::NamedPipe as OsNamedPipe;
 
 pub struct NamedPipe(OsNamedPipe);
 
+/// Direction data flows in, from the point of view of the process creating the pipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
 impl NamedPipe {
+    /// Creates a pipe that is read from by this process.
     pub fn create() -> io::Result<NamedTempFile<Self>> {
-        OsNamedPipe::create_temp(Self)
+        OsNamedPipe::create_temp(Direction::Inbound, Self)
+    }
+
+    /// Creates a pipe that is written to by this process.
+    pub fn create_outbound() -> io::Result<NamedTempFile<Self>> {
+        OsNamedPipe::create_temp(Direction::Outbound, Self)
     }
 
     pub fn open(self) -> io::Result<File> {
         self.0.open()
     }
+
+    /// Connects to a pipe created by another process to write to it, failing if it isn't being
+    /// read from.
+    pub fn connect(path: &Path) -> io::Result<File> {
+        OsNamedPipe::connect(path)
+    }
 }
@@ -2,21 +2,24 @@
 
 use std::{
     ffi::CString,
-    fs::File,
+    fs::{File, OpenOptions},
     io,
-    os::unix::ffi::OsStrExt,
+    os::unix::{ffi::OsStrExt, fs::OpenOptionsExt},
     path::{Path, PathBuf},
 };
 
 use libc::mkfifo;
 use tempfile::NamedTempFile;
 
+use super::Direction;
+
 pub struct NamedPipe {
     path: PathBuf,
+    direction: Direction,
 }
 
 impl NamedPipe {
-    pub fn create(path: &Path) -> io::Result<Self> {
+    pub fn create(path: &Path, direction: Direction) -> io::Result<Self> {
         let c_str = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
 
         if unsafe { mkfifo(c_str.as_ptr(), 0o666) != 0 } {
@@ -24,17 +27,31 @@ impl NamedPipe {
         }
 
         let path = path.to_path_buf();
-        Ok(Self { path })
+        Ok(Self { path, direction })
     }
 
-    pub fn create_temp<T, F: FnMut(Self) -> T>(mut f: F) -> io::Result<NamedTempFile<T>> {
+    pub fn create_temp<T, F: FnMut(Self) -> T>(
+        direction: Direction,
+        mut f: F,
+    ) -> io::Result<NamedTempFile<T>> {
         tempfile::Builder::new()
             .rand_bytes(6)
-            .make(|path| NamedPipe::create(path).map(&mut f))
+            .make(|path| NamedPipe::create(path, direction).map(&mut f))
+    }
+
+    pub fn connect(path: &Path) -> io::Result<File> {
+        // Opening a FIFO for writing blocks until it has a reader, unless non-blocking, in which
+        // case it fails right away instead.
+        OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
     }
 
     pub fn open(self) -> io::Result<File> {
-        let path = self.path;
-        File::open(&path)
+        OpenOptions::new()
+            .read(self.direction == Direction::Inbound)
+            .write(self.direction == Direction::Outbound)
+            .open(&self.path)
     }
 }
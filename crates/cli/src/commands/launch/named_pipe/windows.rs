@@ -17,20 +17,22 @@ use windows::{
     core::PCWSTR,
     Win32::{
         Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE},
-        Storage::FileSystem::PIPE_ACCESS_INBOUND,
+        Storage::FileSystem::{PIPE_ACCESS_INBOUND, PIPE_ACCESS_OUTBOUND},
         System::Pipes::{
             ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_BYTE, PIPE_WAIT,
         },
     },
 };
 
+use super::Direction;
+
 pub struct NamedPipe {
     handle: HANDLE,
     path: PathBuf,
 }
 
 impl NamedPipe {
-    pub fn create(path: &Path) -> io::Result<Self> {
+    pub fn create(path: &Path, direction: Direction) -> io::Result<Self> {
         // https://learn.microsoft.com/en-us/windows/win32/api/namedpipeapi/nf-namedpipeapi-createnamedpipew#parameters
         // 1. The name must start with "\\.\pipe\".
         // 2. The rest may contain any characters other than the backslash.
@@ -46,10 +48,15 @@ impl NamedPipe {
             return Err(io::ErrorKind::InvalidFilename.into());
         }
 
+        let open_mode = match direction {
+            Direction::Inbound => PIPE_ACCESS_INBOUND,
+            Direction::Outbound => PIPE_ACCESS_OUTBOUND,
+        };
+
         let handle = unsafe {
             CreateNamedPipeW(
                 PCWSTR(name.as_ptr()),
-                PIPE_ACCESS_INBOUND,
+                open_mode,
                 PIPE_WAIT | PIPE_READMODE_BYTE,
                 1,
                 4096,
@@ -67,11 +74,14 @@ impl NamedPipe {
         Ok(Self { handle, path })
     }
 
-    pub fn create_temp<T, F: FnMut(Self) -> T>(mut f: F) -> io::Result<NamedTempFile<T>> {
+    pub fn create_temp<T, F: FnMut(Self) -> T>(
+        direction: Direction,
+        mut f: F,
+    ) -> io::Result<NamedTempFile<T>> {
         let mut rand_bytes = [0; 16];
         getrandom::fill(&mut rand_bytes)?;
 
-        let file = Self::create(Path::new(&URL_SAFE.encode(rand_bytes)))?;
+        let file = Self::create(Path::new(&URL_SAFE.encode(rand_bytes)), direction)?;
         let path = file.path.clone();
 
         let mut temp_file = NamedTempFile::from_parts(f(file), TempPath::from_path(path));
@@ -80,6 +90,10 @@ impl NamedPipe {
         Ok(temp_file)
     }
 
+    pub fn connect(path: &Path) -> io::Result<File> {
+        File::options().write(true).open(path)
+    }
+
     pub fn open(self) -> io::Result<File> {
         let handle = self.handle;
         mem::forget(self);
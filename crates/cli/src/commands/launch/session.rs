@@ -0,0 +1,230 @@
+use color_eyre::{eyre::eyre, owo_colors::OwoColorize};
+use me3_launcher_attach_protocol::event::HostEvent;
+
+use crate::output::OutputBuilder;
+
+/// Accumulates the [`HostEvent`]s reported over the course of a game session.
+#[derive(Debug, Default)]
+pub struct SessionSummary {
+    natives_loaded: usize,
+    natives_failed: usize,
+    natives_finalized: usize,
+    finalizers_failed: usize,
+    natives_reloaded: usize,
+    reloads_failed: usize,
+    hooks_installed: usize,
+    hooks_failed: usize,
+    overrides_served: u64,
+    savefile: Option<String>,
+    logs_dropped: u64,
+    logs_spilled: u64,
+    attached: bool,
+    errors: Vec<String>,
+}
+
+impl SessionSummary {
+    /// Record an event, returning a progress line to show to the user if it's noteworthy.
+    pub fn record(&mut self, event: &HostEvent) -> Option<String> {
+        if event.is_error() {
+            self.errors.push(event.to_string());
+        }
+
+        match event {
+            HostEvent::NativeLoaded { .. } => self.natives_loaded += 1,
+            HostEvent::NativeFailed { .. } => self.natives_failed += 1,
+            HostEvent::NativeFinalized { error: None, .. } => self.natives_finalized += 1,
+            HostEvent::NativeFinalized { error: Some(_), .. } => self.finalizers_failed += 1,
+            HostEvent::NativeReloaded { error: None, .. } => self.natives_reloaded += 1,
+            HostEvent::NativeReloaded { error: Some(_), .. } => self.reloads_failed += 1,
+            HostEvent::HookInstalled { .. } => {
+                self.hooks_installed += 1;
+                return None;
+            }
+            HostEvent::HookFailed { .. } => self.hooks_failed += 1,
+            HostEvent::AssetOverride { count, .. } => {
+                self.overrides_served += count;
+                return None;
+            }
+            HostEvent::SavefileRedirected { path } => {
+                if self.savefile.replace(path.clone()).as_ref() == Some(path) {
+                    return None;
+                }
+            }
+            HostEvent::AttachFinished { error } => self.attached = error.is_none(),
+            HostEvent::Fatal { .. } => {}
+            HostEvent::LogsDropped {
+                dropped, spilled, ..
+            } => {
+                self.logs_dropped += dropped;
+                self.logs_spilled += spilled;
+                return None;
+            }
+        }
+
+        let line = if event.is_error() {
+            format!("✗ {event}").red().to_string()
+        } else if matches!(
+            event,
+            HostEvent::NativeFailed { .. }
+                | HostEvent::NativeFinalized { error: Some(_), .. }
+                | HostEvent::NativeReloaded { error: Some(_), .. }
+                | HostEvent::HookFailed { .. }
+        ) {
+            format!("! {event}").yellow().to_string()
+        } else {
+            format!("✓ {event}").green().to_string()
+        };
+
+        Some(line)
+    }
+
+    /// Renders a short overview of the session.
+    pub fn render(&self) -> String {
+        let mut output = OutputBuilder::new("Session summary");
+
+        output.property("Attached", self.attached);
+        output.property(
+            "Natives",
+            format!(
+                "{} loaded, {} failed",
+                self.natives_loaded, self.natives_failed
+            ),
+        );
+
+        if self.natives_finalized > 0 || self.finalizers_failed > 0 {
+            output.property(
+                "Finalizers",
+                format!(
+                    "{} succeeded, {} failed",
+                    self.natives_finalized, self.finalizers_failed
+                ),
+            );
+        }
+
+        if self.natives_reloaded > 0 || self.reloads_failed > 0 {
+            output.property(
+                "Reloads",
+                format!(
+                    "{} succeeded, {} failed",
+                    self.natives_reloaded, self.reloads_failed
+                ),
+            );
+        }
+
+        output.property(
+            "Hooks",
+            format!(
+                "{} installed, {} failed",
+                self.hooks_installed, self.hooks_failed
+            ),
+        );
+        output.property("Overrides served", self.overrides_served);
+
+        if let Some(savefile) = &self.savefile {
+            output.property("Savefile", savefile);
+        }
+
+        if self.logs_dropped > 0 || self.logs_spilled > 0 {
+            output.property(
+                "Logs",
+                format!(
+                    "{} log lines dropped, {} spilled to an overflow file",
+                    self.logs_dropped, self.logs_spilled
+                )
+                .yellow(),
+            );
+        }
+
+        output.build()
+    }
+
+    /// Returns an error if anything reported during the session was fatal.
+    pub fn result(&self) -> color_eyre::Result<()> {
+        match self.errors.as_slice() {
+            [] => Ok(()),
+            [error] => Err(eyre!("game session failed: {error}")),
+            [error, rest @ ..] => Err(eyre!(
+                "game session failed: {error} (and {} more errors)",
+                rest.len()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use me3_launcher_attach_protocol::event::HostEvent;
+
+    use super::SessionSummary;
+
+    #[test]
+    fn optional_native_failure_is_not_fatal() {
+        let mut summary = SessionSummary::default();
+
+        summary.record(&HostEvent::NativeFailed {
+            path: "mod.dll".to_owned(),
+            error: "not found".to_owned(),
+            optional: true,
+        });
+        summary.record(&HostEvent::AttachFinished { error: None });
+
+        assert!(summary.result().is_ok());
+    }
+
+    #[test]
+    fn finalizer_failure_is_not_fatal() {
+        let mut summary = SessionSummary::default();
+
+        let line = summary.record(&HostEvent::NativeFinalized {
+            path: "mod.dll".to_owned(),
+            error: Some("timed out".to_owned()),
+        });
+
+        assert!(line.is_some());
+        assert!(summary.result().is_ok());
+        assert!(summary.render().contains("0 succeeded, 1 failed"));
+    }
+
+    #[test]
+    fn required_native_failure_is_fatal() {
+        let mut summary = SessionSummary::default();
+
+        summary.record(&HostEvent::NativeFailed {
+            path: "mod.dll".to_owned(),
+            error: "not found".to_owned(),
+            optional: false,
+        });
+
+        assert!(summary.result().is_err());
+    }
+
+    #[test]
+    fn counted_events_are_quiet() {
+        let mut summary = SessionSummary::default();
+
+        let line = summary.record(&HostEvent::AssetOverride {
+            path: "regulation.bin".to_owned(),
+            count: 3,
+        });
+
+        assert!(line.is_none());
+        assert_eq!(summary.overrides_served, 3);
+    }
+
+    #[test]
+    fn dropped_logs_are_reported() {
+        let mut summary = SessionSummary::default();
+
+        for kind in ["console", "file"] {
+            summary.record(&HostEvent::LogsDropped {
+                kind: kind.to_owned(),
+                dropped: 2,
+                chunked: 0,
+                spilled: 0,
+            });
+        }
+
+        assert!(summary.render().contains("4 log lines dropped"));
+        assert!(summary.result().is_ok());
+    }
+}
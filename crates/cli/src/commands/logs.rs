@@ -0,0 +1,276 @@
+use std::{fs, path::PathBuf};
+
+use chrono::{DateTime, Local, Utc};
+use clap::Args;
+use color_eyre::{eyre::eyre, owo_colors::OwoColorize};
+use me3_telemetry::LogRecord;
+use serde_json::Value;
+use tracing::Level;
+
+use crate::db::DbContext;
+
+#[derive(Args, Debug)]
+pub struct LogsArgs {
+    /// Log files to show, interleaved by time.
+    #[clap(value_hint = clap::ValueHint::FilePath)]
+    files: Vec<PathBuf>,
+
+    /// Show the most recent log file of a profile.
+    #[clap(short, long)]
+    profile: Option<String>,
+
+    /// Only show records at or above this level (error, warn, info, debug, trace).
+    #[clap(short, long)]
+    level: Option<Level>,
+
+    /// Only show records whose target starts with this prefix [repeatable option]
+    #[clap(short, long)]
+    target: Vec<String>,
+
+    /// Only show records from this process (cli, launcher, host) [repeatable option]
+    #[clap(long)]
+    process: Vec<String>,
+
+    /// Only show records emitted inside of a span with this name [repeatable option]
+    #[clap(long)]
+    span: Vec<String>,
+}
+
+/// A line of a log file.
+#[derive(Debug, PartialEq)]
+enum Entry {
+    Record(Box<LogRecord>),
+
+    /// A line written before JSON logging was enabled or by something other than a tracing
+    /// subscriber.
+    Text(String),
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn logs(db: DbContext, args: LogsArgs) -> color_eyre::Result<()> {
+    let mut files = args.files.clone();
+
+    if let Some(profile) = &args.profile {
+        let latest = db
+            .logs
+            .latest(profile)
+            .ok_or_else(|| eyre!("no logs found for profile {profile:?}"))?;
+
+        files.push(latest.into());
+    }
+
+    if files.is_empty() {
+        return Err(eyre!("no log files given, pass a path or --profile"));
+    }
+
+    let mut entries = Vec::new();
+
+    for path in &files {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| eyre!("failed to read {}: {e}", path.display()))?;
+
+        entries.push(parse(&contents));
+    }
+
+    for entry in merge(entries).filter(|entry| args.matches(entry)) {
+        println!("{}", render(entry));
+    }
+
+    Ok(())
+}
+
+impl LogsArgs {
+    fn has_filters(&self) -> bool {
+        self.level.is_some()
+            || !self.target.is_empty()
+            || !self.process.is_empty()
+            || !self.span.is_empty()
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let record = match entry {
+            Entry::Record(record) => record,
+            Entry::Text(_) => return !self.has_filters(),
+        };
+
+        if let Some(level) = self.level {
+            // More verbose levels compare greater than less verbose ones.
+            if record.level.parse::<Level>().is_ok_and(|l| l > level) {
+                return false;
+            }
+        }
+
+        if !self.target.is_empty() && !self.target.iter().any(|t| record.target.starts_with(t)) {
+            return false;
+        }
+
+        if !self.process.is_empty() && !self.process.contains(&record.process) {
+            return false;
+        }
+
+        if !self.span.is_empty() && !record.spans.iter().any(|s| self.span.contains(s)) {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Parses a log file, pairing each line with the time it was written at.
+///
+/// Plain text lines don't carry a timestamp and are placed right after the preceding record.
+fn parse(contents: &str) -> Vec<(DateTime<Utc>, Entry)> {
+    let mut last_timestamp = DateTime::<Utc>::MIN_UTC;
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match serde_json::from_str::<LogRecord>(line) {
+            Ok(record) => {
+                last_timestamp = record.timestamp;
+                (record.timestamp, Entry::Record(Box::new(record)))
+            }
+            Err(_) => (last_timestamp, Entry::Text(line.to_owned())),
+        })
+        .collect()
+}
+
+/// Interleaves the entries of several files by time, keeping the order of entries within a file.
+fn merge(files: Vec<Vec<(DateTime<Utc>, Entry)>>) -> impl Iterator<Item = Entry> {
+    let mut entries = files.into_iter().flatten().collect::<Vec<_>>();
+
+    // Sorting is stable, so entries with equal timestamps keep their order.
+    entries.sort_by_key(|(timestamp, _)| *timestamp);
+    entries.into_iter().map(|(_, entry)| entry)
+}
+
+fn render(entry: Entry) -> String {
+    let record = match entry {
+        Entry::Record(record) => record,
+        Entry::Text(line) => return line.dimmed().to_string(),
+    };
+
+    let time = record
+        .timestamp
+        .with_timezone(&Local)
+        .format("%H:%M:%S%.3f");
+
+    let process = format!("{:<8}", record.process);
+    let process = match record.process.as_str() {
+        "cli" => process.blue().to_string(),
+        "launcher" => process.magenta().to_string(),
+        "host" => process.cyan().to_string(),
+        _ => process,
+    };
+
+    let level = format!("{:>5}", record.level);
+    let level = match record.level.parse::<Level>() {
+        Ok(Level::ERROR) => level.red().to_string(),
+        Ok(Level::WARN) => level.yellow().to_string(),
+        Ok(Level::INFO) => level.green().to_string(),
+        Ok(Level::DEBUG) => level.blue().to_string(),
+        _ => level.purple().to_string(),
+    };
+
+    let mut line = format!("{} {process} {level} ", time.dimmed());
+
+    if !record.spans.is_empty() {
+        line += &format!("{}: ", record.spans.join(":").bold());
+    }
+
+    line += &format!("{}: {}", record.target.dimmed(), record.message);
+
+    for (name, value) in &record.fields {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+
+        line += &format!(" {}={value}", name.italic());
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use pretty_assertions::assert_eq;
+
+    use super::{merge, parse, Entry, LogsArgs};
+
+    #[derive(Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        args: LogsArgs,
+    }
+
+    fn record(timestamp: &str, process: &str, level: &str, spans: &[&str]) -> String {
+        serde_json::json!({
+            "timestamp": timestamp,
+            "process": process,
+            "level": level,
+            "target": format!("me3_{process}"),
+            "spans": spans,
+            "message": "hello",
+        })
+        .to_string()
+    }
+
+    fn processes(entries: impl Iterator<Item = Entry>) -> Vec<String> {
+        entries
+            .map(|entry| match entry {
+                Entry::Record(record) => record.process,
+                Entry::Text(line) => line,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn files_are_interleaved_by_time() {
+        let cli = [
+            record("2025-01-01T00:00:00Z", "cli", "INFO", &[]),
+            record("2025-01-01T00:00:03Z", "cli", "INFO", &[]),
+        ]
+        .join("\n");
+
+        let host = [
+            record("2025-01-01T00:00:01Z", "host", "INFO", &[]),
+            "plain".to_owned(),
+            record("2025-01-01T00:00:02Z", "host", "INFO", &[]),
+        ]
+        .join("\n");
+
+        let merged = merge(vec![parse(&cli), parse(&host)]);
+
+        assert_eq!(processes(merged), ["cli", "host", "plain", "host", "cli"]);
+    }
+
+    #[test]
+    fn records_are_filtered() {
+        let log = [
+            record("2025-01-01T00:00:00Z", "cli", "DEBUG", &[]),
+            record("2025-01-01T00:00:01Z", "launcher", "WARN", &["run"]),
+            record(
+                "2025-01-01T00:00:02Z",
+                "host",
+                "ERROR",
+                &["attach", "load_native"],
+            ),
+            "plain".to_owned(),
+        ]
+        .join("\n");
+
+        let filtered = |argv: &[&str]| {
+            let cli = Cli::parse_from(["logs"].iter().chain(argv));
+
+            processes(merge(vec![parse(&log)]).filter(|entry| cli.args.matches(entry)))
+        };
+
+        assert_eq!(filtered(&[]), ["cli", "launcher", "host", "plain"]);
+        assert_eq!(filtered(&["--level", "warn"]), ["launcher", "host"]);
+        assert_eq!(filtered(&["--process", "cli"]), ["cli"]);
+        assert_eq!(filtered(&["--target", "me3_host"]), ["host"]);
+        assert_eq!(filtered(&["--span", "load_native"]), ["host"]);
+    }
+}
@@ -1,16 +1,26 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use clap::{ArgAction, Args, Subcommand};
-use color_eyre::eyre::{eyre, OptionExt};
+use color_eyre::eyre::{bail, eyre, Context, OptionExt};
 use me3_mod_protocol::{
     dependency::Dependency,
     native::Native,
     package::{Package, WithPackageSource},
     ModProfile, Supports,
 };
+use serde_json::{Map, Value};
 use tracing::error;
 
-use crate::{config::Config, db::DbContext, output::OutputBuilder, Game};
+use crate::{
+    config::Config,
+    db::{profile::Profile, DbContext},
+    native_check::check_natives,
+    output::OutputBuilder,
+    Game,
+};
 
 #[derive(Subcommand, Debug)]
 #[command(flatten_help = true)]
@@ -24,6 +34,14 @@ pub enum ProfileCommands {
 
     /// Show information on a profile.
     Show(#[clap(flatten)] ProfileNameArgs),
+
+    /// Check that a profile loads, that its native DLLs can be loaded by the game and that native
+    /// configs match their schemas.
+    Check(#[clap(flatten)] ProfileNameArgs),
+
+    /// List files overridden by more than one package of a profile, and the binder entries,
+    /// regulation rows and text entries they conflict over when merged.
+    Conflicts(#[clap(flatten)] ProfileNameArgs),
 }
 
 #[derive(Args, Debug)]
@@ -87,6 +105,14 @@ pub struct ProfileOptions {
     /// improving game performance.
     #[clap(long("no-mem-patch"), default_missing_value = "true", num_args=0..=1)]
     pub no_mem_patch: Option<bool>,
+
+    /// Load copies of native DLLs so they can be rebuilt while the game is running?
+    ///
+    /// Natives are copied, along with their PDBs, into a per-session cache directory before they
+    /// are loaded, leaving the originals unlocked. Rebuilt natives can then be loaded into the
+    /// running game with `me3 reload-native`. Meant for developing natives.
+    #[clap(long("shadow-copy-natives"), default_missing_value = "true", num_args=0..=1)]
+    pub shadow_copy_natives: Option<bool>,
 }
 
 impl ProfileOptions {
@@ -99,6 +125,7 @@ impl ProfileOptions {
                 (a, b) => a.or(b),
             },
             no_mem_patch: other.no_mem_patch.or(self.no_mem_patch),
+            shadow_copy_natives: other.shadow_copy_natives.or(self.shadow_copy_natives),
         }
     }
 }
@@ -223,6 +250,22 @@ pub fn show(db: DbContext, config: Config, name: ProfileNameArgs) -> color_eyre:
                 builder.indent(2);
                 builder.property("Path", package.source().to_string_lossy());
                 builder.property("Enabled", package.enabled);
+
+                if let Some(root) = &package.root {
+                    builder.property("Root", root);
+                }
+
+                if let Some(mount) = &package.mount {
+                    builder.property("Mount", mount);
+                }
+
+                if !package.include.is_empty() {
+                    builder.property("Include", package.include.join(", "));
+                }
+
+                if !package.exclude.is_empty() {
+                    builder.property("Exclude", package.exclude.join(", "));
+                }
             });
         }
     });
@@ -238,6 +281,228 @@ pub fn show(db: DbContext, config: Config, name: ProfileNameArgs) -> color_eyre:
         let options = profile.options();
         builder.property("Start Online", opt_to_str(options.start_online));
         builder.property("Neutralize Arxan", opt_to_str(options.disable_arxan));
+        builder.property(
+            "Shadow Copy Natives",
+            opt_to_str(options.shadow_copy_natives),
+        );
+    });
+
+    println!("{}", output.build());
+
+    Ok(())
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn check(db: DbContext, config: Config, name: ProfileNameArgs) -> color_eyre::Result<()> {
+    let profile_path = name.into_profile_path(&config)?;
+
+    let profile = db.profiles.load(profile_path)?;
+    let (natives, early_natives, packages) = profile.compile()?;
+
+    let base_dir = profile.base_dir().unwrap_or(Path::new("."));
+
+    let mut output = OutputBuilder::new("Profile check");
+    let mut problems = 0;
+
+    output.property(
+        "Load order",
+        format!(
+            "{} natives, {} packages",
+            natives.len() + early_natives.len(),
+            packages.len()
+        ),
+    );
+
+    let game_dir = find_game_dir(&config, &profile);
+
+    output.section("Native DLLs", |builder| {
+        if game_dir.is_none() {
+            builder.property("Note", "Game not found, imports next to it aren't checked");
+        }
+
+        for report in check_natives(early_natives.iter().chain(&natives), game_dir.as_deref()) {
+            builder.section(report.native.id(), |builder| {
+                builder.indent(2);
+
+                if report.problems.is_empty() {
+                    builder.property("Status", "OK");
+                }
+
+                for problem in &report.problems {
+                    problems += 1;
+
+                    let label = if problem.is_fatal() {
+                        "Error"
+                    } else {
+                        "Warning"
+                    };
+                    builder.property(label, problem);
+                }
+            });
+        }
+    });
+
+    output.section("Native configs", |builder| {
+        for native in early_natives.iter().chain(&natives) {
+            let Some(native_config) = &native.config else {
+                continue;
+            };
+
+            builder.section(native.id(), |builder| {
+                builder.indent(2);
+
+                match validate_native_config(native, native_config, base_dir) {
+                    Ok(None) => builder.property("Status", "No schema, not validated"),
+                    Ok(Some(errors)) if errors.is_empty() => builder.property("Status", "Valid"),
+                    Ok(Some(errors)) => {
+                        problems += errors.len();
+
+                        for (index, error) in errors.iter().enumerate() {
+                            builder.property(format!("Error {}", index + 1), error);
+                        }
+                    }
+                    Err(e) => {
+                        problems += 1;
+                        builder.property("Error", format!("{e:#}"));
+                    }
+                }
+            });
+        }
+    });
+
+    println!("{}", output.build());
+
+    if problems > 0 {
+        bail!("found {problems} problems in the profile");
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn conflicts(db: DbContext, config: Config, name: ProfileNameArgs) -> color_eyre::Result<()> {
+    #[cfg(target_os = "windows")]
+    use me3_mod_host_assets::oodle::Oodle;
+    use me3_mod_host_assets::{
+        bhd5::{Bhd5Format, CachedArchives},
+        conflict,
+        dcx::KrakenDecompressor,
+        mapping::VfsOverrideMapping,
+    };
+
+    /// How many conflicting IDs are listed per file before they're summarized.
+    const MAX_LISTED_IDS: usize = 10;
+
+    let profile_path = name.into_profile_path(&config)?;
+
+    let profile = db.profiles.load(profile_path)?;
+    let (_, _, packages) = profile.compile()?;
+
+    let mut mapping = VfsOverrideMapping::new()?;
+    mapping.scan_directories(packages.iter())?;
+
+    let mut overlapping = mapping
+        .overlapping_files()
+        .map(|(path, overrides)| {
+            let packages = overrides
+                .iter()
+                .map(|vfs_override| vfs_override.package().unwrap_or_default().to_owned())
+                .collect::<Vec<_>>();
+
+            (path, packages)
+        })
+        .collect::<Vec<_>>();
+
+    overlapping.sort();
+
+    // Files are merged the same way the game would merge them, into the cache if there is one.
+    let temp_dir;
+    let cache_dir = match config.cache_dir() {
+        Some(cache_dir) => cache_dir.into_path_buf(),
+        None => {
+            temp_dir = tempfile::tempdir()?;
+            temp_dir.path().to_owned()
+        }
+    };
+
+    let game_dir = find_game_dir(&config, &profile);
+
+    // Oodle is loaded from the game's DLL, so Kraken compressed files can only be merged on
+    // Windows.
+    #[cfg(target_os = "windows")]
+    let oodle = game_dir.as_deref().and_then(Oodle::load);
+    #[cfg(target_os = "windows")]
+    let kraken = oodle.as_ref().map(|oodle| oodle as &dyn KrakenDecompressor);
+    #[cfg(not(target_os = "windows"))]
+    let kraken: Option<&dyn KrakenDecompressor> = None;
+
+    let archives = match (game_dir.as_deref(), profile.supported_game()) {
+        (Some(game_dir), Some(game)) => {
+            CachedArchives::load(game_dir, &cache_dir, Bhd5Format::for_game(game)).ok()
+        }
+        _ => None,
+    };
+
+    let reports = mapping.merge_overrides(
+        &cache_dir,
+        game_dir.as_deref(),
+        archives.as_ref(),
+        kraken,
+    )?;
+
+    let mut output = OutputBuilder::new("Package conflicts");
+
+    output.section("Overridden files", |builder| {
+        for (path, packages) in &overlapping {
+            if reports.iter().any(|report| report.path == *path) {
+                continue;
+            }
+
+            let last = packages.last().map(String::as_str).unwrap_or_default();
+            builder.property(path, format!("{} (using {last})", packages.join(", ")));
+        }
+    });
+
+    output.section("Merged files", |builder| {
+        if game_dir.is_none() {
+            builder.property("Note", "Game not found, regulations can't be merged");
+        }
+
+        for report in &reports {
+            builder.section(&report.path, |builder| {
+                builder.indent(2);
+
+                builder.property("Packages", report.packages.join(", "));
+
+                match &report.merged {
+                    Ok(_) => builder.property("Status", "Merged"),
+                    Err(e) => builder.property("Error", format!("{e}, using the last package")),
+                }
+
+                for group in conflict::group(&report.conflicts) {
+                    let mut ids = group
+                        .ids
+                        .iter()
+                        .take(MAX_LISTED_IDS)
+                        .map(i32::to_string)
+                        .collect::<Vec<_>>();
+
+                    if group.ids.len() > MAX_LISTED_IDS {
+                        ids.push(format!("and {} more", group.ids.len() - MAX_LISTED_IDS));
+                    }
+
+                    builder.property(
+                        group.file,
+                        format!(
+                            "{} entries changed by {}, using the last: {}",
+                            group.ids.len(),
+                            group.packages.join(", "),
+                            ids.join(", ")
+                        ),
+                    );
+                }
+            });
+        }
     });
 
     println!("{}", output.build());
@@ -245,6 +510,52 @@ pub fn show(db: DbContext, config: Config, name: ProfileNameArgs) -> color_eyre:
     Ok(())
 }
 
+/// The directory of the game executable, if the profile supports one game and Steam can find it.
+fn find_game_dir(config: &Config, profile: &Profile) -> Option<PathBuf> {
+    installed_game_dir(config, Game(profile.supported_game()?))
+}
+
+/// The directory of the game executable, if Steam can find the game.
+pub(crate) fn installed_game_dir(config: &Config, game: Game) -> Option<PathBuf> {
+    let steam_dir = config.steam_dir().ok()?;
+    let (app, library) = steam_dir.find_app(game.app_id()).ok()??;
+    let exe = library.resolve_app_dir(&app).join(game.launcher());
+
+    exe.parent().map(Path::to_path_buf)
+}
+
+/// Validates the `config` of a native against its schema, returning the validation errors or
+/// `None` if the native doesn't publish a schema.
+fn validate_native_config(
+    native: &Native,
+    config: &Map<String, Value>,
+    base_dir: &Path,
+) -> color_eyre::Result<Option<Vec<String>>> {
+    let Some(schema_path) = native.config_schema_path() else {
+        return Ok(None);
+    };
+
+    let schema_path = base_dir.join(schema_path);
+
+    let schema = fs::read_to_string(&schema_path)
+        .wrap_err_with(|| format!("failed to read {}", schema_path.display()))?;
+
+    let schema: Value = serde_json::from_str(&schema)
+        .wrap_err_with(|| format!("failed to parse {}", schema_path.display()))?;
+
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| eyre!("invalid schema {}: {e}", schema_path.display()))?;
+
+    let config = Value::Object(config.clone());
+
+    let errors = validator
+        .iter_errors(&config)
+        .map(|e| format!("config{}: {e}", e.instance_path))
+        .collect();
+
+    Ok(Some(errors))
+}
+
 pub fn no_profile_dir() -> color_eyre::Report {
     eyre!(
         r#"No profile directory was configured and the default profile directory was inaccessible.
@@ -254,3 +565,40 @@ pub fn no_profile_dir() -> color_eyre::Report {
     "#
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use me3_mod_protocol::native::Native;
+    use serde_json::json;
+
+    use super::validate_native_config;
+
+    #[test]
+    fn native_config_is_validated_against_schema() -> color_eyre::Result<()> {
+        let temp_dir = assert_fs::TempDir::new()?;
+        temp_dir.child("my_native.schema.json").write_str(
+            r#"{
+                "type": "object",
+                "properties": { "fov": { "type": "integer", "maximum": 120 } }
+            }"#,
+        )?;
+
+        let native = Native::new(temp_dir.child("my_native.dll").path());
+
+        let valid = json!({ "fov": 90 });
+        let invalid = json!({ "fov": 150 });
+
+        let errors = validate_native_config(&native, valid.as_object().unwrap(), Path::new("."))?;
+        assert_eq!(errors, Some(vec![]));
+
+        let errors =
+            validate_native_config(&native, invalid.as_object().unwrap(), Path::new("."))?.unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("config/fov"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use clap::Args;
+use color_eyre::eyre::OptionExt;
+use me3_launcher_attach_protocol::control::ControlMessage;
+
+use crate::{commands::launch::control, config::Config};
+
+#[derive(Args, Debug)]
+pub struct ReloadNativeArgs {
+    /// Id of the native to reload, which is its file name unless the profile gives it an `id`.
+    id: String,
+
+    /// Name of the profile the game was launched with, if more than one game is running.
+    #[clap(short, long)]
+    profile: Option<String>,
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn reload_native(config: Config, args: ReloadNativeArgs) -> color_eyre::Result<()> {
+    let cache_dir = config
+        .cache_dir()
+        .ok_or_eyre("can't find the cache directory used by running games")?;
+
+    control::send_to_session(
+        &cache_dir,
+        args.profile.as_deref(),
+        &ControlMessage::ReloadNative {
+            id: args.id.clone(),
+        },
+    )?;
+
+    eprintln!(
+        "Requested a reload of {}, its result is shown by `me3 launch`",
+        args.id
+    );
+
+    Ok(())
+}
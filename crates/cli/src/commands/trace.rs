@@ -0,0 +1,268 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::eyre;
+use me3_launcher_attach_protocol::trace::AssetTrace;
+
+use crate::db::DbContext;
+
+#[derive(Subcommand, Debug)]
+#[command(flatten_help = true)]
+pub enum TraceCommands {
+    /// List the assets the game requested most often and what served them.
+    Top(TraceTopArgs),
+
+    /// List package files the game never requested, by package.
+    Unused(TraceFileArgs),
+
+    /// List assets the game requested that were never overridden.
+    Missed(TraceMissedArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TraceFileArgs {
+    /// Asset trace file to query.
+    #[clap(value_hint = clap::ValueHint::FilePath)]
+    file: Option<PathBuf>,
+
+    /// Query the most recent asset trace of a profile.
+    #[clap(short, long)]
+    profile: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct TraceTopArgs {
+    #[clap(flatten)]
+    trace: TraceFileArgs,
+
+    /// Number of assets to list.
+    #[clap(short('n'), long, default_value_t = 20)]
+    count: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct TraceMissedArgs {
+    #[clap(flatten)]
+    trace: TraceFileArgs,
+
+    /// Only list assets whose path contains this text (e.g. "chr/c2010").
+    #[clap(short, long)]
+    filter: Option<String>,
+}
+
+impl TraceFileArgs {
+    fn load(&self, db: &DbContext) -> color_eyre::Result<AssetTrace> {
+        let path = match (&self.file, &self.profile) {
+            (Some(file), _) => file.clone(),
+            (None, Some(profile)) => {
+                let trace = db.logs.latest_trace(profile).ok_or_else(|| {
+                    eyre!("no asset trace found for {profile:?}, launch it with --trace-assets")
+                })?;
+
+                trace.into()
+            }
+            (None, None) => return Err(eyre!("no asset trace given, pass a path or --profile")),
+        };
+
+        let bytes = fs::read(&path).map_err(|e| eyre!("failed to read {}: {e}", path.display()))?;
+
+        AssetTrace::from_bytes(&bytes).map_err(|e| eyre!("failed to parse {}: {e}", path.display()))
+    }
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn top(db: DbContext, args: TraceTopArgs) -> color_eyre::Result<()> {
+    let trace = args.trace.load(&db)?;
+
+    for asset in most_requested(&trace).into_iter().take(args.count) {
+        match asset.served_by {
+            Some(served_by) => println!("{:>8}  {} ({served_by})", asset.requests, asset.path),
+            None => println!("{:>8}  {}", asset.requests, asset.path),
+        }
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn unused(db: DbContext, args: TraceFileArgs) -> color_eyre::Result<()> {
+    let trace = args.load(&db)?;
+
+    for (package, paths) in unused_files(&trace) {
+        println!("● {package} ({} unused)", paths.len());
+
+        for path in paths {
+            println!("    {path}");
+        }
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(err, skip_all)]
+pub fn missed(db: DbContext, args: TraceMissedArgs) -> color_eyre::Result<()> {
+    let trace = args.trace.load(&db)?;
+
+    let missed = never_overridden(&trace).into_iter().filter(|(path, _)| {
+        args.filter
+            .as_ref()
+            .is_none_or(|filter| path.contains(filter))
+    });
+
+    for (path, requests) in missed {
+        println!("{requests:>8}  {path}");
+    }
+
+    Ok(())
+}
+
+/// A requested asset, with the number of times it was requested.
+#[derive(Debug, PartialEq)]
+struct RequestedAsset<'a> {
+    path: &'a str,
+    requests: usize,
+
+    /// The package (or file, if it isn't part of a package) that last served the asset.
+    served_by: Option<&'a str>,
+}
+
+/// Lists requested assets, most requested first.
+fn most_requested(trace: &AssetTrace) -> Vec<RequestedAsset<'_>> {
+    let mut assets = HashMap::<u32, RequestedAsset>::new();
+
+    for access in &trace.accesses {
+        let asset = assets.entry(access.path).or_insert_with(|| RequestedAsset {
+            path: &trace.paths[access.path as usize],
+            requests: 0,
+            served_by: None,
+        });
+
+        asset.requests += 1;
+
+        if let Some(file) = access
+            .served_by
+            .and_then(|file| trace.files.get(file as usize))
+        {
+            asset.served_by = Some(trace.package_of(file).unwrap_or(&file.source));
+        }
+    }
+
+    let mut assets = assets.into_values().collect::<Vec<_>>();
+    assets.sort_by(|a, b| b.requests.cmp(&a.requests).then(a.path.cmp(b.path)));
+
+    assets
+}
+
+/// Lists the files of each package that never served a request.
+fn unused_files(trace: &AssetTrace) -> BTreeMap<&str, Vec<&str>> {
+    let mut used = vec![false; trace.files.len()];
+
+    for file in trace.accesses.iter().filter_map(|access| access.served_by) {
+        if let Some(used) = used.get_mut(file as usize) {
+            *used = true;
+        }
+    }
+
+    let mut unused = BTreeMap::<&str, Vec<&str>>::new();
+
+    for (file, _) in trace.files.iter().zip(used).filter(|(_, used)| !used) {
+        if let Some(package) = trace.package_of(file) {
+            unused.entry(package).or_default().push(&file.path);
+        }
+    }
+
+    for paths in unused.values_mut() {
+        paths.sort_unstable();
+    }
+
+    unused
+}
+
+/// Lists requested assets that were never overridden, most requested first.
+fn never_overridden(trace: &AssetTrace) -> Vec<(&str, usize)> {
+    most_requested(trace)
+        .into_iter()
+        .filter(|asset| asset.served_by.is_none())
+        .map(|asset| (asset.path, asset.requests))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use me3_launcher_attach_protocol::trace::{AssetTrace, AssetTraceRecorder};
+
+    use super::{most_requested, never_overridden, unused_files};
+
+    fn trace() -> AssetTrace {
+        let anibnd = Some(("D:/mod/chr/c0000.anibnd.dcx", Some("mod")));
+
+        let mut recorder = AssetTraceRecorder::new(0);
+
+        recorder.add_file(
+            "chr/c0000.anibnd.dcx",
+            "D:/mod/chr/c0000.anibnd.dcx",
+            Some("mod"),
+        );
+        recorder.add_file(
+            "parts/wp_a_0100.partsbnd.dcx",
+            "D:/mod/parts/wp_a_0100.partsbnd.dcx",
+            Some("mod"),
+        );
+        recorder.add_file("sd/init.bnk", "D:/sound/sd/init.bnk", Some("sound"));
+
+        recorder.record("chr/c0000.anibnd.dcx", anibnd, 1);
+        recorder.record("chr/c0000.chrbnd.dcx", None, 2);
+        recorder.record("chr/c0000.anibnd.dcx", anibnd, 3);
+        recorder.record("regulation.bin", Some(("D:/saves/regulation.bin", None)), 4);
+        recorder.record("chr/c0000.chrbnd.dcx", None, 5);
+        recorder.record("chr/c0000.anibnd.dcx", anibnd, 6);
+        recorder.record("map/m10_00_00_00.msb.dcx", None, 7);
+
+        recorder.finish()
+    }
+
+    #[test]
+    fn most_requested_assets_come_first() {
+        let trace = trace();
+        let assets = most_requested(&trace);
+
+        let summary = assets
+            .iter()
+            .map(|asset| (asset.path, asset.requests, asset.served_by))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            [
+                ("chr/c0000.anibnd.dcx", 3, Some("mod")),
+                ("chr/c0000.chrbnd.dcx", 2, None),
+                ("map/m10_00_00_00.msb.dcx", 1, None),
+                ("regulation.bin", 1, Some("D:/saves/regulation.bin")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_files_are_grouped_by_package() {
+        let trace = trace();
+        let unused = unused_files(&trace);
+
+        assert_eq!(unused.len(), 2);
+        assert_eq!(unused["mod"], ["parts/wp_a_0100.partsbnd.dcx"]);
+        assert_eq!(unused["sound"], ["sd/init.bnk"]);
+    }
+
+    #[test]
+    fn never_overridden_assets_are_listed() {
+        let trace = trace();
+
+        assert_eq!(
+            never_overridden(&trace),
+            [("chr/c0000.chrbnd.dcx", 2), ("map/m10_00_00_00.msb.dcx", 1)]
+        );
+    }
+}
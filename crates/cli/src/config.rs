@@ -5,6 +5,7 @@ use std::{
 };
 
 use color_eyre::{eyre::eyre, Result};
+use me3_env::LogFormat;
 use me3_mod_protocol::Game;
 use serde::{Deserialize, Serialize};
 use steamlocate::SteamDir;
@@ -41,6 +42,10 @@ pub struct Options {
     #[clap(long, help_heading = "Configuration", value_hint = clap::ValueHint::DirPath)]
     pub(crate) windows_binaries_dir: Option<Box<Path>>,
 
+    /// Format of the records written to log files ("text" or "json").
+    #[clap(long, help_heading = "Configuration")]
+    pub(crate) log_format: Option<LogFormat>,
+
     #[clap(skip)]
     #[serde(default)]
     pub(crate) game: BTreeMap<Game, GameOptions>,
@@ -110,6 +115,7 @@ impl Options {
             profile_dir: other.profile_dir.or(self.profile_dir),
             steam_dir: other.steam_dir.or(self.steam_dir),
             windows_binaries_dir: other.windows_binaries_dir.or(self.windows_binaries_dir),
+            log_format: other.log_format.or(self.log_format),
         }
     }
 
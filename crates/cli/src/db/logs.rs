@@ -23,26 +23,13 @@ impl LogsDb {
         let profile_log_folder = self.base_dir.join(profile_name);
         fs::create_dir_all(&profile_log_folder)?;
 
-        let log_files: Vec<(SystemTime, PathBuf)> = fs::read_dir(&profile_log_folder)
-            .map(|dir| {
-                dir.filter_map(|entry| {
-                    let entry = entry.ok()?;
-                    let metadata = entry.metadata().ok()?;
-                    if metadata.is_file()
-                        && entry.path().extension().is_some_and(|ext| ext == "log")
-                    {
-                        Some((metadata.modified().ok()?, entry.path()))
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-            })
-            .unwrap_or_default();
+        let log_files = log_files(&profile_log_folder);
 
         if log_files.len() >= self.retention {
             if let Some((_, path_to_delete)) = log_files.iter().min_by_key(|(time, _)| *time) {
                 let _ = fs::remove_file(path_to_delete);
+                let _ = fs::remove_file(path_to_delete.with_extension("overflow.log"));
+                let _ = fs::remove_file(path_to_delete.with_extension("trace"));
             }
         }
 
@@ -52,4 +39,55 @@ impl LogsDb {
 
         Ok(log_file_path.into_boxed_path())
     }
+
+    /// Returns the most recently written log file of a profile, if any.
+    pub fn latest(&self, profile_name: &str) -> Option<Box<Path>> {
+        self.recent(profile_name, 1).pop()
+    }
+
+    /// Returns the asset trace of the most recent session of a profile that recorded one.
+    pub fn latest_trace(&self, profile_name: &str) -> Option<Box<Path>> {
+        self.recent(profile_name, self.retention)
+            .into_iter()
+            .map(|log_file| log_file.with_extension("trace"))
+            .find(|trace_file| trace_file.is_file())
+            .map(PathBuf::into_boxed_path)
+    }
+
+    /// Returns up to `count` log files of a profile, newest first.
+    pub fn recent(&self, profile_name: &str, count: usize) -> Vec<Box<Path>> {
+        let mut log_files = log_files(&self.base_dir.join(profile_name));
+        log_files.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+
+        log_files
+            .into_iter()
+            .take(count)
+            .map(|(_, path)| path.into_boxed_path())
+            .collect()
+    }
+}
+
+/// Lists session log files in `dir`, skipping the files host log messages overflow to.
+fn log_files(dir: &Path) -> Vec<(SystemTime, PathBuf)> {
+    fs::read_dir(dir)
+        .map(|dir| {
+            dir.filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                if metadata.is_file()
+                    && entry.path().extension().is_some_and(|ext| ext == "log")
+                    && !entry
+                        .path()
+                        .with_extension("")
+                        .extension()
+                        .is_some_and(|ext| ext == "overflow")
+                {
+                    Some((metadata.modified().ok()?, entry.path()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+        })
+        .unwrap_or_default()
 }
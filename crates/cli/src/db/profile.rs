@@ -7,8 +7,8 @@ use std::{
 
 use color_eyre::eyre::{eyre, Context};
 use me3_mod_protocol::{
-    dependency::{sort_dependencies, Dependency, Dependent},
-    native::Native,
+    dependency::{sort_dependencies, Dependency},
+    native::{Native, NativeLoadStage},
     package::{Package, WithPackageSource},
     Game, ModProfile,
 };
@@ -50,6 +50,11 @@ impl Profile {
         &self.name
     }
 
+    /// Get the path of this profile file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Get the directory containing this profile file.
     pub fn base_dir(&self) -> Option<&Path> {
         self.path.parent()
@@ -84,12 +89,18 @@ impl Profile {
         self.profile.savefile()
     }
 
+    /// Get the log filter directives set by this profile.
+    pub fn log_filter(&self) -> Option<String> {
+        self.profile.log_filter()
+    }
+
     /// Returns misc. options set by this profile.
     pub fn options(&self) -> ProfileOptions {
         ProfileOptions {
             start_online: self.profile.start_online(),
             disable_arxan: self.profile.disable_arxan(),
             no_mem_patch: self.profile.patch_mem().map(|b| !b),
+            shadow_copy_natives: self.profile.shadow_copy_natives(),
         }
     }
 
@@ -131,40 +142,65 @@ impl Profile {
             .map(|native| (native.id(), native))
             .collect::<HashMap<_, _>>();
 
-        let find_load_early_native = |natives: &[Dependent<String>], load_early: bool| {
-            natives.iter().find_map(|native| {
-                let id = native.id();
-                let native = natives_by_id.get(id)?;
-                (native.load_early == load_early).then(|| id.clone())
-            })
-        };
-
         for (native_id, native) in &natives_by_id {
-            if native.load_early {
-                if let Some(after_id) = find_load_early_native(native.loads_after(), false) {
-                    return Err(eyre!(
-                        "load_early native {} loads after {}, which itself is not load_early",
-                        native_id,
-                        after_id
-                    ));
-                }
-            } else if let Some(before_id) = find_load_early_native(native.loads_before(), true) {
+            let stage = native.load_stage();
+
+            if native.load_early && stage != NativeLoadStage::BeforeMain {
+                return Err(eyre!(
+                    "native {native_id} is load_early but has load_stage {stage}"
+                ));
+            }
+
+            if let NativeLoadStage::AfterStep(step) = &stage
+                && !is_fd4_step_name(step)
+            {
+                return Err(eyre!(
+                    "native {native_id} loads after {step:?}, which is not an FD4 step name (e.g. \"CSRegulationStep::STEP_Idle\")"
+                ));
+            }
+
+            let misordered = native
+                .loads_after()
+                .iter()
+                .filter_map(|after| Some((after.id(), natives_by_id.get(after.id())?)))
+                .find(|(_, after)| !after.load_stage().precedes(&stage));
+
+            if let Some((after_id, after)) = misordered {
+                return Err(eyre!(
+                    "native {native_id} ({stage}) loads after {after_id}, which may be loaded at a later stage ({})",
+                    after.load_stage()
+                ));
+            }
+
+            let misordered = native
+                .loads_before()
+                .iter()
+                .filter_map(|before| Some((before.id(), natives_by_id.get(before.id())?)))
+                .find(|(_, before)| !stage.precedes(&before.load_stage()));
+
+            if let Some((before_id, before)) = misordered {
                 return Err(eyre!(
-                    "load_early native {} loads after {}, which itself is not load_early",
-                    before_id,
-                    native_id
+                    "native {native_id} ({stage}) loads before {before_id}, which may be loaded at an earlier stage ({})",
+                    before.load_stage()
                 ));
             }
         }
 
-        ordered_natives.sort_by_key(|native| native.load_early);
-        let early_natives =
-            ordered_natives.split_off(ordered_natives.partition_point(|native| !native.load_early));
+        ordered_natives.sort_by_key(|native| native.load_stage().rank());
+        let mut early_natives = ordered_natives;
+        let ordered_natives = early_natives
+            .split_off(early_natives.partition_point(|native| native.load_stage().is_early()));
 
         Ok((ordered_natives, early_natives, ordered_packages))
     }
 }
 
+/// Checks that `step` looks like the name of an FD4 step function, e.g. "CSFileStep::STEP_Init".
+fn is_fd4_step_name(step: &str) -> bool {
+    step.split_once("::STEP_")
+        .is_some_and(|(class, name)| !class.is_empty() && !name.is_empty())
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ProfileDbError {
     #[error("no profile named {0} could be found")]
@@ -275,6 +311,100 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn natives_are_split_by_load_stage() -> Result<(), Box<dyn Error>> {
+        let temp_dir = assert_fs::TempDir::new()?;
+        temp_dir.child("late.dll").touch()?;
+        temp_dir.child("early.dll").touch()?;
+        temp_dir.child("my-profile.me3").write_str(
+            r#"
+            profileVersion = "v1"
+
+            [[natives]]
+            path = "late.dll"
+            load_stage = { after_step = "CSRegulationStep::STEP_Idle" }
+
+            [[natives]]
+            path = "early.dll"
+            load_stage = "before_arxan"
+            "#,
+        )?;
+
+        let db = ProfileDb {
+            search_paths: vec![Box::from(temp_dir.path())],
+        };
+
+        let (natives, early_natives, _) = db.load("my-profile")?.compile()?;
+
+        assert_eq!(1, natives.len());
+        assert!(natives[0].path.ends_with("late.dll"));
+        assert_eq!(1, early_natives.len());
+        assert!(early_natives[0].path.ends_with("early.dll"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_native_loading_after_later_stage() -> Result<(), Box<dyn Error>> {
+        let temp_dir = assert_fs::TempDir::new()?;
+        temp_dir.child("late.dll").touch()?;
+        temp_dir.child("early.dll").touch()?;
+        temp_dir.child("my-profile.me3").write_str(
+            r#"
+            profileVersion = "v1"
+
+            [[natives]]
+            path = "late.dll"
+            load_stage = "after_file_init"
+
+            [[natives]]
+            path = "early.dll"
+            load_stage = "before_main"
+            load_after = [{ id = "late.dll", optional = false }]
+            "#,
+        )?;
+
+        let db = ProfileDb {
+            search_paths: vec![Box::from(temp_dir.path())],
+        };
+
+        assert!(db.load("my-profile")?.compile().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn natives_depend_on_ids() -> Result<(), Box<dyn Error>> {
+        let temp_dir = assert_fs::TempDir::new()?;
+        temp_dir.child("dependent.dll").touch()?;
+        temp_dir.child("ModEngineExt.dll").touch()?;
+        temp_dir.child("my-profile.me3").write_str(
+            r#"
+            profileVersion = "v1"
+
+            [[natives]]
+            path = "dependent.dll"
+            load_after = [{ id = "my_extension", optional = false }]
+
+            [[natives]]
+            path = "ModEngineExt.dll"
+            id = "my_extension"
+            "#,
+        )?;
+
+        let db = ProfileDb {
+            search_paths: vec![Box::from(temp_dir.path())],
+        };
+
+        let (natives, _, _) = db.load("my-profile")?.compile()?;
+
+        assert_eq!(2, natives.len());
+        assert!(natives[0].path.ends_with("ModEngineExt.dll"));
+        assert!(natives[1].path.ends_with("dependent.dll"));
+
+        Ok(())
+    }
+
     #[test]
     fn load_absolute_me3_file() -> Result<(), Box<dyn Error>> {
         let db = ProfileDb {
@@ -6,7 +6,10 @@ use std::{
 };
 
 use clap::{builder::PossibleValue, ArgAction, Parser, ValueEnum};
-use commands::{profile::ProfileCommands, Commands};
+use commands::{
+    assets::AssetsCommands, cache::CacheCommands, profile::ProfileCommands, trace::TraceCommands,
+    Commands,
+};
 use me3_telemetry::TelemetryConfig;
 use serde::{Deserialize, Serialize};
 use strum::VariantArray;
@@ -14,6 +17,7 @@ use tracing::{debug, info};
 
 mod commands;
 pub mod db;
+mod native_check;
 pub mod output;
 
 #[derive(Parser)]
@@ -124,7 +128,9 @@ fn main() {
     let telemetry_config = TelemetryConfig::default()
         .enabled(config.options.crash_reporting.unwrap_or(false))
         .with_console_writer(stderr)
-        .with_file_writer(tmp_log_file);
+        .with_file_writer(tmp_log_file)
+        .with_file_format(config.options.log_format.unwrap_or_default())
+        .with_process("cli");
 
     let _telemetry_guard = me3_telemetry::install(telemetry_config);
 
@@ -143,6 +149,22 @@ fn main() {
         Commands::Profile(ProfileCommands::Create(args)) => commands::profile::create(config, args),
         Commands::Profile(ProfileCommands::List) => commands::profile::list(db),
         Commands::Profile(ProfileCommands::Show(name)) => commands::profile::show(db, config, name),
+        Commands::Profile(ProfileCommands::Check(name)) => {
+            commands::profile::check(db, config, name)
+        }
+        Commands::Profile(ProfileCommands::Conflicts(name)) => {
+            commands::profile::conflicts(db, config, name)
+        }
+        Commands::Logs(args) => commands::logs::logs(db, args),
+        Commands::ReloadNative(args) => commands::reload_native::reload_native(config, args),
+        Commands::Trace(TraceCommands::Top(args)) => commands::trace::top(db, args),
+        Commands::Trace(TraceCommands::Unused(args)) => commands::trace::unused(db, args),
+        Commands::Trace(TraceCommands::Missed(args)) => commands::trace::missed(db, args),
+        Commands::Assets(AssetsCommands::Ls(args)) => commands::assets::ls(db, config, args),
+        Commands::Assets(AssetsCommands::Find(args)) => commands::assets::find(db, config, args),
+        Commands::Analyze(args) => commands::analyze::analyze(args),
+        Commands::Cache(CacheCommands::Warm(args)) => commands::cache::warm(config, args),
+        Commands::BugReport(args) => commands::bug_report::bug_report(db, config, args),
         #[cfg(target_os = "windows")]
         Commands::AddToPath => commands::windows::add_to_path(),
         #[cfg(target_os = "windows")]
@@ -0,0 +1,301 @@
+//! Checks native DLLs for problems that would otherwise only show up once the game tries to load
+//! them.
+
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use me3_mod_protocol::{
+    dependency::Dependency,
+    native::{Native, NativeInitializerCondition},
+};
+use pelite::{
+    image::{IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_I386},
+    pe64::{exports::GetProcAddress, Pe},
+    PeFile, Wrap,
+};
+use thiserror::Error;
+
+/// DLLs that ship with Windows (and Wine), which natives can import without bundling them.
+const SYSTEM_DLLS: &[&str] = &[
+    "advapi32.dll",
+    "bcrypt.dll",
+    "comctl32.dll",
+    "comdlg32.dll",
+    "crypt32.dll",
+    "d3d11.dll",
+    "d3d12.dll",
+    "d3dcompiler_47.dll",
+    "dbghelp.dll",
+    "dinput8.dll",
+    "dwmapi.dll",
+    "dxgi.dll",
+    "gdi32.dll",
+    "hid.dll",
+    "imm32.dll",
+    "iphlpapi.dll",
+    "kernel32.dll",
+    "kernelbase.dll",
+    "msvcrt.dll",
+    "ntdll.dll",
+    "ole32.dll",
+    "oleaut32.dll",
+    "psapi.dll",
+    "setupapi.dll",
+    "shell32.dll",
+    "shlwapi.dll",
+    "ucrtbase.dll",
+    "user32.dll",
+    "userenv.dll",
+    "uxtheme.dll",
+    "version.dll",
+    "winhttp.dll",
+    "wininet.dll",
+    "winmm.dll",
+    "ws2_32.dll",
+    "xinput1_3.dll",
+    "xinput1_4.dll",
+];
+
+/// Prefixes of the DLLs installed by the Microsoft Visual C++ Redistributable, which may be
+/// missing even though natives commonly import them.
+const VC_REDIST_DLL_PREFIXES: &[&str] = &["concrt140", "msvcp140", "vccorlib140", "vcruntime140"];
+
+#[derive(Debug, Error)]
+pub enum NativeProblem {
+    #[error("the DLL can't be read: {0}")]
+    Unreadable(#[from] io::Error),
+
+    #[error("not a valid DLL: {0}")]
+    InvalidImage(#[from] pelite::Error),
+
+    #[error("built for {0}, but the game is x64")]
+    WrongMachine(&'static str),
+
+    #[error("{kind} `{symbol}` is not exported by the DLL")]
+    MissingExport { kind: &'static str, symbol: String },
+
+    #[error("imports {0}, which is not a system DLL and is not next to the native or the game")]
+    MissingImport(String),
+
+    #[error(
+        "imports {0}, which comes with the Microsoft Visual C++ Redistributable and wasn't found"
+    )]
+    MissingRedistributable(String),
+
+    #[error("same DLL as {0}")]
+    Duplicate(String),
+}
+
+impl NativeProblem {
+    /// Will this problem keep the native from loading?
+    ///
+    /// Imports are resolved at runtime from places that can't all be checked ahead of time, so
+    /// missing ones are only reported.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(
+            self,
+            Self::MissingImport(_) | Self::MissingRedistributable(_) | Self::Duplicate(_)
+        )
+    }
+}
+
+pub struct NativeReport<'a> {
+    pub native: &'a Native,
+    pub problems: Vec<NativeProblem>,
+}
+
+/// Checks each native, looking for imported DLLs next to the native and in `game_dir` if given.
+pub fn check_natives<'a>(
+    natives: impl IntoIterator<Item = &'a Native>,
+    game_dir: Option<&Path>,
+) -> Vec<NativeReport<'a>> {
+    let mut ids_by_path = HashMap::<PathBuf, String>::new();
+
+    natives
+        .into_iter()
+        .map(|native| {
+            let mut problems = check_native(native, game_dir);
+
+            let path = native
+                .path
+                .canonicalize()
+                .unwrap_or_else(|_| native.path.to_path_buf());
+
+            match ids_by_path.entry(path) {
+                Entry::Occupied(entry) => {
+                    problems.push(NativeProblem::Duplicate(entry.get().clone()))
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(native.id());
+                }
+            }
+
+            NativeReport { native, problems }
+        })
+        .collect()
+}
+
+fn check_native(native: &Native, game_dir: Option<&Path>) -> Vec<NativeProblem> {
+    let image = match fs::read(&*native.path) {
+        Ok(image) => image,
+        Err(e) => return vec![e.into()],
+    };
+
+    let file = match PeFile::from_bytes(&image) {
+        Ok(Wrap::T64(file)) => file,
+        Ok(Wrap::T32(_)) => return vec![NativeProblem::WrongMachine("x86")],
+        Err(e) => return vec![e.into()],
+    };
+
+    match file.file_header().Machine {
+        IMAGE_FILE_MACHINE_AMD64 => {}
+        IMAGE_FILE_MACHINE_I386 => return vec![NativeProblem::WrongMachine("x86")],
+        _ => return vec![NativeProblem::WrongMachine("another architecture")],
+    }
+
+    let mut problems = vec![];
+
+    let initializer = match &native.initializer {
+        Some(NativeInitializerCondition::Function(symbol)) => Some(symbol),
+        _ => None,
+    };
+
+    for (kind, symbol) in [
+        ("initializer", initializer),
+        ("finalizer", native.finalizer.as_ref()),
+    ] {
+        if let Some(symbol) = symbol
+            && file.get_export(symbol.as_str()).is_err()
+        {
+            problems.push(NativeProblem::MissingExport {
+                kind,
+                symbol: symbol.clone(),
+            });
+        }
+    }
+
+    let search_dirs = native.path.parent().into_iter().chain(game_dir);
+    let available = search_dirs.flat_map(dll_names).collect::<HashSet<_>>();
+
+    // A DLL without an import directory has nothing to resolve.
+    let Ok(imports) = file.imports() else {
+        return problems;
+    };
+
+    for desc in imports {
+        let Ok(dll_name) = desc.dll_name() else {
+            continue;
+        };
+
+        let dll_name = dll_name.to_string().to_lowercase();
+
+        if is_system_dll(&dll_name) || available.contains(&dll_name) {
+            continue;
+        }
+
+        if is_vc_redist_dll(&dll_name) {
+            problems.push(NativeProblem::MissingRedistributable(dll_name));
+        } else {
+            problems.push(NativeProblem::MissingImport(dll_name));
+        }
+    }
+
+    problems
+}
+
+/// Lowercase names of the DLLs in `dir`.
+fn dll_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+        .filter(|name| name.ends_with(".dll"))
+        .collect()
+}
+
+fn is_system_dll(dll_name: &str) -> bool {
+    if dll_name.starts_with("api-ms-win-") || dll_name.starts_with("ext-ms-") {
+        return true;
+    }
+
+    if SYSTEM_DLLS.contains(&dll_name) {
+        return true;
+    }
+
+    system_dir().is_some_and(|dir| dir.join(dll_name).is_file())
+}
+
+fn is_vc_redist_dll(dll_name: &str) -> bool {
+    VC_REDIST_DLL_PREFIXES
+        .iter()
+        .any(|prefix| dll_name.starts_with(prefix))
+}
+
+#[cfg(target_os = "windows")]
+fn system_dir() -> Option<PathBuf> {
+    let system_root = std::env::var_os("SystemRoot")?;
+    Some(PathBuf::from(system_root).join("System32"))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_dir() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use assert_fs::prelude::*;
+    use me3_mod_protocol::native::Native;
+
+    use super::{check_natives, is_system_dll, is_vc_redist_dll, NativeProblem};
+
+    #[test]
+    fn reports_invalid_and_duplicate_natives() -> Result<(), Box<dyn Error>> {
+        let temp_dir = assert_fs::TempDir::new()?;
+        temp_dir.child("not_a_dll.dll").write_str("MZ")?;
+
+        let native = Native::new(temp_dir.child("not_a_dll.dll").path());
+        let duplicate = Native::new(temp_dir.child("./not_a_dll.dll").path());
+        let missing = Native::new(temp_dir.child("missing.dll").path());
+
+        let natives = [native, duplicate, missing];
+        let reports = check_natives(&natives, None);
+
+        assert!(matches!(
+            reports[0].problems.as_slice(),
+            [NativeProblem::InvalidImage(_)]
+        ));
+        assert!(matches!(
+            reports[1].problems.as_slice(),
+            [NativeProblem::InvalidImage(_), NativeProblem::Duplicate(id)] if id == "not_a_dll.dll"
+        ));
+        assert!(matches!(
+            reports[2].problems.as_slice(),
+            [NativeProblem::Unreadable(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn api_sets_are_system_dlls() {
+        assert!(is_system_dll("api-ms-win-crt-runtime-l1-1-0.dll"));
+        assert!(is_system_dll("kernel32.dll"));
+        assert!(!is_system_dll("my_dependency.dll"));
+    }
+
+    #[test]
+    fn redistributable_dlls_are_recognized() {
+        assert!(is_vc_redist_dll("msvcp140_1.dll"));
+        assert!(is_vc_redist_dll("vcruntime140.dll"));
+        assert!(!is_vc_redist_dll("kernel32.dll"));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, path::PathBuf, process::Command};
+use std::{collections::HashMap, fmt, path::PathBuf, process::Command, str::FromStr};
 
 use me3_mod_protocol::Game;
 use serde::{de::value::MapDeserializer, Deserialize, Serialize};
@@ -17,6 +17,30 @@ pub struct TelemetryVars {
     pub monitor_pipe_path: PathBuf,
 
     pub trace_id: Option<String>,
+
+    /// Format of the records written to `log_file_path`.
+    #[serde(default)]
+    pub log_format: LogFormat,
+
+    /// Extra log filter directives (in `EnvFilter` syntax) applied on top of the defaults.
+    #[serde(default)]
+    pub log_filter: Option<String>,
+
+    /// Pipe the CLI writes new log filters to while the game is running.
+    #[serde(default)]
+    pub control_pipe_path: Option<PathBuf>,
+}
+
+/// Format of the records written to log files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable, free-form text.
+    #[default]
+    Text,
+
+    /// One JSON object per line.
+    Json,
 }
 
 impl EnvVars for TelemetryVars {
@@ -48,6 +72,29 @@ impl EnvVars for LauncherVars {
     const PREFIX: &'static str = "ME3_LAUNCHER_";
 }
 
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown log format {s:?}, expected \"text\" or \"json\""
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => f.write_str("text"),
+            Self::Json => f.write_str("json"),
+        }
+    }
+}
+
 pub trait CommandExt {
     fn with_env_vars(&mut self, vars: impl EnvVars + Serialize) -> &mut Self;
 }
@@ -0,0 +1,99 @@
+#ifndef ME3_H
+#define ME3_H
+
+
+/* This file is generated from crates/ext, do not edit it by hand. */
+
+#include <stdbool.h>
+#include <stdint.h>
+#if defined(__cplusplus)
+#define ME3_EXPORT extern "C" __declspec(dllexport)
+#else
+#define ME3_EXPORT __declspec(dllexport)
+#endif
+
+
+// The version of the `Me3Api` table described here.
+//
+// Functions are only ever appended to the table, and each addition bumps the version. A native
+// must not call a function that was added in a later version than the one it was handed.
+#define ME3_API_VERSION 1
+
+// The verbosity of a message logged through `Me3Api::log`.
+typedef enum Me3LogLevel {
+  ME3_LOG_LEVEL_ERROR = 0,
+  ME3_LOG_LEVEL_WARN = 1,
+  ME3_LOG_LEVEL_INFO = 2,
+  ME3_LOG_LEVEL_DEBUG = 3,
+  ME3_LOG_LEVEL_TRACE = 4,
+} Me3LogLevel;
+
+// The outcome of a `Me3Api` call.
+typedef enum Me3Result {
+  ME3_RESULT_OK = 0,
+  // A pointer was null or a string wasn't valid UTF-8.
+  ME3_RESULT_INVALID_ARGUMENT = 1,
+  // me3 couldn't carry out the call. The reason is written to the me3 log.
+  ME3_RESULT_FAILED = 2,
+} Me3Result;
+
+// A game supported by me3.
+typedef enum Me3Game {
+  ME3_GAME_DARK_SOULS3 = 0,
+  ME3_GAME_SEKIRO = 1,
+  ME3_GAME_ELDEN_RING = 2,
+  ME3_GAME_ARMORED_CORE6 = 3,
+  ME3_GAME_NIGHTREIGN = 4,
+} Me3Game;
+
+// The regional release of a game executable.
+typedef enum Me3Region {
+  ME3_REGION_WORLDWIDE = 0,
+  ME3_REGION_JAPAN = 1,
+} Me3Region;
+
+// The game me3 is attached to, as filled in by `Me3Api::game_info`.
+typedef struct Me3GameInfo {
+  enum Me3Game game;
+  // The product name from the executable's version resource.
+  const char *product;
+  uint16_t major;
+  uint16_t minor;
+  uint16_t patch;
+  uint16_t build;
+  enum Me3Region region;
+} Me3GameInfo;
+
+// The table of functions me3 exposes to natives.
+//
+// All strings are nul-terminated UTF-8.
+typedef struct Me3Api {
+  // Logs `message` into the me3 log with the given `target`, e.g. the name of the native.
+  void (*log)(enum Me3LogLevel level, const char *target, const char *message);
+  // Fills `info` with the game me3 is attached to and its executable version.
+  //
+  // `info->product` points to a string that lives until the game exits.
+  enum Me3Result (*game_info)(struct Me3GameInfo *info);
+  // Serves the file at `disk_path` whenever the game opens `vfs_path`, as if it had been
+  // placed in a package.
+  //
+  // `vfs_path` is relative to the game's virtual filesystem roots, e.g. `regulation.bin` or
+  // `parts/am_f_0000.partsbnd.dcx`. Overrides added later take priority over packages.
+  enum Me3Result (*add_file_override)(const char *vfs_path, const char *disk_path);
+  // Detours the function at `target` to `detour` and writes a pointer that calls the original
+  // function to `trampoline`.
+  //
+  // Detours installed through me3 are tracked alongside its own, so they show up in the
+  // launch summary, and are removed before the native is unloaded or reloaded.
+  enum Me3Result (*hook)(const void *target, const void *detour, const void **trampoline);
+  // Forces the game property `property` (e.g. `Menu.IsEnableOnlineMode`) to `state`.
+  enum Me3Result (*override_game_property)(const char *property, bool state);
+} Me3Api;
+
+/*
+ * Exported by natives that use the extension API. Called once, after the native is loaded and
+ * configured and before its initializer runs. `api` stays valid until the game exits.
+ */
+ME3_EXPORT bool me3_ext_init(const Me3Api *api, uint32_t version);
+
+#endif /* ME3_H */
@@ -0,0 +1,395 @@
+//! The me3 extension API, a versioned C ABI that me3 hands to native mods.
+//!
+//! A native opts in by exporting `me3_ext_init`, which is called with a pointer to a [`Me3Api`]
+//! table after the native is loaded and configured, and before its initializer runs. The table
+//! stays valid until the game exits, so natives may keep it around.
+//!
+//! Natives written in C or C++ can use the generated `include/me3.h` header. Natives written in
+//! Rust can use [`Me3`] for a safe wrapper around the table:
+//!
+//! ```no_run
+//! use me3_ext::{Me3, Me3Api, Me3LogLevel};
+//!
+//! #[unsafe(no_mangle)]
+//! unsafe extern "C" fn me3_ext_init(api: *const Me3Api, version: u32) -> bool {
+//!     let Some(me3) = (unsafe { Me3::from_raw(api, version) }) else {
+//!         return false;
+//!     };
+//!
+//!     me3.log(Me3LogLevel::Info, "my_native", "hello from my native");
+//!     me3.add_file_override("regulation.bin", "C:/mods/my-mod/regulation.bin")
+//!         .is_ok()
+//! }
+//! ```
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    mem,
+    path::Path,
+    ptr,
+};
+
+use thiserror::Error;
+
+/// The version of the `Me3Api` table described here.
+///
+/// Functions are only ever appended to the table, and each addition bumps the version. A native
+/// must not call a function that was added in a later version than the one it was handed.
+pub const ME3_API_VERSION: u32 = 1;
+
+/// The name of the export me3 looks for in natives.
+pub const ME3_EXT_INIT_SYMBOL: &str = "me3_ext_init";
+
+/// The signature of the `me3_ext_init` export.
+///
+/// Returning `false` reports that the native failed to initialize.
+pub type Me3ExtInit = unsafe extern "C" fn(api: *const Me3Api, version: u32) -> bool;
+
+/// The table of functions me3 exposes to natives.
+///
+/// All strings are nul-terminated UTF-8.
+#[repr(C)]
+pub struct Me3Api {
+    /// Logs `message` into the me3 log with the given `target`, e.g. the name of the native.
+    pub log:
+        unsafe extern "C" fn(level: Me3LogLevel, target: *const c_char, message: *const c_char),
+
+    /// Fills `info` with the game me3 is attached to and its executable version.
+    ///
+    /// `info->product` points to a string that lives until the game exits.
+    pub game_info: unsafe extern "C" fn(info: *mut Me3GameInfo) -> Me3Result,
+
+    /// Serves the file at `disk_path` whenever the game opens `vfs_path`, as if it had been
+    /// placed in a package.
+    ///
+    /// `vfs_path` is relative to the game's virtual filesystem roots, e.g. `regulation.bin` or
+    /// `parts/am_f_0000.partsbnd.dcx`. Overrides added later take priority over packages.
+    pub add_file_override:
+        unsafe extern "C" fn(vfs_path: *const c_char, disk_path: *const c_char) -> Me3Result,
+
+    /// Detours the function at `target` to `detour` and writes a pointer that calls the original
+    /// function to `trampoline`.
+    ///
+    /// Detours installed through me3 are tracked alongside its own, so they show up in the
+    /// launch summary, and are removed before the native is unloaded or reloaded.
+    pub hook: unsafe extern "C" fn(
+        target: *const c_void,
+        detour: *const c_void,
+        trampoline: *mut *const c_void,
+    ) -> Me3Result,
+
+    /// Forces the game property `property` (e.g. `Menu.IsEnableOnlineMode`) to `state`.
+    pub override_game_property:
+        unsafe extern "C" fn(property: *const c_char, state: bool) -> Me3Result,
+}
+
+/// The outcome of a `Me3Api` call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Me3Result {
+    Ok = 0,
+    /// A pointer was null or a string wasn't valid UTF-8.
+    InvalidArgument = 1,
+    /// me3 couldn't carry out the call. The reason is written to the me3 log.
+    Failed = 2,
+}
+
+/// The verbosity of a message logged through `Me3Api::log`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Me3LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+/// A game supported by me3.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Me3Game {
+    DarkSouls3 = 0,
+    Sekiro = 1,
+    EldenRing = 2,
+    ArmoredCore6 = 3,
+    Nightreign = 4,
+}
+
+/// The regional release of a game executable.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Me3Region {
+    Worldwide = 0,
+    Japan = 1,
+}
+
+/// The game me3 is attached to, as filled in by `Me3Api::game_info`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Me3GameInfo {
+    pub game: Me3Game,
+    /// The product name from the executable's version resource.
+    pub product: *const c_char,
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub build: u16,
+    pub region: Me3Region,
+}
+
+/// A safe wrapper around the [`Me3Api`] table for natives written in Rust.
+#[derive(Clone, Copy)]
+pub struct Me3 {
+    api: &'static Me3Api,
+    version: u32,
+}
+
+/// The game me3 is attached to.
+#[derive(Clone, Debug)]
+pub struct GameInfo {
+    pub game: Me3Game,
+    pub product: String,
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub build: u16,
+    pub region: Me3Region,
+}
+
+#[derive(Debug, Error)]
+pub enum Me3Error {
+    #[error("me3 rejected an argument")]
+    InvalidArgument,
+
+    #[error("me3 failed to carry out the call (see the me3 log)")]
+    Failed,
+
+    #[error("string contains a nul byte")]
+    Nul(#[from] std::ffi::NulError),
+
+    #[error("path is not valid UTF-8")]
+    NonUtf8Path,
+}
+
+impl Me3 {
+    /// Wraps the arguments of `me3_ext_init`.
+    ///
+    /// Returns `None` if `api` is null or the host implements an older version of the API than
+    /// this crate.
+    ///
+    /// # Safety
+    ///
+    /// `api` must be the pointer me3 passed to `me3_ext_init`.
+    pub unsafe fn from_raw(api: *const Me3Api, version: u32) -> Option<Self> {
+        if version < ME3_API_VERSION {
+            return None;
+        }
+
+        let api = unsafe { api.as_ref()? };
+
+        Some(Self { api, version })
+    }
+
+    /// The version of the API implemented by the host.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Logs `message` into the me3 log with the given `target`.
+    ///
+    /// Nul bytes in `target` and `message` are replaced, since they can't be passed to me3.
+    pub fn log(&self, level: Me3LogLevel, target: &str, message: &str) {
+        let target = lossy_c_string(target);
+        let message = lossy_c_string(message);
+
+        unsafe { (self.api.log)(level, target.as_ptr(), message.as_ptr()) }
+    }
+
+    pub fn game_info(&self) -> Result<GameInfo, Me3Error> {
+        let mut info = Me3GameInfo {
+            game: Me3Game::DarkSouls3,
+            product: ptr::null(),
+            major: 0,
+            minor: 0,
+            patch: 0,
+            build: 0,
+            region: Me3Region::Worldwide,
+        };
+
+        check(unsafe { (self.api.game_info)(&mut info) })?;
+
+        let product = if info.product.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(info.product) }
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        Ok(GameInfo {
+            game: info.game,
+            product,
+            major: info.major,
+            minor: info.minor,
+            patch: info.patch,
+            build: info.build,
+            region: info.region,
+        })
+    }
+
+    /// Serves the file at `disk_path` whenever the game opens `vfs_path`.
+    pub fn add_file_override<P: AsRef<Path>>(
+        &self,
+        vfs_path: &str,
+        disk_path: P,
+    ) -> Result<(), Me3Error> {
+        let disk_path = disk_path.as_ref().to_str().ok_or(Me3Error::NonUtf8Path)?;
+
+        let vfs_path = CString::new(vfs_path)?;
+        let disk_path = CString::new(disk_path)?;
+
+        check(unsafe { (self.api.add_file_override)(vfs_path.as_ptr(), disk_path.as_ptr()) })
+    }
+
+    /// Detours `target` to `detour`, returning a function pointer that calls the original.
+    ///
+    /// # Safety
+    ///
+    /// `F` must be a function pointer type matching the signature of `target`, and `detour` must
+    /// be safe to call wherever the game calls `target`.
+    ///
+    /// # Panics
+    ///
+    /// If `F` is not pointer sized.
+    pub unsafe fn hook<F: Copy>(&self, target: F, detour: F) -> Result<F, Me3Error> {
+        assert_eq!(mem::size_of::<F>(), mem::size_of::<*const c_void>());
+
+        let mut trampoline = ptr::null();
+
+        unsafe {
+            check((self.api.hook)(
+                mem::transmute_copy(&target),
+                mem::transmute_copy(&detour),
+                &mut trampoline,
+            ))?;
+
+            Ok(mem::transmute_copy(&trampoline))
+        }
+    }
+
+    /// Forces the game property `property` to `state`.
+    pub fn override_game_property(&self, property: &str, state: bool) -> Result<(), Me3Error> {
+        let property = CString::new(property)?;
+
+        check(unsafe { (self.api.override_game_property)(property.as_ptr(), state) })
+    }
+}
+
+fn check(result: Me3Result) -> Result<(), Me3Error> {
+    match result {
+        Me3Result::Ok => Ok(()),
+        Me3Result::InvalidArgument => Err(Me3Error::InvalidArgument),
+        Me3Result::Failed => Err(Me3Error::Failed),
+    }
+}
+
+fn lossy_c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "\u{FFFD}")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{c_char, c_void, CStr},
+        sync::Mutex,
+    };
+
+    use super::{Me3, Me3Api, Me3Error, Me3GameInfo, Me3LogLevel, Me3Result, ME3_API_VERSION};
+
+    static LOGGED: Mutex<Vec<(Me3LogLevel, String, String)>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn log(level: Me3LogLevel, target: *const c_char, message: *const c_char) {
+        let (target, message) = unsafe { (CStr::from_ptr(target), CStr::from_ptr(message)) };
+
+        LOGGED.lock().unwrap().push((
+            level,
+            target.to_string_lossy().into_owned(),
+            message.to_string_lossy().into_owned(),
+        ));
+    }
+
+    unsafe extern "C" fn game_info(_: *mut Me3GameInfo) -> Me3Result {
+        Me3Result::Failed
+    }
+
+    unsafe extern "C" fn add_file_override(_: *const c_char, _: *const c_char) -> Me3Result {
+        Me3Result::Ok
+    }
+
+    unsafe extern "C" fn hook(
+        _: *const c_void,
+        detour: *const c_void,
+        trampoline: *mut *const c_void,
+    ) -> Me3Result {
+        unsafe { *trampoline = detour };
+        Me3Result::Ok
+    }
+
+    unsafe extern "C" fn override_game_property(_: *const c_char, _: bool) -> Me3Result {
+        Me3Result::InvalidArgument
+    }
+
+    static API: Me3Api = Me3Api {
+        log,
+        game_info,
+        add_file_override,
+        hook,
+        override_game_property,
+    };
+
+    extern "C" fn answer() -> i32 {
+        42
+    }
+
+    extern "C" fn question() -> i32 {
+        0
+    }
+
+    #[test]
+    fn rejects_older_hosts() {
+        assert!(unsafe { Me3::from_raw(&API, ME3_API_VERSION - 1) }.is_none());
+        assert!(unsafe { Me3::from_raw(std::ptr::null(), ME3_API_VERSION) }.is_none());
+    }
+
+    #[test]
+    fn wraps_api_table() {
+        let me3 = unsafe { Me3::from_raw(&API, ME3_API_VERSION) }.unwrap();
+
+        me3.log(Me3LogLevel::Warn, "native", "a\0b");
+        assert_eq!(
+            LOGGED.lock().unwrap().as_slice(),
+            [(
+                Me3LogLevel::Warn,
+                "native".to_owned(),
+                "a\u{FFFD}b".to_owned()
+            )]
+        );
+
+        let trampoline = unsafe { me3.hook::<extern "C" fn() -> i32>(question, answer) }.unwrap();
+        assert_eq!(trampoline(), 42);
+
+        assert!(me3
+            .add_file_override("regulation.bin", "regulation.bin")
+            .is_ok());
+        assert!(matches!(me3.game_info(), Err(Me3Error::Failed)));
+        assert!(matches!(
+            me3.override_game_property("Menu.IsEnableOnlineMode", true),
+            Err(Me3Error::InvalidArgument)
+        ));
+        assert!(matches!(
+            me3.add_file_override("a\0b", "c"),
+            Err(Me3Error::Nul(_))
+        ));
+    }
+}
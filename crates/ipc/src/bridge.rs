@@ -1,25 +1,21 @@
-use std::{
-    ffi::c_void, io, mem, panic::UnwindSafe, process::Command, ptr::NonNull, sync::LazyLock,
-};
+use std::{mem, panic::UnwindSafe, process::Command, sync::LazyLock, time::Duration};
 
-use me3_env::{deserialize_from_env, serialize_into_command, EnvVars};
+use me3_env::{deserialize_from_env, serialize_into_command};
+use me3_launcher_attach_protocol::event::HostEvent;
 use rkyv::rancor;
-use windows::{
-    core::{Error as WinError, BOOL, PCWSTR},
-    Win32::{
-        Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE},
-        Security::SECURITY_ATTRIBUTES,
-        System::Memory::{
-            CreateFileMappingW, MapViewOfFile, UnmapViewOfFile, FILE_MAP_ALL_ACCESS,
-            MEMORY_MAPPED_VIEW_ADDRESS, PAGE_READWRITE,
-        },
-    },
-};
 
 pub(crate) use crate::bridge::channel::SendError;
-pub use crate::bridge::channel::{RecvError, RecvSpanGuard, SpanError};
+pub use crate::bridge::{
+    channel::{RecvError, RecvSpanGuard, SpanError},
+    log::{LogKind, LogWriter, OverflowPolicy, OverflowStats},
+    platform::OsError,
+};
 use crate::{
-    bridge::shared::SharedBridge,
+    bridge::{
+        log::OverflowCounters,
+        platform::{FileMapping, FileView, SharedMapping, SharedView},
+        shared::SharedBridge,
+    },
     message::{MsgToChild, MsgToParent},
     request::{
         convert::{ConvertRequest, ConvertResponse},
@@ -29,6 +25,8 @@ use crate::{
 
 mod buffer;
 mod channel;
+mod log;
+mod platform;
 mod rel;
 mod shared;
 mod signal;
@@ -58,7 +56,7 @@ pub struct BridgeToChild {
 #[derive(Clone, Debug, thiserror::Error)]
 pub enum BridgeError {
     #[error(transparent)]
-    Os(#[from] WinError),
+    Os(#[from] OsError),
 
     #[error("JSON deserialization error: {0}")]
     Json(Box<str>),
@@ -67,12 +65,6 @@ pub enum BridgeError {
     Size(u32),
 }
 
-#[derive(Clone)]
-pub struct LogWriter<F: Fn(Box<str>) -> MsgToParent + Clone> {
-    bridge: BridgeToParent,
-    to_msg: F,
-}
-
 /// Opens the child end of the IPC bridge to the parent process.
 ///
 /// Can be cheaply called any number of times, but must be preceded by
@@ -109,13 +101,14 @@ pub fn to_child(size_mb: u32, command: &mut Command) -> Result<BridgeToChild, Br
 
     let size = size_of::<SharedBridge>() + size_mb as usize * 1024 * 1024;
 
-    let file_mapping = FileMapping::open(size)?;
+    let file_mapping = FileMapping::create(size)?;
     let file_view = file_mapping.to_view()?;
 
     // SAFETY: `file_view` is a contiguous slice of shared memory.
     let shared =
         unsafe { SharedBridge::new_in(file_view.as_slice()).ok_or(BridgeError::Size(size_mb))? };
 
+    file_mapping.share_with(command);
     serialize_into_command(&file_mapping, command);
 
     Ok(BridgeToChild {
@@ -131,13 +124,8 @@ impl BridgeToParent {
     ///
     /// The writer will error if it receives invalid UTF-8.
     #[inline]
-    pub fn console_log_writer(
-        &self,
-    ) -> LogWriter<impl Fn(Box<str>) -> MsgToParent + Clone + 'static> {
-        LogWriter {
-            bridge: self.clone(),
-            to_msg: MsgToParent::ConsoleLog,
-        }
+    pub fn console_log_writer(&self) -> LogWriter {
+        LogWriter::new(self.clone(), LogKind::Console)
     }
 
     /// Returns a writer implementing [`io::Write`] for sending [`MsgToParent::FileLog`]
@@ -145,11 +133,8 @@ impl BridgeToParent {
     ///
     /// The writer will error if it receives invalid UTF-8.
     #[inline]
-    pub fn file_log_writer(&self) -> LogWriter<impl Fn(Box<str>) -> MsgToParent + Clone + 'static> {
-        LogWriter {
-            bridge: self.clone(),
-            to_msg: MsgToParent::FileLog,
-        }
+    pub fn file_log_writer(&self) -> LogWriter {
+        LogWriter::new(self.clone(), LogKind::File)
     }
 
     /// Fulfill a RPC request from the parent process with the appropriate function.
@@ -170,6 +155,29 @@ impl BridgeToParent {
         self.shared.to_parent.send::<_, rancor::Error>(msg)
     }
 
+    /// Like [`BridgeToParent::send`], but gives up if the message can't be written within
+    /// `timeout`.
+    pub fn send_timeout(&self, msg: MsgToParent, timeout: Duration) -> Result<(), SendError> {
+        self.shared
+            .to_parent
+            .send_timeout::<_, rancor::Error>(msg, timeout)
+    }
+
+    /// The largest serialized message that can be sent to the parent process.
+    pub fn max_message_len(&self) -> usize {
+        self.shared.to_parent.max_message_len()
+    }
+
+    pub(crate) fn overflow_counters(&self, kind: LogKind) -> &OverflowCounters {
+        &self.shared.overflow[kind as usize]
+    }
+
+    /// Sends a [`MsgToParent::Event`] to the parent process.
+    #[inline]
+    pub fn send_event(&self, event: HostEvent) -> Result<(), SendError> {
+        self.send(MsgToParent::Event(event))
+    }
+
     /// Receive messages from the parent process.
     ///
     /// Only one thread can be in this span at a time.
@@ -211,112 +219,10 @@ impl BridgeToChild {
     ) -> Result<RecvSpanGuard<'_, MsgToParent, rancor::Error>, SpanError> {
         self.shared.to_parent.enter_recv_span()
     }
-}
-
-impl<F: Fn(Box<str>) -> MsgToParent + Clone> io::Write for LogWriter<F> {
-    #[inline]
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let str = str::from_utf8(buf).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
-
-        self.bridge
-            .send((self.to_msg)(str.into()))
-            .map_err(io::Error::other)?;
-
-        Ok(str.len())
-    }
 
-    #[inline]
-    fn flush(&mut self) -> io::Result<()> {
-        // Could send `MsgToParent::Flush` here but `recv_loop` already does so automatically.
-        Ok(())
-    }
-}
-
-#[repr(C)]
-#[derive(serde::Serialize, serde::Deserialize)]
-struct FileMapping {
-    handle: usize,
-    size: usize,
-}
-
-#[repr(C)]
-struct FileView {
-    ptr: NonNull<c_void>,
-    size: usize,
-}
-
-impl FileMapping {
-    fn open(size: usize) -> Result<Self, WinError> {
-        // `INHERIT_HANDLE` so the child process can use the handle directly.
-        let handle = unsafe {
-            CreateFileMappingW(
-                INVALID_HANDLE_VALUE,
-                INHERIT_HANDLE,
-                PAGE_READWRITE,
-                (size >> 32) as u32,
-                size as u32,
-                PCWSTR::null(),
-            )?
-        };
-
-        Ok(Self {
-            handle: handle.0 as usize,
-            size,
-        })
-    }
-
-    fn to_view(&self) -> Result<FileView, WinError> {
-        let size = self.size;
-
-        // Non-null or bail.
-        let ptr = unsafe {
-            NonNull::new(MapViewOfFile(self.handle(), FILE_MAP_ALL_ACCESS, 0, 0, size).Value)
-                .ok_or_else(WinError::from_thread)?
-        };
-
-        Ok(FileView { ptr, size })
-    }
-
-    fn handle(&self) -> HANDLE {
-        HANDLE(self.handle as *mut c_void)
-    }
-}
-
-impl FileView {
-    fn as_slice(&self) -> NonNull<[u8]> {
-        NonNull::slice_from_raw_parts(self.ptr.cast(), self.size)
-    }
-}
-
-const INHERIT_HANDLE: Option<*const SECURITY_ATTRIBUTES> = Some(&SECURITY_ATTRIBUTES {
-    nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
-    lpSecurityDescriptor: std::ptr::null_mut(),
-    bInheritHandle: BOOL(1),
-});
-
-impl EnvVars for FileMapping {
-    const PREFIX: &'static str = "ME3_BRIDGE_FILE_";
-}
-
-impl EnvVars for &FileMapping {
-    const PREFIX: &'static str = "ME3_BRIDGE_FILE_";
-}
-
-impl Drop for FileMapping {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = CloseHandle(self.handle());
-        }
-    }
-}
-
-impl Drop for FileView {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
-                Value: self.ptr.as_ptr(),
-            });
-        }
+    /// Number of log messages of `kind` the child process could not deliver as-is.
+    pub fn overflow_stats(&self, kind: LogKind) -> OverflowStats {
+        self.shared.overflow[kind as usize].stats()
     }
 }
 
@@ -107,6 +107,11 @@ impl BipBuffer {
         Some(value)
     }
 
+    /// The largest message (in bytes) that can be passed to [`Self::write`].
+    pub fn max_message_len(&self) -> usize {
+        (self.len as usize / 2).saturating_sub((LEB128_CAP + Self::ALIGN - 1) as usize)
+    }
+
     pub fn write(&self, bytes: &[u8]) -> Result<(), WriteError> {
         let len = bytes.len();
         let leb_len = size_of_leb128(len as u32);
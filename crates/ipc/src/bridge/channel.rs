@@ -3,6 +3,7 @@ use std::{
     marker::PhantomData,
     ptr::NonNull,
     sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
 };
 
 use rkyv::{
@@ -14,11 +15,11 @@ use rkyv::{
     util::AlignedVec,
     Archive, Deserialize, Serialize,
 };
-use windows::core::Error as WinError;
 
 use crate::{
     bridge::{
         buffer::{BipBuffer, WriteError},
+        platform::OsError,
         signal::{MpscSignal, SpmcSignal},
     },
     identity_hasher::IdentityBuildHasher,
@@ -40,7 +41,7 @@ pub struct RecvSpanGuard<'a, T, E> {
 #[derive(Debug, thiserror::Error)]
 pub enum SendError<E = rancor::Error> {
     #[error(transparent)]
-    Os(#[from] WinError),
+    Os(#[from] OsError),
 
     #[error("failed to send message: {0}")]
     Write(#[from] WriteError),
@@ -52,7 +53,7 @@ pub enum SendError<E = rancor::Error> {
 #[derive(Debug, thiserror::Error)]
 pub enum RecvError<E = rancor::Error> {
     #[error(transparent)]
-    Os(#[from] WinError),
+    Os(#[from] OsError),
 
     #[error("failed to deserialize message: {0}")]
     Deserialize(E),
@@ -91,11 +92,30 @@ impl Channel {
         E: Source,
     {
         // Push the message and let the receiver know.
-        self.push_msg(msg)?;
+        self.push_msg(msg, None)?;
         self.has_messages.notify()?;
         Ok(())
     }
 
+    /// Like [`Channel::send`], but fails with [`WriteError::Full`] if the message could not be
+    /// written within `timeout`.
+    ///
+    /// A zero `timeout` never blocks.
+    pub fn send_timeout<M, E>(&self, msg: M, timeout: Duration) -> Result<(), SendError<E>>
+    where
+        M: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, E>>,
+        E: Source,
+    {
+        self.push_msg(msg, Some(Instant::now() + timeout))?;
+        self.has_messages.notify()?;
+        Ok(())
+    }
+
+    /// The largest serialized message that can be sent through this channel.
+    pub fn max_message_len(&self) -> usize {
+        self.queue.max_message_len()
+    }
+
     /// Try to enter the single threaded recv span, which returns an error if
     /// another thread has already entered.
     pub fn enter_recv_span<T, E>(&self) -> Result<RecvSpanGuard<'_, T, E>, SpanError>
@@ -153,19 +173,26 @@ impl Channel {
         message
     }
 
-    fn push_msg<M, E>(&self, msg: M) -> Result<(), SendError<E>>
+    fn push_msg<M, E>(&self, msg: M, deadline: Option<Instant>) -> Result<(), SendError<E>>
     where
         M: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, E>>,
         E: Source,
     {
         let bytes = to_bytes::<E>(&msg).map_err(SendError::Serialize)?;
 
-        // Block until the message can be written.
+        // Block until the message can be written or the deadline passes.
         loop {
-            match self.queue.write(&bytes) {
-                Ok(()) => return Ok(()),
-                Err(WriteError::Full) => self.has_capacity.wait()?,
-                Err(e) => return Err(e.into()),
+            match (self.queue.write(&bytes), deadline) {
+                (Ok(()), _) => return Ok(()),
+                (Err(WriteError::Full), None) => self.has_capacity.wait()?,
+                (Err(WriteError::Full), Some(deadline)) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+
+                    if remaining.is_zero() || !self.has_capacity.wait_timeout(remaining)? {
+                        return Err(WriteError::Full.into());
+                    }
+                }
+                (Err(e), _) => return Err(e.into()),
             }
         }
     }
@@ -0,0 +1,339 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, Write as _},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bridge::{buffer::WriteError, channel::SendError, BridgeToParent},
+    message::MsgToParent,
+};
+
+/// Kinds of log messages sent through a [`LogWriter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogKind {
+    Console,
+    File,
+}
+
+/// What a [`LogWriter`] does with a message when the bridge is full.
+#[derive(Clone, Debug)]
+pub enum OverflowPolicy {
+    /// Wait for the parent process to catch up, dropping the message if it doesn't within
+    /// `timeout` (if any). This is the default, without a timeout.
+    Block { timeout: Option<Duration> },
+
+    /// Hold on to up to `backlog` messages and send them once there is room, dropping the oldest
+    /// ones when the backlog is exceeded.
+    DropOldest { backlog: usize },
+
+    /// Append messages that don't fit to a file.
+    SpillToFile(PathBuf),
+}
+
+/// Number of log messages of a [`LogKind`] that weren't delivered as-is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OverflowStats {
+    /// Messages that were lost.
+    pub dropped: u64,
+
+    /// Messages that were too long and were split into several.
+    pub chunked: u64,
+
+    /// Messages that were written to the spill file instead.
+    pub spilled: u64,
+}
+
+/// Per-kind counters kept in shared memory so the parent process can read them.
+#[repr(C)]
+#[derive(Default)]
+pub struct OverflowCounters {
+    dropped: AtomicU64,
+    chunked: AtomicU64,
+    spilled: AtomicU64,
+}
+
+/// Writer implementing [`io::Write`] that sends each write as a single log message.
+///
+/// Flushing it, or dropping its last clone, sends the messages held back by
+/// [`OverflowPolicy::DropOldest`], waiting up to a second for room and counting the ones that
+/// still don't fit as dropped.
+///
+/// See [`BridgeToParent::console_log_writer`] and [`BridgeToParent::file_log_writer`].
+#[derive(Clone)]
+pub struct LogWriter {
+    overflow: Arc<Overflow>,
+}
+
+struct Overflow {
+    bridge: BridgeToParent,
+    kind: LogKind,
+    policy: OverflowPolicy,
+    backlog: Mutex<VecDeque<Box<str>>>,
+    spill_file: Mutex<Option<File>>,
+}
+
+/// Leeway for the archived [`MsgToParent`] wrapping each chunk.
+const MSG_OVERHEAD: usize = 64;
+
+/// How long flushing a [`LogWriter`] waits for the parent process to make room for the backlog.
+const BACKLOG_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl LogKind {
+    pub(crate) const COUNT: usize = 2;
+
+    fn to_msg(self, s: Box<str>) -> MsgToParent {
+        match self {
+            LogKind::Console => MsgToParent::ConsoleLog(s),
+            LogKind::File => MsgToParent::FileLog(s),
+        }
+    }
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        Self::Block { timeout: None }
+    }
+}
+
+impl OverflowCounters {
+    pub(crate) fn stats(&self) -> OverflowStats {
+        OverflowStats {
+            dropped: self.dropped.load(Ordering::Relaxed),
+            chunked: self.chunked.load(Ordering::Relaxed),
+            spilled: self.spilled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl LogWriter {
+    pub(crate) fn new(bridge: BridgeToParent, kind: LogKind) -> Self {
+        Self {
+            overflow: Arc::new(Overflow {
+                bridge,
+                kind,
+                policy: OverflowPolicy::default(),
+                backlog: Mutex::default(),
+                spill_file: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Sets what to do with messages when the bridge is full.
+    pub fn with_overflow_policy(self, policy: OverflowPolicy) -> Self {
+        Self {
+            overflow: Arc::new(Overflow {
+                bridge: self.overflow.bridge.clone(),
+                kind: self.overflow.kind,
+                policy,
+                backlog: Mutex::default(),
+                spill_file: Mutex::default(),
+            }),
+        }
+    }
+
+    fn counters(&self) -> &OverflowCounters {
+        self.overflow.counters()
+    }
+
+    fn send_chunk(&self, chunk: &str) -> io::Result<()> {
+        let send = |chunk: Box<str>, timeout| {
+            let msg = self.overflow.kind.to_msg(chunk);
+
+            match timeout {
+                Some(timeout) => self.overflow.bridge.send_timeout(msg, timeout),
+                None => self.overflow.bridge.send(msg),
+            }
+        };
+
+        let result = match &self.overflow.policy {
+            OverflowPolicy::Block { timeout } => send(chunk.into(), *timeout),
+            OverflowPolicy::DropOldest { backlog: capacity } => {
+                let mut backlog = self.overflow.backlog.lock().unwrap();
+
+                backlog.push_back(chunk.into());
+
+                // Keep the order of messages by draining the backlog first.
+                while let Some(pending) = backlog.pop_front() {
+                    if let Err(e) = send(pending.clone(), Some(Duration::ZERO)) {
+                        backlog.push_front(pending);
+
+                        if !is_full(&e) {
+                            return Err(io::Error::other(e));
+                        }
+
+                        break;
+                    }
+                }
+
+                let excess = backlog.len().saturating_sub(*capacity);
+                backlog.drain(..excess);
+
+                self.counters()
+                    .dropped
+                    .fetch_add(excess as u64, Ordering::Relaxed);
+
+                return Ok(());
+            }
+            OverflowPolicy::SpillToFile(path) => {
+                let result = send(chunk.into(), Some(Duration::ZERO));
+
+                if matches!(&result, Err(e) if is_full(e)) {
+                    let mut spill_file = self.overflow.spill_file.lock().unwrap();
+
+                    let file = match &mut *spill_file {
+                        Some(file) => file,
+                        None => spill_file
+                            .insert(OpenOptions::new().create(true).append(true).open(path)?),
+                    };
+
+                    file.write_all(chunk.as_bytes())?;
+
+                    self.counters().spilled.fetch_add(1, Ordering::Relaxed);
+
+                    return Ok(());
+                }
+
+                result
+            }
+        };
+
+        match result {
+            Err(e) if is_full(&e) => {
+                self.counters().dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            result => result.map_err(io::Error::other),
+        }
+    }
+}
+
+impl Overflow {
+    fn counters(&self) -> &OverflowCounters {
+        self.bridge.overflow_counters(self.kind)
+    }
+
+    /// Sends the backlog, waiting at most `timeout` for room, and counts the messages that still
+    /// don't fit as dropped.
+    fn drain_backlog(&self, timeout: Duration) -> io::Result<()> {
+        let mut backlog = self.backlog.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let deadline = Instant::now() + timeout;
+        let mut result = Ok(());
+
+        while let Some(pending) = backlog.front() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            match self
+                .bridge
+                .send_timeout(self.kind.to_msg(pending.clone()), remaining)
+            {
+                Ok(()) => {
+                    backlog.pop_front();
+                }
+                Err(e) => {
+                    if !is_full(&e) {
+                        result = Err(io::Error::other(e));
+                    }
+
+                    break;
+                }
+            }
+        }
+
+        self.counters()
+            .dropped
+            .fetch_add(backlog.len() as u64, Ordering::Relaxed);
+
+        backlog.clear();
+
+        result
+    }
+}
+
+impl Drop for Overflow {
+    fn drop(&mut self) {
+        let _ = self.drain_backlog(BACKLOG_DRAIN_TIMEOUT);
+    }
+}
+
+fn is_full(e: &SendError) -> bool {
+    matches!(e, SendError::Write(WriteError::Full))
+}
+
+/// Splits `s` into pieces of at most `max_len` bytes on character boundaries.
+fn chunks(mut s: &str, max_len: usize) -> impl Iterator<Item = &str> {
+    std::iter::from_fn(move || {
+        if s.is_empty() {
+            return None;
+        }
+
+        let mut end = max_len.clamp(1, s.len());
+
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        // `max_len` is smaller than the first character; send it whole.
+        if end == 0 {
+            end = s.chars().next().map_or(s.len(), char::len_utf8);
+        }
+
+        let (chunk, rest) = s.split_at(end);
+        s = rest;
+
+        Some(chunk)
+    })
+}
+
+impl io::Write for LogWriter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let str = str::from_utf8(buf).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+
+        let max_len = self
+            .overflow
+            .bridge
+            .max_message_len()
+            .saturating_sub(MSG_OVERHEAD);
+
+        if str.len() > max_len {
+            self.counters().chunked.fetch_add(1, Ordering::Relaxed);
+        }
+
+        for chunk in chunks(str, max_len) {
+            self.send_chunk(chunk)?;
+        }
+
+        Ok(str.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        // Could send `MsgToParent::Flush` here but `recv_loop` already does so automatically.
+        self.overflow.drain_backlog(BACKLOG_DRAIN_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chunks;
+
+    #[test]
+    fn chunks_split_on_char_boundaries() {
+        let chunks = chunks("aéb€c", 3).collect::<Vec<_>>();
+
+        assert_eq!(chunks, ["aé", "b", "€", "c"]);
+    }
+
+    #[test]
+    fn short_strings_are_not_split() {
+        assert_eq!(chunks("abc", 16).collect::<Vec<_>>(), ["abc"]);
+    }
+}
@@ -0,0 +1,56 @@
+//! Operating system primitives backing the IPC bridge.
+//!
+//! The bridge only needs two things from the platform: a block of memory that can be mapped into
+//! both the parent and child process, and an event object that lives inside of that block and can
+//! be waited on from either process.
+
+use std::{process::Command, ptr::NonNull, time::Duration};
+
+#[cfg(target_os = "linux")]
+pub use self::linux::{Event, FileMapping, FileView, OsError};
+#[cfg(windows)]
+pub use self::windows::{Event, FileMapping, FileView, OsError};
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(windows)]
+mod windows;
+
+/// Memory that can be shared with a child process.
+///
+/// Implementations are serialized into the child's environment and must remain valid in the
+/// child process after it deserializes them.
+pub trait SharedMapping: Sized + serde::Serialize + serde::de::DeserializeOwned {
+    type View: SharedView;
+
+    /// Allocates a new mapping of `size` bytes.
+    fn create(size: usize) -> Result<Self, OsError>;
+
+    /// Maps the whole mapping into the address space of the current process.
+    fn to_view(&self) -> Result<Self::View, OsError>;
+
+    /// Makes the mapping accessible to the process spawned by `command`.
+    fn share_with(&self, command: &mut Command);
+}
+
+/// A mapping of [`SharedMapping`] into the current process, unmapped on drop.
+pub trait SharedView {
+    fn as_slice(&self) -> NonNull<[u8]>;
+}
+
+/// Cross-process event object stored in shared memory.
+///
+/// An auto-reset event wakes a single waiter and resets itself, while a manual-reset event wakes
+/// every waiter and stays set until [`SharedEvent::reset`] is called.
+pub trait SharedEvent: Sized {
+    fn new(manual_reset: bool) -> Result<Self, OsError>;
+
+    fn set(&self) -> Result<(), OsError>;
+
+    fn reset(&self) -> Result<(), OsError>;
+
+    fn wait(&self) -> Result<(), OsError>;
+
+    /// Like [`SharedEvent::wait`], but gives up after `timeout` and returns `false`.
+    fn wait_timeout(&self, timeout: Duration) -> Result<bool, OsError>;
+}
@@ -0,0 +1,263 @@
+use std::{
+    ffi::{c_void, CString},
+    io,
+    os::unix::process::CommandExt,
+    process::Command,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use me3_env::EnvVars;
+
+use super::{SharedEvent, SharedMapping, SharedView};
+
+/// Anonymous shared memory backed by a `memfd` (or an unlinked POSIX shared memory object on
+/// kernels without `memfd_create`).
+///
+/// The file descriptor is close-on-exec in the parent and is only inherited by the processes it
+/// is explicitly shared with.
+#[repr(C)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FileMapping {
+    fd: i32,
+    size: usize,
+}
+
+#[repr(C)]
+pub struct FileView {
+    ptr: NonNull<c_void>,
+    size: usize,
+}
+
+/// Futex-based event.
+///
+/// Uses shared (not process-private) futex operations on a word that lives in the mapping
+/// itself, so no additional handles need to be passed to the child process.
+#[repr(C)]
+pub struct Event {
+    state: AtomicU32,
+    manual_reset: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{}", io::Error::from_raw_os_error(self.0))]
+pub struct OsError(i32);
+
+const UNSET: u32 = 0;
+const SET: u32 = 1;
+
+impl SharedMapping for FileMapping {
+    type View = FileView;
+
+    fn create(size: usize) -> Result<Self, OsError> {
+        let fd = match unsafe { libc::memfd_create(c"me3-bridge".as_ptr(), libc::MFD_CLOEXEC) } {
+            -1 if OsError::last().0 == libc::ENOSYS => open_unlinked_shm()?,
+            -1 => return Err(OsError::last()),
+            fd => fd,
+        };
+
+        let mapping = Self { fd, size };
+
+        if unsafe { libc::ftruncate(fd, size as libc::off_t) } == -1 {
+            return Err(OsError::last());
+        }
+
+        Ok(mapping)
+    }
+
+    fn to_view(&self) -> Result<FileView, OsError> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                self.size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                self.fd,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(OsError::last());
+        }
+
+        Ok(FileView {
+            ptr: NonNull::new(ptr).ok_or(OsError(libc::EINVAL))?,
+            size: self.size,
+        })
+    }
+
+    fn share_with(&self, command: &mut Command) {
+        let fd = self.fd;
+
+        // SAFETY: `fcntl` is async-signal-safe and doesn't allocate.
+        unsafe {
+            command.pre_exec(move || match libc::fcntl(fd, libc::F_SETFD, 0) {
+                -1 => Err(io::Error::last_os_error()),
+                _ => Ok(()),
+            });
+        }
+    }
+}
+
+fn open_unlinked_shm() -> Result<i32, OsError> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let name = CString::new(format!(
+        "/me3-bridge-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+    .expect("no interior nul bytes");
+
+    // `shm_open` sets `FD_CLOEXEC` on the returned descriptor.
+    let fd = unsafe {
+        libc::shm_open(
+            name.as_ptr(),
+            libc::O_RDWR | libc::O_CREAT | libc::O_EXCL,
+            0o600,
+        )
+    };
+
+    if fd == -1 {
+        return Err(OsError::last());
+    }
+
+    // The name is no longer needed once the descriptor is open.
+    unsafe { libc::shm_unlink(name.as_ptr()) };
+
+    Ok(fd)
+}
+
+impl SharedView for FileView {
+    fn as_slice(&self) -> NonNull<[u8]> {
+        NonNull::slice_from_raw_parts(self.ptr.cast(), self.size)
+    }
+}
+
+impl SharedEvent for Event {
+    fn new(manual_reset: bool) -> Result<Self, OsError> {
+        Ok(Self {
+            state: AtomicU32::new(UNSET),
+            manual_reset,
+        })
+    }
+
+    fn set(&self) -> Result<(), OsError> {
+        self.state.store(SET, Ordering::Release);
+
+        let waiters = if self.manual_reset { i32::MAX } else { 1 };
+        futex_wake(&self.state, waiters)
+    }
+
+    fn reset(&self) -> Result<(), OsError> {
+        self.state.store(UNSET, Ordering::Release);
+        Ok(())
+    }
+
+    fn wait(&self) -> Result<(), OsError> {
+        while !self.try_acquire() {
+            futex_wait(&self.state, UNSET, None)?;
+        }
+
+        Ok(())
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> Result<bool, OsError> {
+        let deadline = Instant::now() + timeout;
+
+        while !self.try_acquire() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+
+            futex_wait(&self.state, UNSET, Some(remaining))?;
+        }
+
+        Ok(true)
+    }
+}
+
+impl Event {
+    fn try_acquire(&self) -> bool {
+        if self.manual_reset {
+            self.state.load(Ordering::Acquire) == SET
+        } else {
+            self.state
+                .compare_exchange(SET, UNSET, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        }
+    }
+}
+
+impl OsError {
+    fn last() -> Self {
+        Self(io::Error::last_os_error().raw_os_error().unwrap_or(0))
+    }
+}
+
+impl From<OsError> for io::Error {
+    fn from(value: OsError) -> Self {
+        io::Error::from_raw_os_error(value.0)
+    }
+}
+
+/// Blocks while `*word == expected`, returning early on spurious wakeups and timeouts.
+fn futex_wait(word: &AtomicU32, expected: u32, timeout: Option<Duration>) -> Result<(), OsError> {
+    let timeout = timeout.map(|timeout| libc::timespec {
+        tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as _,
+    });
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word.as_ptr(),
+            libc::FUTEX_WAIT,
+            expected,
+            timeout.as_ref().map_or(ptr::null(), ptr::from_ref),
+        )
+    };
+
+    match result {
+        -1 => match OsError::last() {
+            OsError(libc::EAGAIN | libc::EINTR | libc::ETIMEDOUT) => Ok(()),
+            e => Err(e),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn futex_wake(word: &AtomicU32, count: i32) -> Result<(), OsError> {
+    match unsafe { libc::syscall(libc::SYS_futex, word.as_ptr(), libc::FUTEX_WAKE, count) } {
+        -1 => Err(OsError::last()),
+        _ => Ok(()),
+    }
+}
+
+impl EnvVars for FileMapping {
+    const PREFIX: &'static str = "ME3_BRIDGE_FILE_";
+}
+
+impl EnvVars for &FileMapping {
+    const PREFIX: &'static str = "ME3_BRIDGE_FILE_";
+}
+
+impl Drop for FileMapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+impl Drop for FileView {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr(), self.size);
+        }
+    }
+}
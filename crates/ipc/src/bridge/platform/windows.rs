@@ -0,0 +1,167 @@
+use std::{ffi::c_void, process::Command, ptr::NonNull, time::Duration};
+
+use me3_env::EnvVars;
+pub use windows::core::Error as OsError;
+use windows::{
+    core::{BOOL, PCWSTR},
+    Win32::{
+        Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE, WAIT_OBJECT_0, WAIT_TIMEOUT},
+        Security::SECURITY_ATTRIBUTES,
+        System::{
+            Memory::{
+                CreateFileMappingW, MapViewOfFile, UnmapViewOfFile, FILE_MAP_ALL_ACCESS,
+                MEMORY_MAPPED_VIEW_ADDRESS, PAGE_READWRITE,
+            },
+            Threading::{CreateEventW, ResetEvent, SetEvent, WaitForSingleObject, INFINITE},
+        },
+    },
+};
+
+use super::{SharedEvent, SharedMapping, SharedView};
+
+#[repr(C)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FileMapping {
+    handle: usize,
+    size: usize,
+}
+
+#[repr(C)]
+pub struct FileView {
+    ptr: NonNull<c_void>,
+    size: usize,
+}
+
+#[repr(C)]
+pub struct Event {
+    handle: HANDLE,
+}
+
+impl SharedMapping for FileMapping {
+    type View = FileView;
+
+    fn create(size: usize) -> Result<Self, OsError> {
+        // `INHERIT_HANDLE` so the child process can use the handle directly.
+        let handle = unsafe {
+            CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                INHERIT_HANDLE,
+                PAGE_READWRITE,
+                (size >> 32) as u32,
+                size as u32,
+                PCWSTR::null(),
+            )?
+        };
+
+        Ok(Self {
+            handle: handle.0 as usize,
+            size,
+        })
+    }
+
+    fn to_view(&self) -> Result<FileView, OsError> {
+        let size = self.size;
+
+        // Non-null or bail.
+        let ptr = unsafe {
+            NonNull::new(MapViewOfFile(self.handle(), FILE_MAP_ALL_ACCESS, 0, 0, size).Value)
+                .ok_or_else(OsError::from_thread)?
+        };
+
+        Ok(FileView { ptr, size })
+    }
+
+    fn share_with(&self, _command: &mut Command) {
+        // The handle is inheritable and `Command` spawns processes with handle inheritance.
+    }
+}
+
+impl FileMapping {
+    fn handle(&self) -> HANDLE {
+        HANDLE(self.handle as *mut c_void)
+    }
+}
+
+impl SharedView for FileView {
+    fn as_slice(&self) -> NonNull<[u8]> {
+        NonNull::slice_from_raw_parts(self.ptr.cast(), self.size)
+    }
+}
+
+impl SharedEvent for Event {
+    fn new(manual_reset: bool) -> Result<Self, OsError> {
+        let handle = unsafe { CreateEventW(INHERIT_HANDLE, manual_reset, false, PCWSTR::null())? };
+
+        Ok(Self { handle })
+    }
+
+    fn set(&self) -> Result<(), OsError> {
+        unsafe { SetEvent(self.handle) }
+    }
+
+    fn reset(&self) -> Result<(), OsError> {
+        unsafe { ResetEvent(self.handle) }
+    }
+
+    fn wait(&self) -> Result<(), OsError> {
+        match unsafe { WaitForSingleObject(self.handle, INFINITE) } {
+            WAIT_OBJECT_0 => Ok(()),
+            _ => Err(OsError::from_thread()),
+        }
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> Result<bool, OsError> {
+        // Clamp below `INFINITE`, which would never time out.
+        let ms = timeout.as_millis().min((INFINITE - 1) as u128) as u32;
+
+        match unsafe { WaitForSingleObject(self.handle, ms) } {
+            WAIT_OBJECT_0 => Ok(true),
+            WAIT_TIMEOUT => Ok(false),
+            _ => Err(OsError::from_thread()),
+        }
+    }
+}
+
+const INHERIT_HANDLE: Option<*const SECURITY_ATTRIBUTES> = Some(&SECURITY_ATTRIBUTES {
+    nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+    lpSecurityDescriptor: std::ptr::null_mut(),
+    bInheritHandle: BOOL(1),
+});
+
+impl EnvVars for FileMapping {
+    const PREFIX: &'static str = "ME3_BRIDGE_FILE_";
+}
+
+impl EnvVars for &FileMapping {
+    const PREFIX: &'static str = "ME3_BRIDGE_FILE_";
+}
+
+impl Drop for FileMapping {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle());
+        }
+    }
+}
+
+impl Drop for FileView {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnmapViewOfFile(MEMORY_MAPPED_VIEW_ADDRESS {
+                Value: self.ptr.as_ptr(),
+            });
+        }
+    }
+}
+
+impl Drop for Event {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+unsafe impl Send for Event {}
+
+unsafe impl Sync for Event {}
@@ -1,11 +1,15 @@
 use std::ptr::NonNull;
 
-use crate::bridge::channel::Channel;
+use crate::bridge::{
+    channel::Channel,
+    log::{LogKind, OverflowCounters},
+};
 
 #[repr(C)]
 pub struct SharedBridge {
     pub(crate) to_parent: Channel,
     pub(crate) to_child: Channel,
+    pub(crate) overflow: [OverflowCounters; LogKind::COUNT],
 }
 
 impl SharedBridge {
@@ -28,6 +32,7 @@ impl SharedBridge {
             let ptr = start.as_ptr();
             (&raw mut (*ptr).to_parent).write(Channel::new());
             (&raw mut (*ptr).to_child).write(Channel::new());
+            (&raw mut (*ptr).overflow).write(Default::default());
 
             let bridge = start.as_mut();
 
@@ -1,14 +1,9 @@
-use std::sync::atomic::{fence, AtomicU32, Ordering};
-
-use windows::{
-    core::{Error as WinError, PCWSTR},
-    Win32::{
-        Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0},
-        System::Threading::{CreateEventW, ResetEvent, SetEvent, WaitForSingleObject, INFINITE},
-    },
+use std::{
+    sync::atomic::{fence, AtomicU32, Ordering},
+    time::Duration,
 };
 
-use crate::bridge::INHERIT_HANDLE;
+use crate::bridge::platform::{Event, OsError, SharedEvent};
 
 /// Multiple producers, single consumer signal.
 ///
@@ -16,7 +11,7 @@ use crate::bridge::INHERIT_HANDLE;
 #[repr(C, align(128))]
 pub struct MpscSignal {
     notify_count: AtomicU32,
-    handle: HANDLE,
+    event: Event,
 }
 
 /// Single producer, multiple consumers signal.
@@ -25,94 +20,75 @@ pub struct MpscSignal {
 #[repr(C, align(128))]
 pub struct SpmcSignal {
     asleep: AtomicU32,
-    handle: HANDLE,
+    event: Event,
 }
 
 impl MpscSignal {
     pub fn new() -> Self {
-        let handle = unsafe {
-            CreateEventW(INHERIT_HANDLE, false, false, PCWSTR::null()).expect("CreateEventW failed")
-        };
-
         Self {
             notify_count: AtomicU32::new(1),
-            handle,
+            event: Event::new(false).expect("failed to create event"),
         }
     }
 
-    pub fn notify(&self) -> Result<(), WinError> {
+    pub fn notify(&self) -> Result<(), OsError> {
         if self.notify_count.fetch_add(1, Ordering::AcqRel) != 0 {
             return Ok(());
         }
-        unsafe { SetEvent(self.handle) }
+        self.event.set()
     }
 
-    pub fn wait(&self) -> Result<(), WinError> {
+    pub fn wait(&self) -> Result<(), OsError> {
         if self.notify_count.fetch_sub(1, Ordering::AcqRel) != 1 {
             return Ok(());
         }
-        match unsafe { WaitForSingleObject(self.handle, INFINITE) } {
-            WAIT_OBJECT_0 => Ok(()),
-            _ => Err(WinError::from_thread()),
-        }
+        self.event.wait()
     }
 }
 
 impl SpmcSignal {
     pub fn new() -> Self {
-        // This event is manual reset since we want to wake up multiple threads.
-        let handle = unsafe {
-            CreateEventW(INHERIT_HANDLE, true, false, PCWSTR::null()).expect("CreateEventW failed")
-        };
-
         Self {
             asleep: AtomicU32::new(0),
-            handle,
+            // This event is manual reset since we want to wake up multiple threads.
+            event: Event::new(true).expect("failed to create event"),
         }
     }
 
-    pub fn notify(&self) -> Result<(), WinError> {
+    pub fn notify(&self) -> Result<(), OsError> {
         if self.asleep.load(Ordering::Acquire) == 0 {
             // No threads are sleeping
             return Ok(());
         }
-        unsafe { SetEvent(self.handle) }
+        self.event.set()
     }
 
-    pub fn wait(&self) -> Result<(), WinError> {
+    pub fn wait(&self) -> Result<(), OsError> {
         let _ = self.asleep.fetch_add(1, Ordering::Relaxed);
-        if unsafe { WaitForSingleObject(self.handle, INFINITE) != WAIT_OBJECT_0 } {
-            return Err(WinError::from_thread());
-        }
+        self.event.wait()?;
         if self.asleep.fetch_sub(1, Ordering::Release) != 1 {
             // Other threads are sleeping.
             return Ok(());
         }
         fence(Ordering::Acquire);
-        unsafe { ResetEvent(self.handle) }
+        self.event.reset()
     }
-}
 
-impl Drop for MpscSignal {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = CloseHandle(self.handle);
+    /// Like [`SpmcSignal::wait`], but gives up after `timeout` and returns `false`.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<bool, OsError> {
+        let _ = self.asleep.fetch_add(1, Ordering::Relaxed);
+        let notified = self.event.wait_timeout(timeout)?;
+        if self.asleep.fetch_sub(1, Ordering::Release) != 1 {
+            // Other threads are sleeping.
+            return Ok(notified);
         }
-    }
-}
-
-impl Drop for SpmcSignal {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = CloseHandle(self.handle);
+        if !notified {
+            // A notification landing after the timeout leaves the event set for the next wait,
+            // resetting it here would lose it.
+            return Ok(false);
         }
+        fence(Ordering::Acquire);
+        self.event.reset()?;
+        Ok(true)
     }
 }
-
-unsafe impl Send for MpscSignal {}
-
-unsafe impl Sync for MpscSignal {}
-
-unsafe impl Send for SpmcSignal {}
-
-unsafe impl Sync for SpmcSignal {}
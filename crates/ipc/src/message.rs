@@ -1,3 +1,4 @@
+use me3_launcher_attach_protocol::event::HostEvent;
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::request::{Request, RequestError, RequestId, Response};
@@ -12,6 +13,9 @@ pub enum MsgToParent {
     /// A single log message from the child process meant for the log file.
     FileLog(Box<str>),
 
+    /// A structured event from the child process meant for the CLI.
+    Event(HostEvent),
+
     /// RPC response coming from the child process.
     ///
     /// The message thread must use [`Response::forward`](crate::request::Response::forward) to
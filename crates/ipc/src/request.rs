@@ -13,7 +13,10 @@ use std::{
     },
 };
 
-use me3_launcher_attach_protocol::{AttachRequest, AttachResult};
+use me3_launcher_attach_protocol::{
+    AttachRequest, AttachResult, LogFilterRequest, LogFilterResult, ReloadNativeRequest,
+    ReloadNativeResult,
+};
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::{
@@ -35,6 +38,8 @@ pub struct RequestId(u64);
 #[derive(Clone, Archive, Serialize, Deserialize)]
 pub enum Request {
     Attach(AttachRequest),
+    LogFilter(LogFilterRequest),
+    ReloadNative(ReloadNativeRequest),
 }
 
 /// Kinds of responses seen by
@@ -42,6 +47,8 @@ pub enum Request {
 #[derive(Clone, Archive, Serialize, Deserialize)]
 pub enum Response {
     Attach(AttachResult),
+    LogFilter(LogFilterResult),
+    ReloadNative(ReloadNativeResult),
 }
 
 #[derive(Clone, Debug, thiserror::Error, Archive, Serialize, Deserialize)]
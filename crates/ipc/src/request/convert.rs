@@ -1,6 +1,9 @@
 use std::any::type_name;
 
-use me3_launcher_attach_protocol::{AttachRequest, AttachResult};
+use me3_launcher_attach_protocol::{
+    AttachRequest, AttachResult, LogFilterRequest, LogFilterResult, ReloadNativeRequest,
+    ReloadNativeResult,
+};
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::request::{Request, Response};
@@ -77,3 +80,63 @@ impl ConvertResponse for AttachResult {
         }
     }
 }
+
+impl ConvertRequest for LogFilterRequest {
+    type Res = LogFilterResult;
+
+    fn into_req(self) -> Request {
+        Request::LogFilter(self)
+    }
+
+    fn try_from_req(req: Request) -> Result<Self, TryFromRequestError> {
+        match req {
+            Request::LogFilter(req) => Ok(req),
+            _ => Err(TryFromError::err::<Self, _>()),
+        }
+    }
+}
+
+impl ConvertResponse for LogFilterResult {
+    type Req = LogFilterRequest;
+
+    fn into_res(self) -> Response {
+        Response::LogFilter(self)
+    }
+
+    fn try_from_res(res: Response) -> Result<Self, TryFromResponseError> {
+        match res {
+            Response::LogFilter(res) => Ok(res),
+            _ => Err(TryFromError::err::<Self, _>()),
+        }
+    }
+}
+
+impl ConvertRequest for ReloadNativeRequest {
+    type Res = ReloadNativeResult;
+
+    fn into_req(self) -> Request {
+        Request::ReloadNative(self)
+    }
+
+    fn try_from_req(req: Request) -> Result<Self, TryFromRequestError> {
+        match req {
+            Request::ReloadNative(req) => Ok(req),
+            _ => Err(TryFromError::err::<Self, _>()),
+        }
+    }
+}
+
+impl ConvertResponse for ReloadNativeResult {
+    type Req = ReloadNativeRequest;
+
+    fn into_res(self) -> Response {
+        Response::ReloadNative(self)
+    }
+
+    fn try_from_res(res: Response) -> Result<Self, TryFromResponseError> {
+        match res {
+            Response::ReloadNative(res) => Ok(res),
+            _ => Err(TryFromError::err::<Self, _>()),
+        }
+    }
+}
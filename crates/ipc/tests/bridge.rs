@@ -0,0 +1,285 @@
+//! Exercises both ends of the bridge across a real process boundary.
+//!
+//! The test binary re-executes itself to run [`child_process`] as the child end of the bridge.
+
+use std::{
+    io::Write,
+    process::{Child, Command, Stdio},
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use me3_ipc::{
+    bridge::{self, BridgeToChild, LogKind, OverflowPolicy},
+    message::MsgToParent,
+    request::{RequestError, Response},
+};
+use me3_launcher_attach_protocol::{
+    event::HostEvent, AttachConfig, AttachRequest, AttachResult, Attachment,
+};
+use me3_mod_protocol::Game;
+
+const CHILD_VAR: &str = "ME3_IPC_TEST_CHILD";
+
+const PANIC_SAVEFILE: &str = "panic";
+
+const FLOOD_MODE: &str = "flood";
+
+const BACKLOG_MODE: &str = "backlog";
+
+struct Parent {
+    bridge: Arc<BridgeToChild>,
+    child: Child,
+    messages: mpsc::Receiver<MsgToParent>,
+}
+
+fn child_command(mode: &str) -> Command {
+    let mut command = Command::new(std::env::current_exe().unwrap());
+
+    command
+        .args(["--exact", "child_process", "--nocapture"])
+        .env(CHILD_VAR, mode)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    command
+}
+
+impl Parent {
+    fn spawn() -> Self {
+        let mut command = child_command("serve");
+
+        let bridge = Arc::new(bridge::to_child(1, &mut command).unwrap());
+        let child = command.spawn().unwrap();
+
+        let (sender, messages) = mpsc::channel();
+
+        thread::spawn({
+            let bridge = bridge.clone();
+
+            move || {
+                let recv_span = bridge.enter_recv_span().unwrap();
+
+                while let Ok(msg) = recv_span.recv() {
+                    match msg {
+                        MsgToParent::Response(res) => Response::forward(res),
+                        msg => {
+                            if sender.send(msg).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            bridge,
+            child,
+            messages,
+        }
+    }
+
+    fn next_message(&self) -> MsgToParent {
+        self.messages
+            .recv_timeout(Duration::from_secs(10))
+            .expect("no message from child process")
+    }
+}
+
+impl Drop for Parent {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn attach_request(savefile: Option<&str>) -> AttachRequest {
+    AttachRequest {
+        config: AttachConfig {
+            game: Game::EldenRing,
+            natives: vec![],
+            early_natives: vec![],
+            packages: vec![],
+            savefile: savefile.map(str::to_owned),
+            cache_path: None,
+            suspend: false,
+            boot_boost: false,
+            skip_logos: false,
+            start_online: false,
+            disable_arxan: false,
+            mem_patch: false,
+            skip_steam_init: true,
+            shadow_copy_natives: false,
+            asset_trace: None,
+        },
+    }
+}
+
+/// Child end of the bridge, only does anything when spawned by [`Parent::spawn`].
+#[test]
+fn child_process() {
+    let Some(mode) = std::env::var_os(CHILD_VAR) else {
+        return;
+    };
+
+    let bridge = bridge::to_parent().unwrap();
+
+    if mode == FLOOD_MODE {
+        let mut writer = bridge
+            .console_log_writer()
+            .with_overflow_policy(OverflowPolicy::Block {
+                timeout: Some(Duration::from_millis(10)),
+            });
+
+        // Larger than the whole buffer, so it can't be delivered in full without a reader.
+        let message = "x".repeat(bridge.max_message_len() * 3);
+        writer.write_all(message.as_bytes()).unwrap();
+
+        return;
+    }
+
+    if mode == BACKLOG_MODE {
+        let mut writer = bridge
+            .console_log_writer()
+            .with_overflow_policy(OverflowPolicy::DropOldest { backlog: 64 });
+
+        // More than the buffer holds, but few enough to all fit in the backlog.
+        let message = "x".repeat(bridge.max_message_len() / 2);
+
+        for _ in 0..16 {
+            writer.write_all(message.as_bytes()).unwrap();
+        }
+
+        return;
+    }
+
+    write!(bridge.console_log_writer(), "hello from the child").unwrap();
+
+    bridge
+        .send_event(HostEvent::AttachFinished { error: None })
+        .unwrap();
+
+    let recv_span = bridge.enter_recv_span().unwrap();
+
+    loop {
+        let Ok(me3_ipc::message::MsgToChild::Request(req)) = recv_span.recv() else {
+            continue;
+        };
+
+        bridge
+            .fulfill(req, |req: AttachRequest| -> AttachResult {
+                if req.config.savefile.as_deref() == Some(PANIC_SAVEFILE) {
+                    panic!("requested panic");
+                }
+
+                Ok(Attachment)
+            })
+            .unwrap();
+    }
+}
+
+#[test]
+fn child_messages_are_received() {
+    let parent = Parent::spawn();
+
+    match parent.next_message() {
+        MsgToParent::ConsoleLog(s) => assert_eq!(&*s, "hello from the child"),
+        _ => panic!("expected a console log message"),
+    }
+
+    match parent.next_message() {
+        MsgToParent::Event(event) => {
+            assert_eq!(event, HostEvent::AttachFinished { error: None })
+        }
+        _ => panic!("expected an event"),
+    }
+}
+
+#[test]
+fn request_is_fulfilled() {
+    let parent = Parent::spawn();
+
+    let result = parent.bridge.request(attach_request(None)).unwrap();
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn concurrent_requests_are_fulfilled() {
+    let parent = Parent::spawn();
+
+    thread::scope(|s| {
+        let handles = (0..16)
+            .map(|_| s.spawn(|| parent.bridge.request(attach_request(None))))
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert!(handle.join().unwrap().unwrap().is_ok());
+        }
+    });
+}
+
+#[test]
+fn request_panic_is_propagated() {
+    let parent = Parent::spawn();
+
+    let result = parent.bridge.request(attach_request(Some(PANIC_SAVEFILE)));
+
+    match result {
+        Err(RequestError::Panic(msg)) => assert_eq!(&*msg, "requested panic"),
+        _ => panic!("expected the request to panic"),
+    }
+}
+
+#[test]
+fn recv_span_is_exclusive() {
+    let mut command = Command::new(std::env::current_exe().unwrap());
+    let bridge = bridge::to_child(1, &mut command).unwrap();
+
+    let recv_span = bridge.enter_recv_span().unwrap();
+
+    assert!(bridge.enter_recv_span().is_err());
+
+    // Requests from the receiving thread would never see their response.
+    assert!(matches!(
+        bridge.request(attach_request(None)),
+        Err(RequestError::RequestFromRecv)
+    ));
+
+    drop(recv_span);
+
+    assert!(bridge.enter_recv_span().is_ok());
+}
+
+#[test]
+fn overflowing_messages_are_counted() {
+    let mut command = child_command(FLOOD_MODE);
+    let bridge = bridge::to_child(1, &mut command).unwrap();
+
+    // Nothing reads from the bridge until the child process exits.
+    let status = command.status().unwrap();
+    assert!(status.success());
+
+    let stats = bridge.overflow_stats(LogKind::Console);
+
+    assert_eq!(stats.chunked, 1);
+    assert!(stats.dropped > 0);
+    assert_eq!(bridge.overflow_stats(LogKind::File), Default::default());
+}
+
+#[test]
+fn undelivered_backlog_is_counted() {
+    let mut command = child_command(BACKLOG_MODE);
+    let bridge = bridge::to_child(1, &mut command).unwrap();
+
+    // Nothing reads from the bridge, so dropping the writer can't send its whole backlog.
+    let status = command.status().unwrap();
+    assert!(status.success());
+
+    let stats = bridge.overflow_stats(LogKind::Console);
+
+    assert!(stats.dropped > 0);
+    assert_eq!(stats.chunked, 0);
+}
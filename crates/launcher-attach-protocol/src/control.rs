@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// Commands sent by the CLI to the launcher over the control pipe while the game is running.
+///
+/// Each command is written as a single line of JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlMessage {
+    /// Replace the log filter of the mod host.
+    LogFilter { filter: String },
+
+    /// Finalize, unload and load a native again from its original path.
+    ReloadNative { id: String },
+}
+
+impl ControlMessage {
+    /// Encode this command as a single, newline terminated, control pipe line.
+    pub fn to_control_line(&self) -> String {
+        // Serializing a plain enum with string fields is infallible.
+        let json = serde_json::to_string(self).expect("failed to serialize control message");
+
+        format!("{json}\n")
+    }
+
+    /// Decode a control pipe line.
+    pub fn from_control_line(line: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(line.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControlMessage;
+
+    #[test]
+    fn control_line_roundtrip() {
+        let message = ControlMessage::ReloadNative {
+            id: "my_mod.dll".to_owned(),
+        };
+
+        let line = message.to_control_line();
+
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(ControlMessage::from_control_line(&line).unwrap(), message);
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Prefix marking a monitor pipe line as a [`HostEvent`] record instead of plain log output.
+///
+/// This is the ASCII record separator, as used by JSON text sequences (RFC 7464).
+pub const EVENT_RECORD_SEPARATOR: char = '\x1e';
+
+/// Structured events reported by the mod host over the course of a game session.
+///
+/// The host sends these to the launcher over the IPC bridge, which forwards them to the CLI on
+/// the monitor pipe.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HostEvent {
+    /// A native DLL was loaded and initialized.
+    NativeLoaded { path: String },
+
+    /// A native DLL failed to load or initialize.
+    NativeFailed {
+        path: String,
+        error: String,
+        optional: bool,
+    },
+
+    /// A native's finalizer was called as the game exited.
+    NativeFinalized { path: String, error: Option<String> },
+
+    /// A native was reloaded while the game was running.
+    NativeReloaded { path: String, error: Option<String> },
+
+    /// A hook was installed.
+    HookInstalled { name: String },
+
+    /// A hook could not be installed.
+    HookFailed { name: String, error: String },
+
+    /// A file was served from a package instead of the game's own archives, `count` times since
+    /// the last report.
+    AssetOverride { path: String, count: u64 },
+
+    /// The game's savefile was redirected.
+    SavefileRedirected { path: String },
+
+    /// The host finished handling the attach request.
+    AttachFinished { error: Option<String> },
+
+    /// An unrecoverable error occurred.
+    Fatal { error: String },
+
+    /// Log messages of `kind` didn't make it to the launcher as-is because they were sent faster
+    /// than it could keep up with.
+    LogsDropped {
+        kind: String,
+        dropped: u64,
+        chunked: u64,
+        spilled: u64,
+    },
+}
+
+impl HostEvent {
+    /// Is this event fatal to the game session?
+    pub fn is_error(&self) -> bool {
+        match self {
+            HostEvent::NativeFailed { optional, .. } => !optional,
+            HostEvent::AttachFinished { error } => error.is_some(),
+            HostEvent::Fatal { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Encode this event as a single, newline terminated, monitor pipe record.
+    pub fn to_monitor_line(&self) -> String {
+        // Serializing a plain enum with string fields is infallible.
+        let json = serde_json::to_string(self).expect("failed to serialize event");
+
+        format!("{EVENT_RECORD_SEPARATOR}{json}\n")
+    }
+
+    /// Decode a monitor pipe line, returning `None` if it isn't an event record.
+    pub fn from_monitor_line(line: &str) -> Option<Result<Self, serde_json::Error>> {
+        let json = line.strip_prefix(EVENT_RECORD_SEPARATOR)?;
+
+        Some(serde_json::from_str(json.trim_end()))
+    }
+}
+
+impl fmt::Display for HostEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostEvent::NativeLoaded { path } => write!(f, "loaded native {path}"),
+            HostEvent::NativeFailed {
+                path,
+                error,
+                optional: true,
+            } => write!(f, "failed to load optional native {path}: {error}"),
+            HostEvent::NativeFailed { path, error, .. } => {
+                write!(f, "failed to load native {path}: {error}")
+            }
+            HostEvent::NativeFinalized { path, error: None } => {
+                write!(f, "finalized native {path}")
+            }
+            HostEvent::NativeFinalized {
+                path,
+                error: Some(error),
+            } => write!(f, "failed to finalize native {path}: {error}"),
+            HostEvent::NativeReloaded { path, error: None } => write!(f, "reloaded native {path}"),
+            HostEvent::NativeReloaded {
+                path,
+                error: Some(error),
+            } => write!(f, "failed to reload native {path}: {error}"),
+            HostEvent::HookInstalled { name } => write!(f, "installed hook {name}"),
+            HostEvent::HookFailed { name, error } => {
+                write!(f, "failed to install hook {name}: {error}")
+            }
+            HostEvent::AssetOverride { path, count: 1 } => write!(f, "served override {path}"),
+            HostEvent::AssetOverride { path, count } => {
+                write!(f, "served override {path} {count} times")
+            }
+            HostEvent::SavefileRedirected { path } => write!(f, "redirected savefile to {path}"),
+            HostEvent::AttachFinished { error: None } => write!(f, "attached to game"),
+            HostEvent::AttachFinished { error: Some(error) } => {
+                write!(f, "failed to attach to game: {error}")
+            }
+            HostEvent::Fatal { error } => write!(f, "fatal error: {error}"),
+            HostEvent::LogsDropped {
+                kind,
+                dropped,
+                chunked,
+                spilled,
+            } => write!(
+                f,
+                "{dropped} {kind} log lines dropped ({spilled} spilled to file, {chunked} split)"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HostEvent;
+
+    #[test]
+    fn monitor_line_roundtrip() {
+        let event = HostEvent::NativeFailed {
+            path: "C:\\mods\\mod.dll".to_owned(),
+            error: "not found\nsecond line".to_owned(),
+            optional: false,
+        };
+
+        let line = event.to_monitor_line();
+
+        assert_eq!(line.matches('\n').count(), 1);
+        assert_eq!(HostEvent::from_monitor_line(&line).unwrap().unwrap(), event);
+    }
+
+    #[test]
+    fn plain_line_is_not_event() {
+        assert!(HostEvent::from_monitor_line("INFO me3_mod_host: attached\n").is_none());
+    }
+}
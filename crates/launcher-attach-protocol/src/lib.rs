@@ -14,6 +14,10 @@ use rkyv::{
 };
 use serde::{Deserialize, Serialize};
 
+pub mod control;
+pub mod event;
+pub mod trace;
+
 #[derive(
     Clone, Debug, Serialize, Deserialize, rkyv::Archive, rkyv::Deserialize, rkyv::Serialize,
 )]
@@ -64,6 +68,15 @@ pub struct AttachConfig {
 
     /// Should we avoid checking if Steam is running as part of pre-launch checks?
     pub skip_steam_init: bool,
+
+    /// Load copies of natives from the cache directory, so they can be rebuilt and reloaded
+    /// while the game is running?
+    pub shadow_copy_natives: bool,
+
+    #[rkyv(with = AsOptionString)]
+    /// Path to write the asset requests made by the game to when it exits, if they're traced.
+    #[serde(default)]
+    pub asset_trace: Option<PathBuf>,
 }
 
 impl AttachConfig {
@@ -155,3 +168,49 @@ impl<E: Into<eyre::Report>> From<E> for AttachError {
         AttachError(format!("{err:#?}"))
     }
 }
+
+/// Replaces the log filter of the mod host while the game is running.
+#[derive(
+    Clone, Debug, Deserialize, Serialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct LogFilterRequest {
+    /// Filter directives in `EnvFilter` syntax, applied on top of the default log levels.
+    pub filter: String,
+}
+
+pub type LogFilterResult = Result<(), LogFilterError>;
+
+#[derive(
+    Clone, Debug, Deserialize, Serialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct LogFilterError(pub String);
+
+impl<E: Into<eyre::Report>> From<E> for LogFilterError {
+    fn from(value: E) -> Self {
+        let err = value.into();
+        LogFilterError(format!("{err:#}"))
+    }
+}
+
+/// Reloads a native while the game is running.
+#[derive(
+    Clone, Debug, Deserialize, Serialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct ReloadNativeRequest {
+    /// Id of the native, as used in the `load_before` and `load_after` lists of a profile.
+    pub id: String,
+}
+
+pub type ReloadNativeResult = Result<(), ReloadNativeError>;
+
+#[derive(
+    Clone, Debug, Deserialize, Serialize, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize,
+)]
+pub struct ReloadNativeError(pub String);
+
+impl<E: Into<eyre::Report>> From<E> for ReloadNativeError {
+    fn from(value: E) -> Self {
+        let err = value.into();
+        ReloadNativeError(format!("{err:#}"))
+    }
+}
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use rkyv::{
+    rancor::{self, Source},
+    util::AlignedVec,
+};
+
+/// Identifies asset trace files, followed by the archived [`AssetTrace`].
+const MAGIC: &[u8; 8] = b"me3trc01";
+
+/// The asset requests of a game session, written by the mod host when the game exits.
+///
+/// Paths and package names are stored once and referred to by index, since the game requests the
+/// same files many times.
+#[derive(Clone, Debug, Default, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct AssetTrace {
+    /// When tracing started, in milliseconds since the Unix epoch.
+    pub started_at: u64,
+
+    /// Names of the packages in [`AssetTrace::files`].
+    pub packages: Vec<String>,
+
+    /// Every file that could be served instead of a game asset.
+    pub files: Vec<OverrideFile>,
+
+    /// Every path requested by the game, as matched against package files.
+    pub paths: Vec<String>,
+
+    /// Asset requests, in the order they were made.
+    pub accesses: Vec<AssetAccess>,
+}
+
+/// A file served instead of a game asset.
+#[derive(Clone, Debug, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct OverrideFile {
+    /// The path the file is served under.
+    pub path: String,
+
+    /// The file on disk.
+    pub source: String,
+
+    /// Index of the package the file belongs to in [`AssetTrace::packages`], if it's part of one.
+    pub package: Option<u32>,
+}
+
+/// A single asset request.
+#[derive(Clone, Debug, PartialEq, rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+pub struct AssetAccess {
+    /// Index of the requested path in [`AssetTrace::paths`].
+    pub path: u32,
+
+    /// Index of the file served instead in [`AssetTrace::files`], if the request was overridden.
+    pub served_by: Option<u32>,
+
+    /// Milliseconds since [`AssetTrace::started_at`].
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug)]
+struct NotAnAssetTrace;
+
+impl fmt::Display for NotAnAssetTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not an me3 asset trace file")
+    }
+}
+
+impl Error for NotAnAssetTrace {}
+
+impl AssetTrace {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, rancor::Error> {
+        let archive = rkyv::to_bytes::<rancor::Error>(self)?;
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + archive.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&archive);
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, rancor::Error> {
+        let archive = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| rancor::Error::new(NotAnAssetTrace))?;
+
+        let mut aligned = AlignedVec::<16>::with_capacity(archive.len());
+        aligned.extend_from_slice(archive);
+
+        rkyv::from_bytes::<Self, rancor::Error>(&aligned)
+    }
+
+    /// Name of the package `file` belongs to, if any.
+    pub fn package_of(&self, file: &OverrideFile) -> Option<&str> {
+        file.package
+            .and_then(|package| self.packages.get(package as usize))
+            .map(String::as_str)
+    }
+}
+
+/// Builds an [`AssetTrace`], storing each path and package name once.
+#[derive(Debug, Default)]
+pub struct AssetTraceRecorder {
+    trace: AssetTrace,
+    packages: HashMap<String, u32>,
+    files: HashMap<String, u32>,
+    paths: HashMap<String, u32>,
+}
+
+impl AssetTraceRecorder {
+    pub fn new(started_at: u64) -> Self {
+        Self {
+            trace: AssetTrace {
+                started_at,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Adds a file that may be served instead of the asset at `path`, returning its index.
+    ///
+    /// Files are identified by `source`, so adding one again returns the existing index.
+    pub fn add_file(&mut self, path: &str, source: &str, package: Option<&str>) -> u32 {
+        if let Some(&index) = self.files.get(source) {
+            return index;
+        }
+
+        let package =
+            package.map(|package| intern(&mut self.packages, &mut self.trace.packages, package));
+
+        let index = self.trace.files.len() as u32;
+
+        self.trace.files.push(OverrideFile {
+            path: path.to_owned(),
+            source: source.to_owned(),
+            package,
+        });
+
+        self.files.insert(source.to_owned(), index);
+
+        index
+    }
+
+    /// Records a request for the asset at `path`, overridden by `served_by` if it isn't `None`.
+    pub fn record(&mut self, path: &str, served_by: Option<(&str, Option<&str>)>, elapsed_ms: u64) {
+        let served_by = served_by.map(|(source, package)| self.add_file(path, source, package));
+        let path = intern(&mut self.paths, &mut self.trace.paths, path);
+
+        self.trace.accesses.push(AssetAccess {
+            path,
+            served_by,
+            elapsed_ms,
+        });
+    }
+
+    pub fn finish(self) -> AssetTrace {
+        self.trace
+    }
+}
+
+fn intern(indices: &mut HashMap<String, u32>, values: &mut Vec<String>, value: &str) -> u32 {
+    if let Some(&index) = indices.get(value) {
+        return index;
+    }
+
+    let index = values.len() as u32;
+
+    values.push(value.to_owned());
+    indices.insert(value.to_owned(), index);
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetTrace, AssetTraceRecorder};
+
+    #[test]
+    fn recorded_trace_roundtrip() -> Result<(), rkyv::rancor::Error> {
+        let mut recorder = AssetTraceRecorder::new(1_700_000_000_000);
+
+        recorder.add_file(
+            "chr/c0000.anibnd.dcx",
+            "D:/mod/chr/c0000.anibnd.dcx",
+            Some("mod"),
+        );
+        recorder.add_file(
+            "parts/am_m_1000.partsbnd.dcx",
+            "D:/mod/parts/am_m_1000.partsbnd.dcx",
+            Some("mod"),
+        );
+
+        recorder.record(
+            "chr/c0000.anibnd.dcx",
+            Some(("D:/mod/chr/c0000.anibnd.dcx", Some("mod"))),
+            10,
+        );
+        recorder.record("chr/c0000.chrbnd.dcx", None, 12);
+        recorder.record(
+            "chr/c0000.anibnd.dcx",
+            Some(("D:/mod/chr/c0000.anibnd.dcx", Some("mod"))),
+            20,
+        );
+        recorder.record(
+            "regulation.bin",
+            Some(("D:/saves/regulation.bin", None)),
+            30,
+        );
+
+        let trace = recorder.finish();
+
+        assert_eq!(trace.packages, ["mod"]);
+        assert_eq!(trace.files.len(), 3);
+        assert_eq!(trace.paths.len(), 3);
+        assert_eq!(trace.accesses.len(), 4);
+        assert_eq!(trace.accesses[0].path, trace.accesses[2].path);
+        assert_eq!(trace.accesses[0].served_by, Some(0));
+        assert_eq!(trace.accesses[1].served_by, None);
+        assert_eq!(trace.package_of(&trace.files[2]), None);
+
+        let bytes = trace.to_bytes()?;
+        assert_eq!(AssetTrace::from_bytes(&bytes)?, trace);
+        assert!(AssetTrace::from_bytes(&bytes[1..]).is_err());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     ffi::c_void,
-    io::Write,
+    fs::File,
+    io::{BufRead, BufReader, Write},
     iter, mem,
     os::windows::{
         ffi::OsStrExt,
@@ -14,9 +15,15 @@ use std::{
 
 use eyre::{eyre, Context};
 use me3_env::{deserialize_from_env, serialize_into_command, TelemetryVars};
-use me3_ipc::{bridge::BridgeToChild, message::MsgToParent, request::Response};
-use me3_launcher_attach_protocol::{AttachRequest, Attachment};
-use tracing::{error, info, instrument};
+use me3_ipc::{
+    bridge::{BridgeToChild, LogKind, OverflowStats},
+    message::MsgToParent,
+    request::Response,
+};
+use me3_launcher_attach_protocol::{
+    control::ControlMessage, AttachRequest, Attachment, LogFilterRequest, ReloadNativeRequest,
+};
+use tracing::{error, info, instrument, warn};
 use tracing_subscriber::fmt::MakeWriter;
 use windows::{
     core::{s, w, Error as WinError},
@@ -113,8 +120,56 @@ impl Game {
         Ok(response)
     }
 
-    pub fn join(mut self) {
+    /// Waits for the game to exit, returning how many of its log messages were lost.
+    pub fn join(mut self) -> [(LogKind, OverflowStats); 2] {
         let _ = self.child.wait();
+
+        [LogKind::Console, LogKind::File].map(|kind| (kind, self.bridge.overflow_stats(kind)))
+    }
+
+    /// Forwards the commands the CLI writes to `control_pipe_path` to the mod host.
+    pub fn forward_control_messages(&self, control_pipe_path: PathBuf) {
+        let bridge = self.bridge.clone();
+        std::thread::spawn(move || {
+            let control_pipe = match File::open(&control_pipe_path) {
+                Ok(control_pipe) => control_pipe,
+                Err(error) => {
+                    warn!(%error, "failed to open control pipe");
+                    return;
+                }
+            };
+
+            for line in BufReader::new(control_pipe).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                let message = match ControlMessage::from_control_line(&line) {
+                    Ok(message) => message,
+                    Err(error) => {
+                        warn!(%error, "received malformed control message");
+                        continue;
+                    }
+                };
+
+                match message {
+                    ControlMessage::LogFilter { filter } => {
+                        match bridge.request(LogFilterRequest { filter }) {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => error!(error = e.0, "failed to change log filter"),
+                            Err(error) => error!(%error, "failed to change log filter"),
+                        }
+                    }
+                    ControlMessage::ReloadNative { id } => {
+                        match bridge.request(ReloadNativeRequest { id: id.clone() }) {
+                            Ok(Ok(())) => info!(id, "reloaded native"),
+                            Ok(Err(e)) => error!(error = e.0, id, "failed to reload native"),
+                            Err(error) => error!(%error, id, "failed to reload native"),
+                        }
+                    }
+                }
+            }
+        });
     }
 
     fn spawn_msg_thread(&self, console_log: MakeWriterWrapper, file_log: MakeWriterWrapper) {
@@ -139,6 +194,9 @@ impl Game {
                     MsgToParent::FileLog(s) => {
                         let _ = file_log.make_writer().write_all(s.as_bytes());
                     }
+                    MsgToParent::Event(event) => {
+                        let _ = console_log.write_event(&event);
+                    }
                 }
             }
         });
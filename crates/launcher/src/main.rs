@@ -1,10 +1,11 @@
 #![windows_subsystem = "windows"]
 #![feature(windows_process_extensions_main_thread_handle)]
 
-use std::fs::OpenOptions;
+use std::{fs::OpenOptions, path::PathBuf};
 
 use me3_env::{LauncherVars, TelemetryVars};
-use me3_launcher_attach_protocol::{AttachConfig, AttachRequest};
+use me3_ipc::bridge::OverflowStats;
+use me3_launcher_attach_protocol::{event::HostEvent, AttachConfig, AttachRequest};
 use me3_telemetry::TelemetryConfig;
 use tracing::{info, instrument, warn};
 
@@ -20,6 +21,7 @@ pub type LauncherResult<T> = eyre::Result<T>;
 fn run(
     console_log_writer: MakeWriterWrapper,
     file_log_writer: MakeWriterWrapper,
+    control_pipe_path: Option<PathBuf>,
 ) -> LauncherResult<()> {
     info!("Launcher started");
 
@@ -45,7 +47,12 @@ fn run(
     let mut game = Game::launch(&args.exe, game_path)?;
     let request = AttachRequest { config };
 
-    match game.attach(&args.host_dll, console_log_writer, file_log_writer, request) {
+    match game.attach(
+        &args.host_dll,
+        console_log_writer.clone(),
+        file_log_writer,
+        request,
+    ) {
         Ok(_) => info!("attached to game successfully"),
         Err(e) => {
             game.child.kill()?;
@@ -53,7 +60,24 @@ fn run(
         }
     }
 
-    game.join();
+    if let Some(control_pipe_path) = control_pipe_path {
+        game.forward_control_messages(control_pipe_path);
+    }
+
+    for (kind, stats) in game.join() {
+        if stats == OverflowStats::default() {
+            continue;
+        }
+
+        warn!(?kind, ?stats, "log messages were lost");
+
+        let _ = console_log_writer.write_event(&HostEvent::LogsDropped {
+            kind: format!("{kind:?}").to_lowercase(),
+            dropped: stats.dropped,
+            chunked: stats.chunked,
+            spilled: stats.spilled,
+        });
+    }
 
     Ok(())
 }
@@ -81,11 +105,30 @@ fn main() -> LauncherResult<()> {
         .enabled(telemetry_vars.enabled)
         .with_console_writer(console_log_writer.clone())
         .with_file_writer(file_log_writer.clone())
+        .with_file_format(telemetry_vars.log_format)
+        .with_process("launcher")
+        .with_trace_id(telemetry_vars.trace_id)
+        .with_log_filter(telemetry_vars.log_filter)
         .capture_panics(true);
 
     let _telemetry = me3_telemetry::install(telemetry_config);
 
-    me3_telemetry::with_root_span("launcher", "run", move || {
-        run(console_log_writer, file_log_writer)
-    })
+    let result = me3_telemetry::with_root_span("launcher", "run", {
+        let console_log_writer = console_log_writer.clone();
+        move || {
+            run(
+                console_log_writer,
+                file_log_writer,
+                telemetry_vars.control_pipe_path,
+            )
+        }
+    });
+
+    if let Err(e) = &result {
+        let _ = console_log_writer.write_event(&HostEvent::Fatal {
+            error: format!("{e:#}"),
+        });
+    }
+
+    result
 }
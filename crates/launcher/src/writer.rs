@@ -4,6 +4,7 @@ use std::{
     sync::{Arc, Mutex, MutexGuard},
 };
 
+use me3_launcher_attach_protocol::event::HostEvent;
 use tracing_subscriber::fmt::MakeWriter;
 
 #[derive(Clone)]
@@ -17,6 +18,14 @@ impl MakeWriterWrapper {
             inner: Arc::new(Mutex::new(f)),
         }
     }
+
+    /// Writes a [`HostEvent`] record that the CLI picks out of the monitor pipe.
+    pub fn write_event(&self, event: &HostEvent) -> io::Result<()> {
+        use io::Write;
+
+        self.make_writer()
+            .write_all(event.to_monitor_line().as_bytes())
+    }
 }
 
 impl<'a> MakeWriter<'a> for MakeWriterWrapper {
@@ -1,4 +1,38 @@
-use std::{ptr::NonNull, slice};
+//! BHD5, the headers of the BDT archives holding the game's files.
+//!
+//! [`Bhd5Header`] and [`Bhd5Holder`] are views of headers the game decrypted in memory, while
+//! [`Bhd5`] reads the decrypted headers boot boost caches on disk, in the format of [`cache`].
+//! [`warm_cache`] decrypts and caches headers ahead of launching the game, with the keys of
+//! [`rsa`].
+
+pub mod cache;
+pub mod rsa;
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    ptr::NonNull,
+    slice, thread,
+};
+
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit},
+    Aes128,
+};
+use me3_mod_protocol::Game;
+use thiserror::Error;
+use xxhash_rust::xxh3::xxh3_128_with_seed;
+
+use self::{cache::CacheError, rsa::RsaPublicKey};
+
+/// Seed of the hash decrypted headers are cached under.
+///
+/// When changing storage or compression defaults, don't forget to change the seed.
+const CACHE_SEED: u64 = 2;
+
+const MAGIC: &[u8; 4] = b"BHD5";
+const FILE_HEADER_SIZE: usize = 0x28;
 
 #[repr(C)]
 pub struct Bhd5Header {
@@ -62,3 +96,681 @@ impl Bhd5Holder {
         }
     }
 }
+
+/// A decrypted BHD5 header, listing the files of an archive by the hash of their path.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bhd5 {
+    /// Files are stored in buckets, by their path hash modulo the bucket count.
+    pub buckets: Vec<Vec<Bhd5File>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bhd5File {
+    pub hash: u64,
+    pub offset: u64,
+
+    /// Size of the file in the archive, without the padding of encrypted files if it's known.
+    pub size: u64,
+
+    /// Size of the file in the archive, including the padding of encrypted files.
+    pub padded_size: u64,
+
+    /// The key of the encrypted ranges of the file, if it has any.
+    pub aes_key: Option<Bhd5AesKey>,
+}
+
+/// The AES-128 key and ranges of a file that are encrypted, in ECB mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bhd5AesKey {
+    pub key: [u8; 16],
+    pub ranges: Vec<(u64, u64)>,
+}
+
+/// The archives of a game, read through the headers boot boost cached for them.
+pub struct CachedArchives {
+    pub format: Bhd5Format,
+    pub archives: Vec<CachedArchive>,
+
+    /// Archives without a cached header, e.g. because the game wasn't launched with boot boost.
+    pub uncached: Vec<String>,
+}
+
+pub struct CachedArchive {
+    /// Path of the header, relative to the game directory, with `/` separators.
+    pub name: String,
+    pub bdt_path: PathBuf,
+    pub bhd5: Bhd5,
+}
+
+/// What [`warm_cache`] did for an archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WarmStatus {
+    /// The header was already cached.
+    Cached,
+
+    /// The header was decrypted and cached.
+    Decrypted,
+
+    /// None of the keys decrypt the header.
+    NoKey,
+
+    /// One of the keys matches the header, but it couldn't be decrypted, e.g. because it's
+    /// truncated.
+    Failed(String),
+}
+
+/// Layout of the file headers and the path hash they're keyed by, which changed with Elden Ring.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bhd5Format {
+    /// Dark Souls 3 and Sekiro, with 32-bit path hashes.
+    DarkSouls3,
+
+    /// Elden Ring and later games, with 64-bit path hashes.
+    EldenRing,
+}
+
+#[derive(Debug, Error)]
+pub enum Bhd5Error {
+    #[error("not a BHD5 file")]
+    Magic,
+
+    #[error("big endian BHD5 files aren't supported")]
+    BigEndian,
+
+    #[error("BHD5 file is truncated")]
+    Truncated,
+
+    #[error("BHD5 file isn't encrypted with this key")]
+    WrongKey,
+
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+}
+
+impl Bhd5 {
+    pub fn parse(bytes: &[u8], format: Bhd5Format) -> Result<Self, Bhd5Error> {
+        let reader = Reader(bytes);
+
+        if &reader.bytes::<4>(0)? != MAGIC {
+            return Err(Bhd5Error::Magic);
+        }
+
+        if reader.bytes::<1>(4)? != [0xFF] {
+            return Err(Bhd5Error::BigEndian);
+        }
+
+        let bucket_count = reader.u32(0x10)? as usize;
+        let bucket_offset = reader.u32(0x14)? as usize;
+
+        let buckets = (0..bucket_count)
+            .map(|bucket| {
+                let bucket = bucket_offset + bucket * 8;
+
+                let file_count = reader.u32(bucket)? as usize;
+                let files_offset = reader.u32(bucket + 4)? as usize;
+
+                (0..file_count)
+                    .map(|file| reader.file(files_offset + file * FILE_HEADER_SIZE, format))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { buckets })
+    }
+
+    /// Decompresses and parses a header cached by boot boost.
+    pub fn from_cached(cached: &[u8], format: Bhd5Format) -> Result<Self, Bhd5Error> {
+        Self::parse(&cache::decode(cached)?, format)
+    }
+
+    /// Looks up a file by its path, e.g. `chr/c0000.anibnd.dcx`.
+    pub fn file(&self, path: &str, format: Bhd5Format) -> Option<&Bhd5File> {
+        let hash = path_hash(path, format);
+        let bucket = hash.checked_rem(self.buckets.len() as u64)?;
+        let bucket = self.buckets.get(bucket as usize)?;
+
+        bucket.iter().find(|file| file.hash == hash)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Bhd5File> {
+        self.buckets.iter().flatten()
+    }
+}
+
+impl Bhd5File {
+    /// Reads the file from the BDT archive its header is from, decrypting it if needed.
+    pub fn read<R: Read + Seek>(&self, bdt: &mut R) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; self.padded_size.max(self.size) as usize];
+
+        bdt.seek(SeekFrom::Start(self.offset))?;
+        bdt.read_exact(&mut data)?;
+
+        if let Some(aes_key) = &self.aes_key {
+            aes_key.decrypt(&mut data);
+        }
+
+        data.truncate(self.size as usize);
+
+        Ok(data)
+    }
+}
+
+impl Bhd5AesKey {
+    fn decrypt(&self, data: &mut [u8]) {
+        let cipher = Aes128::new(GenericArray::from_slice(&self.key));
+
+        for &(start, end) in &self.ranges {
+            let Some(range) = data.get_mut(start as usize..end as usize) else {
+                continue;
+            };
+
+            for block in range.chunks_exact_mut(16) {
+                cipher.decrypt_block(GenericArray::from_mut_slice(block));
+            }
+        }
+    }
+}
+
+impl Bhd5Format {
+    pub fn for_game(game: Game) -> Self {
+        match game {
+            Game::DarkSouls3 | Game::Sekiro => Self::DarkSouls3,
+            Game::EldenRing | Game::ArmoredCore6 | Game::Nightreign => Self::EldenRing,
+        }
+    }
+}
+
+impl CachedArchives {
+    /// Finds the `.bhd` headers in `game_dir` and its subdirectories, e.g. `sd/sd.bhd`, and reads
+    /// the decrypted copies boot boost cached for them in `cache_dir`.
+    pub fn load(game_dir: &Path, cache_dir: &Path, format: Bhd5Format) -> io::Result<Self> {
+        let mut archives = Vec::new();
+        let mut uncached = Vec::new();
+
+        for bhd_path in bhd_files(game_dir)? {
+            let name = archive_name(game_dir, &bhd_path);
+
+            let cached_path = cache_dir.join(cache_file_name(&fs::read(&bhd_path)?));
+
+            let bhd5 = fs::read(cached_path)
+                .ok()
+                .and_then(|cached| Bhd5::from_cached(&cached, format).ok());
+
+            match bhd5 {
+                Some(bhd5) => archives.push(CachedArchive {
+                    name,
+                    bdt_path: bhd_path.with_extension("bdt"),
+                    bhd5,
+                }),
+                None => uncached.push(name),
+            }
+        }
+
+        Ok(Self {
+            format,
+            archives,
+            uncached,
+        })
+    }
+
+    /// Looks up a file by its path in the archives, e.g. `chr/c0000.anibnd.dcx`.
+    pub fn find(&self, path: &str) -> Option<(&CachedArchive, &Bhd5File)> {
+        self.archives.iter().find_map(|archive| {
+            let file = archive.bhd5.file(path, self.format)?;
+            Some((archive, file))
+        })
+    }
+
+    /// Reads a file from the archives, if it's in one of them.
+    pub fn read(&self, path: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some((archive, file)) = self.find(path) else {
+            return Ok(None);
+        };
+
+        let mut bdt = File::open(&archive.bdt_path)?;
+        file.read(&mut bdt).map(Some)
+    }
+}
+
+/// Decrypts and caches the `.bhd` headers in `game_dir` that aren't cached in `cache_dir` yet,
+/// in the format boot boost reads, so the game doesn't have to decrypt them on launch.
+///
+/// Each header is decrypted with the first of `keys` it's encrypted with, on a thread of its own.
+/// Returns the status of every header, named by its path relative to the game directory.
+pub fn warm_cache(
+    game_dir: &Path,
+    cache_dir: &Path,
+    keys: &[RsaPublicKey],
+) -> io::Result<Vec<(String, WarmStatus)>> {
+    let bhd_files = bhd_files(game_dir)?;
+
+    thread::scope(|scope| {
+        let handles = bhd_files
+            .iter()
+            .map(|bhd_path| scope.spawn(move || warm_archive(bhd_path, cache_dir, keys)))
+            .collect::<Vec<_>>();
+
+        bhd_files
+            .iter()
+            .zip(handles)
+            .map(|(bhd_path, handle)| {
+                let status = handle.join().expect("thread panicked")?;
+                Ok((archive_name(game_dir, bhd_path), status))
+            })
+            .collect()
+    })
+}
+
+fn warm_archive(
+    bhd_path: &Path,
+    cache_dir: &Path,
+    keys: &[RsaPublicKey],
+) -> io::Result<WarmStatus> {
+    let encrypted = fs::read(bhd_path)?;
+    let cached_path = cache_dir.join(cache_file_name(&encrypted));
+
+    let is_cached = fs::read(&cached_path)
+        .ok()
+        .is_some_and(|cached| cache::decode(&cached).is_ok());
+
+    if is_cached {
+        return Ok(WarmStatus::Cached);
+    }
+
+    for key in keys {
+        match decrypt(&encrypted, key) {
+            Ok(decrypted) => {
+                cache::write(&cached_path, &decrypted)?;
+                return Ok(WarmStatus::Decrypted);
+            }
+            Err(Bhd5Error::WrongKey) => continue,
+            Err(e) => return Ok(WarmStatus::Failed(e.to_string())),
+        }
+    }
+
+    Ok(WarmStatus::NoKey)
+}
+
+/// Decrypts an encrypted `.bhd` file with the public key of its archive, to the same contents
+/// the game decrypts it to.
+pub fn decrypt(encrypted: &[u8], key: &RsaPublicKey) -> Result<Vec<u8>, Bhd5Error> {
+    // Check the first block before decrypting the rest with what could be the wrong key.
+    let file_size = decrypted_size(encrypted, key)?;
+
+    let mut decrypted = key.decrypt(encrypted);
+
+    // Trailing padding of the last block isn't part of the header.
+    if file_size > decrypted.len() {
+        return Err(Bhd5Error::Truncated);
+    }
+
+    decrypted.truncate(file_size);
+
+    Ok(decrypted)
+}
+
+/// Decrypts only the first block of an encrypted `.bhd` file to read the size of its header.
+fn decrypted_size(encrypted: &[u8], key: &RsaPublicKey) -> Result<usize, Bhd5Error> {
+    let first_block = encrypted.get(..key.size()).ok_or(Bhd5Error::Truncated)?;
+    let first_block = key.decrypt(first_block);
+
+    if !first_block.starts_with(MAGIC) {
+        return Err(Bhd5Error::WrongKey);
+    }
+
+    Ok(Reader(&first_block).u32(0x0C)? as usize)
+}
+
+/// The `.bhd` files in `game_dir` and its direct subdirectories, sorted by path.
+fn bhd_files(game_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut bhd_files = Vec::new();
+
+    for entry in fs::read_dir(game_dir)?.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            bhd_files.extend(fs::read_dir(&path)?.flatten().map(|entry| entry.path()));
+        } else {
+            bhd_files.push(path);
+        }
+    }
+
+    bhd_files.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("bhd"))
+    });
+
+    bhd_files.sort();
+
+    Ok(bhd_files)
+}
+
+/// Path of a header relative to the game directory, with `/` separators.
+fn archive_name(game_dir: &Path, bhd_path: &Path) -> String {
+    bhd_path
+        .strip_prefix(game_dir)
+        .unwrap_or(bhd_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// The hash files are looked up by, of their path in the archive, lowercase with `/` separators
+/// and a leading `/`.
+pub fn path_hash(path: &str, format: Bhd5Format) -> u64 {
+    let mut path = path.trim().replace('\\', "/").to_lowercase();
+
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+
+    let prime = match format {
+        Bhd5Format::DarkSouls3 => 37,
+        Bhd5Format::EldenRing => 133,
+    };
+
+    let hash = path.bytes().fold(0u64, |hash, byte| {
+        hash.wrapping_mul(prime).wrapping_add(byte as u64)
+    });
+
+    match format {
+        Bhd5Format::DarkSouls3 => hash as u32 as u64,
+        Bhd5Format::EldenRing => hash,
+    }
+}
+
+/// The file name boot boost caches the decrypted header of the encrypted BHD5 file `encrypted`
+/// under.
+pub fn cache_file_name(encrypted: &[u8]) -> String {
+    format!("{:032x}.bhd.lz4", xxh3_128_with_seed(encrypted, CACHE_SEED))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], Bhd5Error> {
+        self.0
+            .get(offset..offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(Bhd5Error::Truncated)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, Bhd5Error> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, Bhd5Error> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+
+    fn file(&self, offset: usize, format: Bhd5Format) -> Result<Bhd5File, Bhd5Error> {
+        let (hash, padded_size, unpadded_size, file_offset, aes_key_offset) = match format {
+            Bhd5Format::DarkSouls3 => (
+                self.u32(offset)? as u64,
+                self.u32(offset + 0x04)? as u64,
+                self.u64(offset + 0x20)?,
+                self.u64(offset + 0x08)?,
+                self.u64(offset + 0x18)? as usize,
+            ),
+            Bhd5Format::EldenRing => (
+                self.u64(offset)?,
+                self.u32(offset + 0x08)? as u64,
+                self.u32(offset + 0x0C)? as u64,
+                self.u64(offset + 0x10)?,
+                self.u64(offset + 0x20)? as usize,
+            ),
+        };
+
+        let aes_key = match aes_key_offset {
+            0 => None,
+            offset => Some(self.aes_key(offset)?),
+        };
+
+        Ok(Bhd5File {
+            hash,
+            offset: file_offset,
+            size: if unpadded_size != 0 {
+                unpadded_size
+            } else {
+                padded_size
+            },
+            padded_size,
+            aes_key,
+        })
+    }
+
+    fn aes_key(&self, offset: usize) -> Result<Bhd5AesKey, Bhd5Error> {
+        let key = self.bytes::<16>(offset)?;
+        let range_count = self.u32(offset + 0x10)? as usize;
+
+        let ranges = (0..range_count)
+            .map(|range| {
+                let range = offset + 0x14 + range * 0x10;
+                Ok((self.u64(range)?, self.u64(range + 8)?))
+            })
+            .collect::<Result<Vec<_>, Bhd5Error>>()?
+            .into_iter()
+            // Unused ranges are stored as -1.
+            .filter(|&(start, end)| start != u64::MAX && end != u64::MAX && start < end)
+            .collect();
+
+        Ok(Bhd5AesKey { key, ranges })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::Cursor};
+
+    use aes::{
+        cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit},
+        Aes128,
+    };
+    use num_bigint::BigUint;
+
+    use super::{
+        cache, path_hash, rsa::RsaPublicKey, warm_cache, Bhd5, Bhd5AesKey, Bhd5File, Bhd5Format,
+        CachedArchives, WarmStatus, FILE_HEADER_SIZE,
+    };
+
+    /// A 512-bit key, and the private exponent to encrypt headers with.
+    const MODULUS: &[u8] = b"f887f33bc2b0ecbb45245a46555081fd741fd24d558f560e847c80dbc901d233fe50e21f13cbc6860b77a97cc32ae838d23def8bf5a6e36c5e235cf813dea3df";
+    const PRIVATE_EXPONENT: &[u8] = b"18c74522e2284d91e634d7a347823d538dae56834b527d1bf96d885361590321e59f2738a47c10e9a12b49dcf713aa771ae8605b9286c82d45ea0b2bea3769f1";
+
+    /// Builds an Elden Ring header with `paths` spread over two buckets.
+    fn bhd5(paths: &[&str]) -> Vec<u8> {
+        let mut buckets = [Vec::new(), Vec::new()];
+
+        for (index, path) in paths.iter().enumerate() {
+            let hash = path_hash(path, Bhd5Format::EldenRing);
+            buckets[(hash % 2) as usize].push((hash, index as u64));
+        }
+
+        let bucket_offset = 0x1C;
+        let mut files_offset = bucket_offset + buckets.len() * 8;
+
+        let mut out = b"BHD5\xFF\0\0\0\x01\0\0\0".to_vec();
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&(buckets.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(bucket_offset as u32).to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+
+        for bucket in &buckets {
+            out.extend_from_slice(&(bucket.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(files_offset as u32).to_le_bytes());
+            files_offset += bucket.len() * FILE_HEADER_SIZE;
+        }
+
+        for (hash, index) in buckets.iter().flatten() {
+            out.extend_from_slice(&hash.to_le_bytes());
+            out.extend_from_slice(&0x100u32.to_le_bytes());
+            out.extend_from_slice(&0xF0u32.to_le_bytes());
+            out.extend_from_slice(&(index * 0x100).to_le_bytes());
+            out.extend_from_slice(&[0; 16]);
+        }
+
+        out
+    }
+
+    /// Encrypts a header with the private key, the way the games' headers are.
+    fn encrypt(decrypted: &[u8]) -> (RsaPublicKey, Vec<u8>) {
+        let modulus = BigUint::parse_bytes(MODULUS, 16).unwrap();
+        let private_exponent = BigUint::parse_bytes(PRIVATE_EXPONENT, 16).unwrap();
+
+        let mut encrypted = Vec::new();
+
+        for block in decrypted.chunks(63) {
+            let mut block = block.to_vec();
+            block.resize(63, 0);
+
+            let block = BigUint::from_bytes_be(&block)
+                .modpow(&private_exponent, &modulus)
+                .to_bytes_be();
+
+            encrypted.resize(encrypted.len() + 64 - block.len(), 0);
+            encrypted.extend_from_slice(&block);
+        }
+
+        let key = RsaPublicKey::new(&modulus.to_bytes_be(), &[1, 0, 1]);
+
+        (key, encrypted)
+    }
+
+    #[test]
+    fn path_hashes_are_normalized() {
+        let hash = path_hash("/chr/c0000.anibnd.dcx", Bhd5Format::EldenRing);
+
+        assert_eq!(
+            path_hash("Chr\\C0000.anibnd.dcx", Bhd5Format::EldenRing),
+            hash
+        );
+        assert_eq!(path_hash("/", Bhd5Format::DarkSouls3), b'/' as u64);
+        assert!(path_hash("/chr/c0000.anibnd.dcx", Bhd5Format::DarkSouls3) <= u32::MAX as u64);
+    }
+
+    #[test]
+    fn files_are_found_by_path() {
+        let paths = ["regulation.bin", "chr/c0000.anibnd.dcx", "sd/init.bnk"];
+        let bhd5 = Bhd5::parse(&bhd5(&paths), Bhd5Format::EldenRing).unwrap();
+
+        assert_eq!(bhd5.files().count(), 3);
+        assert_eq!(
+            bhd5.file("/Chr/c0000.anibnd.dcx", Bhd5Format::EldenRing),
+            Some(&Bhd5File {
+                hash: path_hash("chr/c0000.anibnd.dcx", Bhd5Format::EldenRing),
+                offset: 0x100,
+                size: 0xF0,
+                padded_size: 0x100,
+                aes_key: None,
+            })
+        );
+        assert!(bhd5
+            .file("chr/c0001.anibnd.dcx", Bhd5Format::EldenRing)
+            .is_none());
+    }
+
+    #[test]
+    fn headers_without_buckets_have_no_files() {
+        let mut bytes = bhd5(&[]);
+        bytes[0x10..0x14].copy_from_slice(&0u32.to_le_bytes());
+
+        let bhd5 = Bhd5::parse(&bytes, Bhd5Format::EldenRing).unwrap();
+
+        assert_eq!(bhd5.files().count(), 0);
+        assert!(bhd5.file("regulation.bin", Bhd5Format::EldenRing).is_none());
+    }
+
+    #[test]
+    fn cached_headers_are_decompressed() {
+        let bytes = bhd5(&["regulation.bin"]);
+
+        let mut cached = cache::encode(&bytes);
+
+        assert_eq!(
+            Bhd5::from_cached(&cached, Bhd5Format::EldenRing).unwrap(),
+            Bhd5::parse(&bytes, Bhd5Format::EldenRing).unwrap()
+        );
+
+        cached[0] ^= 1;
+        assert!(Bhd5::from_cached(&cached, Bhd5Format::EldenRing).is_err());
+    }
+
+    #[test]
+    fn encrypted_ranges_are_decrypted() {
+        let key = [7; 16];
+        let plain = (0..0x40).collect::<Vec<u8>>();
+
+        let mut encrypted = plain.clone();
+        let cipher = Aes128::new(GenericArray::from_slice(&key));
+        cipher.encrypt_block(GenericArray::from_mut_slice(&mut encrypted[0x10..0x20]));
+
+        let mut bdt = vec![0xAA; 8];
+        bdt.extend_from_slice(&encrypted);
+
+        let file = Bhd5File {
+            hash: 0,
+            offset: 8,
+            size: 0x38,
+            padded_size: 0x40,
+            aes_key: Some(Bhd5AesKey {
+                key,
+                ranges: vec![(0x10, 0x20)],
+            }),
+        };
+
+        assert_eq!(file.read(&mut Cursor::new(bdt)).unwrap(), &plain[..0x38]);
+    }
+
+    #[test]
+    fn headers_are_decrypted_into_the_cache() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_dir = test_dir.path();
+        let game_dir = test_dir.join("game");
+        let cache_dir = test_dir.join("cache");
+
+        fs::create_dir_all(game_dir.join("sd")).unwrap();
+        fs::create_dir_all(&cache_dir).unwrap();
+
+        let mut decrypted = bhd5(&["regulation.bin", "chr/c0000.anibnd.dcx", "sd/init.bnk"]);
+        let file_size = decrypted.len() as u32;
+        decrypted[0x0C..0x10].copy_from_slice(&file_size.to_le_bytes());
+
+        let (key, encrypted) = encrypt(&decrypted);
+        assert_eq!(super::decrypt(&encrypted, &key).unwrap(), decrypted);
+        assert_eq!(
+            super::decrypted_size(&encrypted, &key).unwrap(),
+            decrypted.len()
+        );
+
+        fs::write(game_dir.join("Data0.bhd"), &encrypted).unwrap();
+        fs::write(
+            game_dir.join("sd/sd.bhd"),
+            &encrypted[..encrypted.len() - 64],
+        )
+        .unwrap();
+
+        let other_key = RsaPublicKey::new(&[0xFF; 64], &[1, 0, 1]);
+
+        assert_eq!(
+            warm_cache(&game_dir, &cache_dir, &[other_key.clone(), key.clone()]).unwrap(),
+            [
+                ("Data0.bhd".to_owned(), WarmStatus::Decrypted),
+                (
+                    "sd/sd.bhd".to_owned(),
+                    WarmStatus::Failed("BHD5 file is truncated".to_owned())
+                ),
+            ]
+        );
+        assert_eq!(
+            warm_cache(&game_dir, &cache_dir, &[other_key]).unwrap(),
+            [
+                ("Data0.bhd".to_owned(), WarmStatus::Cached),
+                ("sd/sd.bhd".to_owned(), WarmStatus::NoKey),
+            ]
+        );
+
+        let archives = CachedArchives::load(&game_dir, &cache_dir, Bhd5Format::EldenRing).unwrap();
+
+        assert_eq!(archives.uncached, ["sd/sd.bhd"]);
+        assert!(archives.find("chr/c0000.anibnd.dcx").is_some());
+    }
+}
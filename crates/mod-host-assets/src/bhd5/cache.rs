@@ -0,0 +1,222 @@
+//! The format boot boost caches decrypted headers in.
+//!
+//! A cached header starts with a magic value, the format version, the size of the header and of
+//! its compressed data, and an XXH3 checksum of the header, all little endian. The header
+//! follows, compressed as an LZ4 block, which decompresses fast enough to be read while the game
+//! waits.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use xxhash_rust::xxh3::xxh3_64;
+
+const MAGIC: &[u8; 8] = b"ME3BHD5\0";
+const VERSION: u32 = 1;
+
+const HEADER_SIZE: usize = 0x28;
+
+/// The most an LZ4 block can expand by: every extra 255 bytes of a match take one more byte.
+const MAX_COMPRESSION_RATIO: usize = 255;
+
+/// The fixed size header of a cached file, read before allocating memory for its contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheHeader {
+    /// Size of the decrypted header.
+    pub size: usize,
+    pub compressed_size: usize,
+    pub checksum: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("not a cached header, or cached by an older version")]
+    Magic,
+
+    #[error("cached header version {0} isn't supported")]
+    UnsupportedVersion(u32),
+
+    #[error("cached header is truncated")]
+    Truncated,
+
+    #[error("cached header is {0} bytes, but {1} bytes were expected")]
+    Size(usize, usize),
+
+    #[error("cached header claims {0} bytes, more than {1} compressed bytes can hold")]
+    Implausible(usize, usize),
+
+    #[error("cached header can't be decompressed: {0}")]
+    Decompress(#[from] lz4_flex::block::DecompressError),
+
+    #[error("cached header checksum doesn't match its contents")]
+    Checksum,
+}
+
+impl CacheHeader {
+    pub fn parse(cached: &[u8]) -> Result<Self, CacheError> {
+        let header = cached.get(..HEADER_SIZE).ok_or(CacheError::Truncated)?;
+
+        if &header[..8] != MAGIC {
+            return Err(CacheError::Magic);
+        }
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(header[offset..offset + 8].try_into().unwrap());
+
+        let version = u32_at(0x08);
+        if version != VERSION {
+            return Err(CacheError::UnsupportedVersion(version));
+        }
+
+        let size = u64_at(0x10) as usize;
+        let compressed_size = u64_at(0x18) as usize;
+
+        let max_size = compressed_size.saturating_mul(MAX_COMPRESSION_RATIO);
+        if size > max_size {
+            return Err(CacheError::Implausible(size, compressed_size));
+        }
+
+        Ok(Self {
+            size,
+            compressed_size,
+            checksum: u64_at(0x20),
+        })
+    }
+}
+
+/// Compresses a decrypted header into the cache format.
+pub fn encode(decrypted: &[u8]) -> Vec<u8> {
+    let compressed = lz4_flex::block::compress(decrypted);
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + compressed.len());
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(decrypted.len() as u64).to_le_bytes());
+    out.extend_from_slice(&(compressed.len() as u64).to_le_bytes());
+    out.extend_from_slice(&xxh3_64(decrypted).to_le_bytes());
+    out.extend_from_slice(&compressed);
+
+    out
+}
+
+/// Decompresses a cached header into `out`, which must be exactly as large as the header, and
+/// verifies its checksum.
+pub fn decode_into(cached: &[u8], out: &mut [u8]) -> Result<(), CacheError> {
+    let header = CacheHeader::parse(cached)?;
+
+    if header.size != out.len() {
+        return Err(CacheError::Size(header.size, out.len()));
+    }
+
+    let compressed = HEADER_SIZE
+        .checked_add(header.compressed_size)
+        .and_then(|end| cached.get(HEADER_SIZE..end))
+        .ok_or(CacheError::Truncated)?;
+
+    let size = lz4_flex::block::decompress_into(compressed, out)?;
+
+    if size != header.size {
+        return Err(CacheError::Size(size, header.size));
+    }
+
+    if xxh3_64(out) != header.checksum {
+        return Err(CacheError::Checksum);
+    }
+
+    Ok(())
+}
+
+/// Decompresses a cached header and verifies its checksum.
+pub fn decode(cached: &[u8]) -> Result<Vec<u8>, CacheError> {
+    let header = CacheHeader::parse(cached)?;
+
+    let mut out = vec![0; header.size];
+    decode_into(cached, &mut out)?;
+
+    Ok(out)
+}
+
+/// Compresses and writes a decrypted header to `path`, through a temporary file so a partially
+/// written cache is never read.
+pub fn write(path: &Path, decrypted: &[u8]) -> io::Result<()> {
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+
+    fs::write(&partial_path, encode(decrypted))?;
+    fs::rename(partial_path, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, decode_into, encode, CacheError, CacheHeader};
+
+    #[test]
+    fn headers_round_trip() {
+        let decrypted = (0..0x4000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let cached = encode(&decrypted);
+
+        assert!(cached.len() < decrypted.len());
+        assert_eq!(CacheHeader::parse(&cached).unwrap().size, decrypted.len());
+        assert_eq!(decode(&cached).unwrap(), decrypted);
+
+        let mut out = vec![0; decrypted.len() - 1];
+        assert!(matches!(
+            decode_into(&cached, &mut out),
+            Err(CacheError::Size(..))
+        ));
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let cached = encode(b"BHD5 header contents, BHD5 header contents");
+
+        let mut corrupted = cached.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        assert!(decode(&corrupted).is_err());
+
+        let mut corrupted = cached.clone();
+        corrupted[0x20] ^= 1;
+        assert!(matches!(decode(&corrupted), Err(CacheError::Checksum)));
+
+        assert!(matches!(
+            decode(&cached[..cached.len() - 1]),
+            Err(CacheError::Truncated)
+        ));
+
+        // The previous format: a u32 size followed by raw DEFLATE data.
+        assert!(matches!(
+            decode(&[4, 0, 0, 0, 1, 2]),
+            Err(CacheError::Truncated)
+        ));
+        assert!(matches!(decode(&[0; 0x30]), Err(CacheError::Magic)));
+    }
+
+    #[test]
+    fn sizes_are_checked_before_allocating() {
+        let zeroes = vec![0; 0x100000];
+        let cached = encode(&zeroes);
+        assert_eq!(decode(&cached).unwrap(), zeroes);
+
+        let mut oversized = cached.clone();
+        oversized[0x10..0x18].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            decode(&oversized),
+            Err(CacheError::Implausible(..))
+        ));
+
+        let mut overflowing = cached;
+        overflowing[0x10..0x18].copy_from_slice(&0u64.to_le_bytes());
+        overflowing[0x18..0x20].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(decode(&overflowing), Err(CacheError::Truncated)));
+    }
+}
@@ -0,0 +1,196 @@
+//! The RSA public keys `.bhd` files are encrypted for.
+//!
+//! Headers are encrypted with the private key of their archive, in blocks of the key's size and
+//! without padding, so they're decrypted with textbook RSA. Every block decrypts to one byte less
+//! than the size of the key.
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use num_bigint::BigUint;
+use pkcs1::der::Decode;
+use thiserror::Error;
+
+const PEM_BEGIN: &str = "-----BEGIN RSA PUBLIC KEY-----";
+const PEM_END: &str = "-----END RSA PUBLIC KEY-----";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    modulus: BigUint,
+    exponent: BigUint,
+
+    /// Size of the modulus in bytes, and of the encrypted blocks.
+    size: usize,
+}
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("malformed PEM")]
+    Pem,
+
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("invalid RSA public key: {0}")]
+    Der(#[from] pkcs1::der::Error),
+}
+
+impl RsaPublicKey {
+    /// A key from its big endian modulus and public exponent.
+    pub fn new(modulus: &[u8], exponent: &[u8]) -> Self {
+        let modulus = BigUint::from_bytes_be(modulus);
+
+        Self {
+            size: modulus.bits().div_ceil(8) as usize,
+            modulus,
+            exponent: BigUint::from_bytes_be(exponent),
+        }
+    }
+
+    /// Parses a PKCS#1 key in PEM format, as the games embed them.
+    pub fn from_pem(pem: &str) -> Result<Self, KeyError> {
+        let mut lines = pem.trim_matches(char::from(0)).trim().lines();
+
+        let _ = lines
+            .next()
+            .filter(|line| line.trim() == PEM_BEGIN)
+            .ok_or(KeyError::Pem)?;
+
+        let _ = lines
+            .next_back()
+            .filter(|line| line.trim() == PEM_END)
+            .ok_or(KeyError::Pem)?;
+
+        let is_base64char = |c: &char| c.is_ascii_alphanumeric() | ['+', '/', '='].contains(c);
+
+        let mut normalized = String::with_capacity(pem.len());
+        normalized.extend(lines.flat_map(|line| line.chars().filter(is_base64char)));
+
+        let der = BASE64_STANDARD.decode(&normalized)?;
+        let key = pkcs1::RsaPublicKey::from_der(&der)?;
+
+        Ok(Self::new(
+            key.modulus.as_bytes(),
+            key.public_exponent.as_bytes(),
+        ))
+    }
+
+    /// Size of the modulus in bytes, and of the encrypted blocks.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Decrypts `encrypted` one block at a time, ignoring a trailing partial block.
+    pub fn decrypt(&self, encrypted: &[u8]) -> Vec<u8> {
+        let block_size = self.size.saturating_sub(1);
+
+        let mut decrypted = Vec::with_capacity(encrypted.len() / self.size.max(1) * block_size);
+
+        for block in encrypted.chunks_exact(self.size.max(1)) {
+            let block = BigUint::from_bytes_be(block)
+                .modpow(&self.exponent, &self.modulus)
+                .to_bytes_be();
+
+            // Decrypted blocks are padded with leading zeros to a fixed size.
+            let padding = block_size.saturating_sub(block.len());
+            decrypted.resize(decrypted.len() + padding, 0);
+            decrypted.extend_from_slice(&block);
+        }
+
+        decrypted
+    }
+}
+
+/// Finds the PEM encoded keys in `bytes`, e.g. the executable of a game, which embeds the keys
+/// of its archives.
+pub fn find_pem_keys(bytes: &[u8]) -> Vec<&str> {
+    let is_pem_char = |b: &u8| b.is_ascii_alphanumeric() || b"+/=\r\n".contains(b);
+
+    let mut keys = Vec::new();
+    let mut rest = bytes;
+
+    while let Some(start) = find(rest, PEM_BEGIN.as_bytes()) {
+        rest = &rest[start..];
+
+        let body_len = rest[PEM_BEGIN.len()..]
+            .iter()
+            .take_while(|b| is_pem_char(b))
+            .count();
+
+        let end = PEM_BEGIN.len() + body_len;
+
+        if rest[end..].starts_with(PEM_END.as_bytes()) {
+            let (pem, after) = rest.split_at(end + PEM_END.len());
+
+            // Only ASCII was matched.
+            keys.push(str::from_utf8(pem).unwrap());
+            rest = after;
+        } else {
+            rest = &rest[PEM_BEGIN.len()..];
+        }
+    }
+
+    keys
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+
+    use super::{find_pem_keys, KeyError, RsaPublicKey};
+
+    const PEM: &str = "-----BEGIN RSA PUBLIC KEY-----
+MEgCQQD4h/M7wrDsu0UkWkZVUIH9dB/STVWPVg6EfIDbyQHSM/5Q4h8Ty8aGC3ep
+fMMq6DjSPe+L9abjbF4jXPgT3qPfAgMBAAE=
+-----END RSA PUBLIC KEY-----
+";
+
+    #[test]
+    fn blocks_are_decrypted_with_the_public_key() {
+        // 61 * 53, with the private exponent 2753.
+        let key = RsaPublicKey::new(&3233u32.to_be_bytes(), &17u32.to_be_bytes());
+        assert_eq!(key.size(), 2);
+
+        let private = |m: u32| {
+            let c = BigUint::from(m).modpow(&BigUint::from(2753u32), &BigUint::from(3233u32));
+            (c.to_u32_digits().first().copied().unwrap_or(0) as u16).to_be_bytes()
+        };
+
+        let encrypted = [private(b'B' as u32), private(0), private(b'D' as u32)].concat();
+
+        assert_eq!(key.decrypt(&encrypted), b"B\0D");
+        assert_eq!(key.decrypt(&encrypted[..5]), b"B\0");
+    }
+
+    #[test]
+    fn pem_keys_are_parsed() {
+        let key = RsaPublicKey::from_pem(PEM).unwrap();
+        assert_eq!(key.size(), 64);
+
+        assert_eq!(
+            RsaPublicKey::from_pem(&format!("{}\0\0", PEM.replace('\n', "\r\n"))).unwrap(),
+            key
+        );
+
+        assert!(matches!(
+            RsaPublicKey::from_pem(&PEM[1..]),
+            Err(KeyError::Pem)
+        ));
+    }
+
+    #[test]
+    fn pem_keys_are_found_in_executables() {
+        let exe = [
+            b"\0\0-----BEGIN RSA PUBLIC KEY-----\0".as_slice(),
+            PEM.as_bytes(),
+            b"\0\x90-----BEGIN RSA PUBLIC KEY-----\nAB CD\n-----END RSA PUBLIC KEY-----",
+        ]
+        .concat();
+
+        assert_eq!(find_pem_keys(&exe), [PEM.trim_end()]);
+    }
+}
@@ -0,0 +1,774 @@
+//! BND4, the binder archive format of Dark Souls 3 and later games.
+//!
+//! Binders are read into memory in full and written back with the same header flags. Entry data
+//! is kept as it is, so entries that are compressed inside of the binder stay compressed.
+
+use thiserror::Error;
+
+use crate::dcx::{self, Dcx, DcxError, KrakenDecompressor};
+
+const MAGIC: &[u8; 4] = b"BND4";
+const HEADER_SIZE: usize = 0x40;
+
+/// The binder has a hash table of entry names, used by the game to find entries.
+const EXTENDED_HASH_TABLE: u8 = 4;
+
+/// Flags of the `format` header field, which decide what each entry header holds.
+mod format {
+    pub const BIG_ENDIAN: u8 = 0b0000_0001;
+    pub const IDS: u8 = 0b0000_0010;
+    pub const NAMES1: u8 = 0b0000_0100;
+    pub const NAMES2: u8 = 0b0000_1000;
+    pub const LONG_OFFSETS: u8 = 0b0001_0000;
+    pub const COMPRESSION: u8 = 0b0010_0000;
+    pub const FLAG7: u8 = 0b1000_0000;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bnd4 {
+    unk04: u8,
+    unk05: u8,
+    bit_big_endian: bool,
+    version: [u8; 8],
+    unicode: bool,
+    format: u8,
+    extended: u8,
+    pub entries: Vec<Bnd4Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bnd4Entry {
+    /// Per-entry flags, e.g. whether the entry is compressed.
+    pub flags: u8,
+    pub id: i32,
+    pub name: String,
+    pub data: Vec<u8>,
+
+    /// Size of the entry once decompressed, if it is compressed inside of the binder.
+    pub uncompressed_size: Option<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum Bnd4Error {
+    #[error("not a BND4 binder")]
+    NotBnd4,
+
+    #[error("BND4 binder is truncated")]
+    Truncated,
+
+    #[error("big endian BND4 binders aren't supported")]
+    BigEndian,
+
+    #[error("BND4 binder entries have no names")]
+    Unnamed,
+
+    #[error("BND4 entry name {0:?} can't be stored in a binder without unicode names")]
+    NonAsciiName(String),
+
+    #[error(transparent)]
+    Dcx(#[from] DcxError),
+}
+
+impl Bnd4 {
+    /// Creates an empty binder with the header flags the games use for named entries.
+    pub fn new() -> Self {
+        Self {
+            unk04: 0,
+            unk05: 0,
+            bit_big_endian: false,
+            version: *b"07D7R6\0\0",
+            unicode: true,
+            format: format::IDS | format::NAMES1 | format::NAMES2 | format::COMPRESSION,
+            extended: EXTENDED_HASH_TABLE,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, Bnd4Error> {
+        let reader = Reader(bytes);
+
+        if &reader.bytes::<4>(0)? != MAGIC {
+            return Err(Bnd4Error::NotBnd4);
+        }
+
+        if reader.u8(0x09)? != 0 {
+            return Err(Bnd4Error::BigEndian);
+        }
+
+        let bit_big_endian = reader.u8(0x0A)? == 0;
+        let file_count = reader.u32(0x0C)? as usize;
+        let file_header_size = reader.usize(0x20)?;
+        let unicode = reader.u8(0x30)? != 0;
+
+        let raw_format = reader.u8(0x31)?;
+        let reverse = bit_big_endian
+            || (raw_format & format::BIG_ENDIAN != 0 && raw_format & format::FLAG7 == 0);
+        let format = if reverse {
+            raw_format
+        } else {
+            raw_format.reverse_bits()
+        };
+
+        if format & format::BIG_ENDIAN != 0 {
+            return Err(Bnd4Error::BigEndian);
+        }
+
+        // Checking that every entry header is in the binder also keeps a corrupt entry count from
+        // reading the same header over and over.
+        file_count
+            .checked_mul(file_header_size)
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .filter(|&headers_end| headers_end <= bytes.len())
+            .ok_or(Bnd4Error::Truncated)?;
+
+        let entries = (0..file_count)
+            .map(|index| {
+                read_entry(
+                    &reader,
+                    HEADER_SIZE + index * file_header_size,
+                    format,
+                    bit_big_endian,
+                    unicode,
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            unk04: reader.u8(0x04)?,
+            unk05: reader.u8(0x05)?,
+            bit_big_endian,
+            version: reader.bytes::<8>(0x18)?,
+            unicode,
+            format,
+            extended: reader.u8(0x32)?,
+            entries,
+        })
+    }
+
+    pub fn has_names(&self) -> bool {
+        self.format & (format::NAMES1 | format::NAMES2) != 0
+    }
+
+    /// Finds the entry named `name`, comparing names the way the game hashes them.
+    pub fn entry(&self, name: &str) -> Option<&Bnd4Entry> {
+        let key = normalize_name(name);
+
+        self.entries
+            .iter()
+            .find(|entry| normalize_name(&entry.name) == key)
+    }
+
+    pub fn entry_mut(&mut self, name: &str) -> Option<&mut Bnd4Entry> {
+        let key = normalize_name(name);
+
+        self.entries
+            .iter_mut()
+            .find(|entry| normalize_name(&entry.name) == key)
+    }
+
+    /// Adds `entry`, replacing the entry with the same name if there is one.
+    pub fn merge_entry(&mut self, entry: Bnd4Entry) {
+        match self.entry_mut(&entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Adds the entries of `other`, replacing entries with the same name.
+    pub fn merge(&mut self, other: Bnd4) -> Result<(), Bnd4Error> {
+        if !self.has_names() || !other.has_names() {
+            return Err(Bnd4Error::Unnamed);
+        }
+
+        for entry in other.entries {
+            self.merge_entry(entry);
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Bnd4Error> {
+        let header_size = self.entry_header_size();
+        let names_offset = HEADER_SIZE + self.entries.len() * header_size;
+
+        let mut names = Vec::new();
+        let mut name_offsets = Vec::with_capacity(self.entries.len());
+
+        if self.has_names() {
+            for entry in &self.entries {
+                name_offsets.push(names_offset + names.len());
+                self.write_name(&mut names, &entry.name)?;
+            }
+        }
+
+        let mut out = vec![0; names_offset];
+        out.extend_from_slice(&names);
+
+        let hash_table_offset = if self.extended == EXTENDED_HASH_TABLE {
+            pad(&mut out, 8);
+
+            let offset = out.len();
+            write_hash_table(&mut out, &self.entries);
+            offset
+        } else {
+            0
+        };
+
+        let headers_end = out.len();
+
+        let mut data_offsets = Vec::with_capacity(self.entries.len());
+
+        for entry in &self.entries {
+            if !entry.data.is_empty() {
+                pad(&mut out, 0x10);
+            }
+
+            data_offsets.push(out.len());
+            out.extend_from_slice(&entry.data);
+        }
+
+        let mut header = Writer(&mut out[..HEADER_SIZE]);
+
+        header.bytes(0x00, MAGIC);
+        header.u8(0x04, self.unk04);
+        header.u8(0x05, self.unk05);
+        header.u8(0x0A, !self.bit_big_endian as u8);
+        header.u32(0x0C, self.entries.len() as u32);
+        header.u64(0x10, HEADER_SIZE as u64);
+        header.bytes(0x18, &self.version);
+        header.u64(0x20, header_size as u64);
+        header.u64(0x28, headers_end as u64);
+        header.u8(0x30, self.unicode as u8);
+        header.u8(0x31, self.raw_format());
+        header.u8(0x32, self.extended);
+        header.u64(0x38, hash_table_offset as u64);
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            let start = HEADER_SIZE + index * header_size;
+            let mut header = Writer(&mut out[start..start + header_size]);
+
+            header.u8(0, self.raw_entry_flags(entry.flags));
+            header.u32(4, u32::MAX);
+            header.u64(8, entry.data.len() as u64);
+
+            let mut offset = 16;
+
+            if self.format & format::COMPRESSION != 0 {
+                let uncompressed_size = entry.uncompressed_size.unwrap_or(entry.data.len() as u64);
+                header.u64(offset, uncompressed_size);
+                offset += 8;
+            }
+
+            if self.format & format::LONG_OFFSETS != 0 {
+                header.u64(offset, data_offsets[index] as u64);
+                offset += 8;
+            } else {
+                header.u32(offset, data_offsets[index] as u32);
+                offset += 4;
+            }
+
+            if self.format & format::IDS != 0 {
+                header.u32(offset, entry.id as u32);
+                offset += 4;
+            }
+
+            if self.has_names() {
+                header.u32(offset, name_offsets[index] as u32);
+                offset += 4;
+            }
+
+            if self.format == format::NAMES1 {
+                header.u32(offset, entry.id as u32);
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn entry_header_size(&self) -> usize {
+        let mut size = 16;
+
+        if self.format & format::COMPRESSION != 0 {
+            size += 8;
+        }
+
+        size += if self.format & format::LONG_OFFSETS != 0 {
+            8
+        } else {
+            4
+        };
+
+        if self.format & format::IDS != 0 {
+            size += 4;
+        }
+
+        if self.has_names() {
+            size += 4;
+        }
+
+        if self.format == format::NAMES1 {
+            size += 8;
+        }
+
+        size
+    }
+
+    fn raw_format(&self) -> u8 {
+        let reverse = self.bit_big_endian
+            || (self.format & format::BIG_ENDIAN != 0 && self.format & format::FLAG7 == 0);
+
+        if reverse {
+            self.format
+        } else {
+            self.format.reverse_bits()
+        }
+    }
+
+    fn raw_entry_flags(&self, flags: u8) -> u8 {
+        if reverses_entry_flags(self.format, self.bit_big_endian) {
+            flags
+        } else {
+            flags.reverse_bits()
+        }
+    }
+
+    fn write_name(&self, out: &mut Vec<u8>, name: &str) -> Result<(), Bnd4Error> {
+        if self.unicode {
+            for unit in name.encode_utf16().chain([0]) {
+                out.extend_from_slice(&unit.to_le_bytes());
+            }
+        } else {
+            if !name.is_ascii() {
+                return Err(Bnd4Error::NonAsciiName(name.to_owned()));
+            }
+
+            out.extend_from_slice(name.as_bytes());
+            out.push(0);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Bnd4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Merges the binders overriding the same file into one, giving entries from later binders
+/// precedence over entries with the same name in earlier ones.
+///
+/// Binders may be DCX compressed. The merged binder is DCX compressed if the last binder was.
+pub fn merge<'a, I>(
+    binders: I,
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<Vec<u8>, Bnd4Error>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut merged: Option<Bnd4> = None;
+    let mut compressed = false;
+
+    for bytes in binders {
+        compressed = Dcx::is_dcx(bytes);
+
+        let binder = if compressed {
+            Bnd4::parse(&Dcx::parse(bytes)?.decompress(kraken)?)?
+        } else {
+            Bnd4::parse(bytes)?
+        };
+
+        match &mut merged {
+            Some(merged) => merged.merge(binder)?,
+            None => merged = Some(binder),
+        }
+    }
+
+    let merged = merged.unwrap_or_default().to_bytes()?;
+
+    if compressed {
+        Ok(dcx::compress_deflate(&merged))
+    } else {
+        Ok(merged)
+    }
+}
+
+fn read_entry(
+    reader: &Reader,
+    offset: usize,
+    format: u8,
+    bit_big_endian: bool,
+    unicode: bool,
+) -> Result<Bnd4Entry, Bnd4Error> {
+    let raw_flags = reader.u8(offset)?;
+    let flags = if reverses_entry_flags(format, bit_big_endian) {
+        raw_flags
+    } else {
+        raw_flags.reverse_bits()
+    };
+
+    let size = reader.usize(offset + 8)?;
+    let mut field = offset + 16;
+
+    let uncompressed_size = if format & format::COMPRESSION != 0 {
+        let uncompressed_size = reader.u64(field)?;
+        field += 8;
+        Some(uncompressed_size)
+    } else {
+        None
+    };
+
+    let data_offset = if format & format::LONG_OFFSETS != 0 {
+        field += 8;
+        reader.usize(field - 8)?
+    } else {
+        field += 4;
+        reader.u32(field - 4)? as usize
+    };
+
+    let mut id = -1;
+
+    if format & format::IDS != 0 {
+        id = reader.u32(field)? as i32;
+        field += 4;
+    }
+
+    let mut name = String::new();
+
+    if format & (format::NAMES1 | format::NAMES2) != 0 {
+        let name_offset = reader.u32(field)? as usize;
+        field += 4;
+
+        name = if unicode {
+            reader.utf16_c_str(name_offset)?
+        } else {
+            reader.c_str(name_offset)?
+        };
+    }
+
+    if format == format::NAMES1 {
+        id = reader.u32(field)? as i32;
+    }
+
+    let data = data_offset
+        .checked_add(size)
+        .and_then(|end| reader.0.get(data_offset..end))
+        .ok_or(Bnd4Error::Truncated)?
+        .to_vec();
+
+    Ok(Bnd4Entry {
+        flags,
+        id,
+        name,
+        // Uncompressed entries store their own size here, which is recomputed when writing.
+        uncompressed_size: uncompressed_size.filter(|&uncompressed| uncompressed != size as u64),
+        data,
+    })
+}
+
+fn reverses_entry_flags(format: u8, bit_big_endian: bool) -> bool {
+    bit_big_endian || (format & format::BIG_ENDIAN != 0 && format & format::FLAG7 == 0)
+}
+
+/// Lower-cases a name and uses `/` separators with a leading `/`, like the game does before
+/// hashing it.
+fn normalize_name(name: &str) -> String {
+    let mut normalized = name.to_lowercase().replace('\\', "/");
+
+    if !normalized.starts_with('/') {
+        normalized.insert(0, '/');
+    }
+
+    normalized
+}
+
+fn name_hash(name: &str) -> u32 {
+    normalize_name(name)
+        .encode_utf16()
+        .fold(0u32, |hash, unit| {
+            hash.wrapping_mul(37).wrapping_add(unit as u32)
+        })
+}
+
+/// Writes the name hash table the game uses to look up entries: entry hashes are grouped by their
+/// remainder modulo a prime number of groups.
+fn write_hash_table(out: &mut Vec<u8>, entries: &[Bnd4Entry]) {
+    let group_count = (entries.len() as u32 / 7..)
+        .find(|&candidate| is_prime(candidate))
+        .expect("there is always a larger prime");
+
+    let mut groups = vec![Vec::new(); group_count as usize];
+
+    for (index, entry) in entries.iter().enumerate() {
+        let hash = name_hash(&entry.name);
+        groups[(hash % group_count) as usize].push((hash, index as u32));
+    }
+
+    let hashes_offset_field = out.len();
+
+    out.extend_from_slice(&0u64.to_le_bytes());
+    out.extend_from_slice(&group_count.to_le_bytes());
+    out.extend_from_slice(&[0x10, 8, 8, 0]);
+
+    let mut first = 0u32;
+
+    for group in &mut groups {
+        group.sort_unstable();
+
+        out.extend_from_slice(&(group.len() as u32).to_le_bytes());
+        out.extend_from_slice(&first.to_le_bytes());
+
+        first += group.len() as u32;
+    }
+
+    let hashes_offset = out.len() as u64;
+    out[hashes_offset_field..hashes_offset_field + 8].copy_from_slice(&hashes_offset.to_le_bytes());
+
+    for (hash, index) in groups.into_iter().flatten() {
+        out.extend_from_slice(&hash.to_le_bytes());
+        out.extend_from_slice(&index.to_le_bytes());
+    }
+}
+
+fn is_prime(candidate: u32) -> bool {
+    candidate >= 2
+        && (2..)
+            .take_while(|d| d * d <= candidate)
+            .all(|d| !candidate.is_multiple_of(d))
+}
+
+fn pad(out: &mut Vec<u8>, alignment: usize) {
+    out.resize(out.len().next_multiple_of(alignment), 0);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], Bnd4Error> {
+        offset
+            .checked_add(N)
+            .and_then(|end| self.0.get(offset..end))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(Bnd4Error::Truncated)
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, Bnd4Error> {
+        self.bytes::<1>(offset).map(|[byte]| byte)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, Bnd4Error> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, Bnd4Error> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+
+    /// Reads a 64-bit size or offset, which can't be in bounds if it doesn't fit in a `usize`.
+    fn usize(&self, offset: usize) -> Result<usize, Bnd4Error> {
+        usize::try_from(self.u64(offset)?).map_err(|_| Bnd4Error::Truncated)
+    }
+
+    fn c_str(&self, offset: usize) -> Result<String, Bnd4Error> {
+        let bytes = self.0.get(offset..).ok_or(Bnd4Error::Truncated)?;
+        let len = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(Bnd4Error::Truncated)?;
+
+        Ok(bytes[..len].iter().map(|&b| b as char).collect())
+    }
+
+    fn utf16_c_str(&self, offset: usize) -> Result<String, Bnd4Error> {
+        let units = self
+            .0
+            .get(offset..)
+            .ok_or(Bnd4Error::Truncated)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+
+        let mut name = Vec::new();
+
+        for unit in units {
+            if unit == 0 {
+                return Ok(String::from_utf16_lossy(&name));
+            }
+
+            name.push(unit);
+        }
+
+        Err(Bnd4Error::Truncated)
+    }
+}
+
+struct Writer<'a>(&'a mut [u8]);
+
+impl Writer<'_> {
+    fn bytes(&mut self, offset: usize, bytes: &[u8]) {
+        self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn u8(&mut self, offset: usize, value: u8) {
+        self.0[offset] = value;
+    }
+
+    fn u32(&mut self, offset: usize, value: u32) {
+        self.bytes(offset, &value.to_le_bytes());
+    }
+
+    fn u64(&mut self, offset: usize, value: u64) {
+        self.bytes(offset, &value.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{merge, name_hash, Bnd4, Bnd4Entry, Bnd4Error};
+    use crate::dcx::{self, Dcx};
+
+    fn entry(id: i32, name: &str, data: &[u8]) -> Bnd4Entry {
+        Bnd4Entry {
+            flags: 0x40,
+            id,
+            name: name.to_owned(),
+            data: data.to_vec(),
+            uncompressed_size: None,
+        }
+    }
+
+    fn binder(entries: &[(i32, &str, &[u8])]) -> Bnd4 {
+        let mut binder = Bnd4::new();
+
+        binder.entries = entries
+            .iter()
+            .map(|&(id, name, data)| entry(id, name, data))
+            .collect();
+
+        binder
+    }
+
+    #[test]
+    fn binder_roundtrip() {
+        let binder = binder(&[
+            (
+                0,
+                "N:\\GR\\data\\INTERROOT_win64\\msg\\engUS\\ItemName.fmg",
+                b"items",
+            ),
+            (
+                1,
+                "N:\\GR\\data\\INTERROOT_win64\\msg\\engUS\\WeaponName.fmg",
+                b"",
+            ),
+            (
+                2,
+                "N:\\GR\\data\\INTERROOT_win64\\msg\\engUS\\NpcName.fmg",
+                &[7; 33],
+            ),
+        ]);
+
+        let bytes = binder.to_bytes().unwrap();
+
+        assert_eq!(&bytes[..4], b"BND4");
+        // Elden Ring stores this format with its bits reversed.
+        assert_eq!(bytes[0x31], 0x74);
+
+        let parsed = Bnd4::parse(&bytes).unwrap();
+        assert_eq!(parsed, binder);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+
+        assert_eq!(
+            parsed
+                .entry("n:/gr/data/interroot_win64/msg/engus/npcname.fmg")
+                .map(|e| e.id),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn binder_data_is_aligned() {
+        let bytes = binder(&[(0, "a.fmg", b"abc"), (1, "b.fmg", b"defg")])
+            .to_bytes()
+            .unwrap();
+
+        let data_offset = |index: usize| {
+            let header = 0x40 + index * 0x24;
+            u32::from_le_bytes(bytes[header + 0x18..header + 0x1C].try_into().unwrap()) as usize
+        };
+
+        assert_eq!(data_offset(0) % 0x10, 0);
+        assert_eq!(data_offset(1) % 0x10, 0);
+        assert_eq!(&bytes[data_offset(1)..data_offset(1) + 4], b"defg");
+    }
+
+    #[test]
+    fn corrupt_entry_headers_are_truncated() {
+        let bytes = binder(&[(0, "a.fmg", b"abc")]).to_bytes().unwrap();
+
+        let mut huge_header = bytes.clone();
+        huge_header[0x20..0x28].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        let mut huge_count = bytes.clone();
+        huge_count[0x0C..0x10].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut huge_size = bytes;
+        huge_size[0x48..0x50].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        for bytes in [huge_header, huge_count, huge_size] {
+            assert!(matches!(Bnd4::parse(&bytes), Err(Bnd4Error::Truncated)));
+        }
+    }
+
+    #[test]
+    fn name_hashes_match_the_game() {
+        // The game hashes "/" followed by the lower-cased path.
+        assert_eq!(name_hash("A"), ('/' as u32) * 37 + 'a' as u32);
+        assert_eq!(name_hash("a\\b"), name_hash("/A/B"));
+    }
+
+    #[test]
+    fn merged_entries_follow_load_order() {
+        let base = binder(&[(0, "menu\\a.layout", b"a1"), (1, "menu\\b.layout", b"b1")]);
+        let first = binder(&[(1, "MENU\\B.layout", b"b2"), (2, "menu\\c.layout", b"c2")]);
+        let second = binder(&[(2, "menu\\c.layout", b"c3")]);
+
+        let inputs = [
+            dcx::compress_deflate(&base.to_bytes().unwrap()),
+            first.to_bytes().unwrap(),
+            dcx::compress_deflate(&second.to_bytes().unwrap()),
+        ];
+
+        let merged = merge(inputs.iter().map(Vec::as_slice), None).unwrap();
+
+        let merged = Dcx::parse(&merged).unwrap().decompress(None).unwrap();
+        let merged = Bnd4::parse(&merged).unwrap();
+
+        let entries = merged
+            .entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.data.as_slice()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            entries,
+            [
+                ("menu\\a.layout", &b"a1"[..]),
+                ("MENU\\B.layout", b"b2"),
+                ("menu\\c.layout", b"c3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unnamed_binders_cant_be_merged() {
+        let mut unnamed = Bnd4::new();
+        unnamed.format = super::format::IDS;
+        unnamed.entries.push(entry(0, "", b"data"));
+
+        let bytes = unnamed.to_bytes().unwrap();
+        assert_eq!(Bnd4::parse(&bytes).unwrap(), unnamed);
+
+        assert!(merge([bytes.as_slice(), bytes.as_slice()], None).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Wwise soundbanks, which embed the WEM files of their sounds.
+//!
+//! Only the sections listing and storing embedded WEMs (`DIDX` and `DATA`) are read, the rest are
+//! written back unchanged.
+
+use thiserror::Error;
+
+/// Embedded WEM files are aligned to this in the `DATA` section.
+const WEM_ALIGNMENT: usize = 0x10;
+const DIDX_ENTRY_SIZE: usize = 0x0C;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bnk {
+    sections: Vec<Section>,
+
+    /// Embedded WEM files, in the order they're stored.
+    pub wems: Vec<Wem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wem {
+    pub id: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Section {
+    /// Rebuilt from [`Bnk::wems`] when the bank is written.
+    Didx,
+    Data,
+    Other([u8; 4], Vec<u8>),
+}
+
+#[derive(Debug, Error)]
+pub enum BnkError {
+    #[error("soundbank is truncated")]
+    Truncated,
+
+    #[error("soundbank lists embedded WEMs without a DATA section")]
+    MissingData,
+
+    #[error("embedded WEM {0} is outside of the DATA section")]
+    WemOutOfBounds(u32),
+
+    #[error("soundbank doesn't embed WEM {0}, only embedded WEMs can be replaced")]
+    UnknownWem(u32),
+}
+
+impl Bnk {
+    pub fn parse(bytes: &[u8]) -> Result<Self, BnkError> {
+        let mut sections = Vec::new();
+        let mut didx = None;
+        let mut data = None;
+
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let header = bytes.get(offset..offset + 8).ok_or(BnkError::Truncated)?;
+
+            let tag: [u8; 4] = header[..4].try_into().unwrap();
+            let size = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+
+            let body = bytes
+                .get(offset + 8..offset + 8 + size)
+                .ok_or(BnkError::Truncated)?;
+
+            match &tag {
+                b"DIDX" => {
+                    didx = Some(body);
+                    sections.push(Section::Didx);
+                }
+                b"DATA" => {
+                    data = Some(body);
+                    sections.push(Section::Data);
+                }
+                _ => sections.push(Section::Other(tag, body.to_vec())),
+            }
+
+            offset += 8 + size;
+        }
+
+        let wems = match (didx, data) {
+            (None, _) => Vec::new(),
+            (Some(_), None) => return Err(BnkError::MissingData),
+            (Some(didx), Some(data)) => didx
+                .chunks_exact(DIDX_ENTRY_SIZE)
+                .map(|entry| {
+                    let id = u32::from_le_bytes(entry[0..4].try_into().unwrap());
+                    let offset = u32::from_le_bytes(entry[4..8].try_into().unwrap()) as usize;
+                    let size = u32::from_le_bytes(entry[8..12].try_into().unwrap()) as usize;
+
+                    let data = data
+                        .get(offset..offset + size)
+                        .ok_or(BnkError::WemOutOfBounds(id))?;
+
+                    Ok(Wem {
+                        id,
+                        data: data.to_vec(),
+                    })
+                })
+                .collect::<Result<_, BnkError>>()?,
+        };
+
+        Ok(Self { sections, wems })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut didx = Vec::with_capacity(self.wems.len() * DIDX_ENTRY_SIZE);
+        let mut data = Vec::new();
+
+        for wem in &self.wems {
+            data.resize(data.len().next_multiple_of(WEM_ALIGNMENT), 0);
+
+            didx.extend_from_slice(&wem.id.to_le_bytes());
+            didx.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            didx.extend_from_slice(&(wem.data.len() as u32).to_le_bytes());
+
+            data.extend_from_slice(&wem.data);
+        }
+
+        let mut out = Vec::new();
+
+        for section in &self.sections {
+            let (tag, body) = match section {
+                Section::Didx => (b"DIDX", &didx),
+                Section::Data => (b"DATA", &data),
+                Section::Other(tag, body) => (tag, body),
+            };
+
+            out.extend_from_slice(tag);
+            out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            out.extend_from_slice(body);
+        }
+
+        out
+    }
+
+    /// Replaces the data of the embedded WEM with ID `id`, returning `false` if the bank doesn't
+    /// embed it.
+    ///
+    /// The bank's other sections refer to WEMs by ID, so new WEMs can't be added.
+    pub fn replace(&mut self, id: u32, data: Vec<u8>) -> bool {
+        match self.wems.iter_mut().find(|wem| wem.id == id) {
+            Some(wem) => {
+                wem.data = data;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Replaces the embedded WEMs of `bank` with `wems`, in order, so the last WEM with an ID wins.
+pub fn patch<'a, I>(bank: &[u8], wems: I) -> Result<Vec<u8>, BnkError>
+where
+    I: IntoIterator<Item = (u32, &'a [u8])>,
+{
+    let mut bank = Bnk::parse(bank)?;
+
+    for (id, data) in wems {
+        if !bank.replace(id, data.to_vec()) {
+            return Err(BnkError::UnknownWem(id));
+        }
+    }
+
+    Ok(bank.to_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{patch, Bnk, BnkError, Wem};
+
+    fn section(tag: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = tag.to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn bnk() -> Vec<u8> {
+        let mut didx = Vec::new();
+        for (id, offset, size) in [(100u32, 0u32, 3u32), (200, 16, 5)] {
+            didx.extend_from_slice(&id.to_le_bytes());
+            didx.extend_from_slice(&offset.to_le_bytes());
+            didx.extend_from_slice(&size.to_le_bytes());
+        }
+
+        let mut data = b"abc".to_vec();
+        data.resize(16, 0);
+        data.extend_from_slice(b"defgh");
+
+        [
+            section(b"BKHD", &[1, 2, 3, 4]),
+            section(b"DIDX", &didx),
+            section(b"DATA", &data),
+            section(b"HIRC", &[5, 6]),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn banks_round_trip() {
+        let bnk = Bnk::parse(&bnk()).unwrap();
+
+        assert_eq!(
+            bnk.wems,
+            [
+                Wem {
+                    id: 100,
+                    data: b"abc".to_vec()
+                },
+                Wem {
+                    id: 200,
+                    data: b"defgh".to_vec()
+                },
+            ]
+        );
+        assert_eq!(bnk.to_bytes(), self::bnk());
+    }
+
+    #[test]
+    fn embedded_wems_are_replaced() {
+        let mut bnk = Bnk::parse(&bnk()).unwrap();
+
+        assert!(bnk.replace(100, b"0123456789abcdefXYZ".to_vec()));
+        assert!(!bnk.replace(300, Vec::new()));
+
+        let bnk = Bnk::parse(&bnk.to_bytes()).unwrap();
+
+        assert_eq!(bnk.wems[0].data, b"0123456789abcdefXYZ");
+        assert_eq!(bnk.wems[1].data, b"defgh");
+    }
+
+    #[test]
+    fn only_embedded_wems_are_patched() {
+        let patched = patch(&bnk(), [(200, &b"xy"[..]), (200, &b"z"[..])]).unwrap();
+        assert_eq!(Bnk::parse(&patched).unwrap().wems[1].data, b"z");
+
+        assert!(matches!(
+            patch(&bnk(), [(300, &b"xy"[..])]),
+            Err(BnkError::UnknownWem(300))
+        ));
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+/// An entry of a merged file, e.g. a param row or a text entry, changed by more than one package
+/// in different ways.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryConflict {
+    /// File name of the param or FMG the entry is in, e.g. `EquipParamWeapon.param`.
+    pub file: String,
+    pub id: i32,
+
+    /// The packages that changed the entry, in load order. The last one wins.
+    pub packages: Vec<String>,
+}
+
+/// Collects conflicting changes, ordered by file and entry.
+#[derive(Debug, Default)]
+pub(crate) struct Conflicts(BTreeMap<(String, i32), Vec<String>>);
+
+impl Conflicts {
+    /// Records that `package` changed an entry that `earlier` had already changed differently.
+    pub fn record(&mut self, file: &str, id: i32, earlier: &str, package: &str) {
+        let packages = self.0.entry((file.to_owned(), id)).or_default();
+
+        if packages.is_empty() {
+            packages.push(earlier.to_owned());
+        }
+
+        packages.push(package.to_owned());
+    }
+
+    pub fn into_vec(self) -> Vec<EntryConflict> {
+        self.0
+            .into_iter()
+            .map(|((file, id), packages)| EntryConflict { file, id, packages })
+            .collect()
+    }
+}
+
+/// Conflicts in the same file between the same packages, for reporting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictGroup<'a> {
+    pub file: &'a str,
+    pub packages: &'a [String],
+    pub ids: Vec<i32>,
+}
+
+/// Groups `conflicts` by file and packages, since packages tend to conflict over many entries
+/// at once.
+pub fn group(conflicts: &[EntryConflict]) -> Vec<ConflictGroup<'_>> {
+    let mut groups = BTreeMap::<(&str, &[String]), Vec<i32>>::new();
+
+    for conflict in conflicts {
+        groups
+            .entry((&conflict.file, &conflict.packages))
+            .or_default()
+            .push(conflict.id);
+    }
+
+    groups
+        .into_iter()
+        .map(|((file, packages), ids)| ConflictGroup {
+            file,
+            packages,
+            ids,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{group, Conflicts};
+
+    #[test]
+    fn conflicts_are_grouped_by_file_and_packages() {
+        let mut conflicts = Conflicts::default();
+
+        conflicts.record("WeaponName.fmg", 2, "a", "b");
+        conflicts.record("WeaponName.fmg", 1, "a", "b");
+        conflicts.record("WeaponName.fmg", 1, "b", "c");
+        conflicts.record("WeaponName.fmg", 3, "a", "c");
+
+        let conflicts = conflicts.into_vec();
+        let groups = group(&conflicts);
+
+        let groups = groups
+            .iter()
+            .map(|group| (group.file, group.packages.join(" "), group.ids.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            groups,
+            [
+                ("WeaponName.fmg", "a b".to_owned(), vec![2]),
+                ("WeaponName.fmg", "a b c".to_owned(), vec![1]),
+                ("WeaponName.fmg", "a c".to_owned(), vec![3]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! DCX, the compressed container most game files are stored in.
+//!
+//! Only the layout used by Dark Souls 3 and later games is supported: a `DCX` header followed by
+//! `DCS` (sizes), `DCP` (compression parameters) and `DCA` chunks, then the compressed data.
+
+use miniz_oxide::{deflate::compress_to_vec_zlib, inflate::decompress_to_vec_zlib_with_limit};
+use thiserror::Error;
+
+const DCX_MAGIC: &[u8; 4] = b"DCX\0";
+
+/// Layout of the header written by [`compress_deflate`], matching the games' own DFLT files.
+const DFLT_VERSION: u32 = 0x10000;
+const DFLT_LEVEL: u8 = 9;
+const DCS_OFFSET: u32 = 0x18;
+const DCP_OFFSET: u32 = 0x24;
+const DCA_OFFSET: u32 = 0x44;
+const DATA_OFFSET: u32 = 0x4C;
+
+/// Compression algorithm of a DCX file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DcxFormat {
+    /// zlib, used by older games and some tools.
+    Deflate,
+
+    /// Oodle Kraken, used by Sekiro and Elden Ring.
+    Kraken,
+
+    /// Zstandard, used by more recent games.
+    Zstd,
+}
+
+/// Decompresses Oodle Kraken data, which can only be done with the Oodle library shipped with the
+/// game.
+pub trait KrakenDecompressor {
+    /// Decompresses `compressed` into `out`, which is exactly the size of the uncompressed data.
+    fn decompress(&self, compressed: &[u8], out: &mut [u8]) -> Result<(), DcxError>;
+}
+
+/// A parsed DCX file, borrowing its compressed data.
+#[derive(Debug)]
+pub struct Dcx<'a> {
+    pub format: DcxFormat,
+    pub uncompressed_size: u32,
+    pub compressed: &'a [u8],
+}
+
+#[derive(Debug, Error)]
+pub enum DcxError {
+    #[error("not a DCX file")]
+    NotDcx,
+
+    #[error("DCX file is truncated")]
+    Truncated,
+
+    #[error("unexpected {0:?} chunk in DCX header")]
+    InvalidChunk(String),
+
+    #[error("unsupported DCX compression {0:?}")]
+    UnsupportedFormat(String),
+
+    #[error("Kraken compressed DCX files can't be decompressed without the game's Oodle library")]
+    NoKrakenDecompressor,
+
+    #[error("failed to decompress DCX data: {0}")]
+    Decompress(String),
+}
+
+impl<'a> Dcx<'a> {
+    /// Checks if `bytes` start with a DCX header, without validating the rest of it.
+    pub fn is_dcx(bytes: &[u8]) -> bool {
+        bytes.starts_with(DCX_MAGIC)
+    }
+
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, DcxError> {
+        if !Self::is_dcx(bytes) {
+            return Err(DcxError::NotDcx);
+        }
+
+        let dcs_offset = read_u32_be(bytes, 0x08)? as usize;
+        let dcp_offset = read_u32_be(bytes, 0x0C)? as usize;
+        let dca_offset = read_u32_be(bytes, 0x10)? as usize;
+
+        expect_chunk(bytes, dcs_offset, b"DCS\0")?;
+        let uncompressed_size = read_u32_be(bytes, dcs_offset + 4)?;
+        let compressed_size = read_u32_be(bytes, dcs_offset + 8)? as usize;
+
+        expect_chunk(bytes, dcp_offset, b"DCP\0")?;
+        let format = match read_chunk(bytes, dcp_offset + 4)? {
+            b"DFLT" => DcxFormat::Deflate,
+            b"KRAK" => DcxFormat::Kraken,
+            b"ZSTD" => DcxFormat::Zstd,
+            other => {
+                return Err(DcxError::UnsupportedFormat(
+                    String::from_utf8_lossy(other).into_owned(),
+                ))
+            }
+        };
+
+        expect_chunk(bytes, dca_offset, b"DCA\0")?;
+        let data_offset = dca_offset + read_u32_be(bytes, dca_offset + 4)? as usize;
+
+        let compressed = bytes
+            .get(data_offset..data_offset + compressed_size)
+            .ok_or(DcxError::Truncated)?;
+
+        Ok(Self {
+            format,
+            uncompressed_size,
+            compressed,
+        })
+    }
+
+    /// Decompresses the data, using `kraken` for Kraken compressed files.
+    pub fn decompress(&self, kraken: Option<&dyn KrakenDecompressor>) -> Result<Vec<u8>, DcxError> {
+        let size = self.uncompressed_size as usize;
+
+        let data = match self.format {
+            DcxFormat::Deflate => decompress_to_vec_zlib_with_limit(self.compressed, size)
+                .map_err(|e| DcxError::Decompress(e.to_string()))?,
+            DcxFormat::Kraken => {
+                let kraken = kraken.ok_or(DcxError::NoKrakenDecompressor)?;
+
+                let mut data = vec![0; size];
+                kraken.decompress(self.compressed, &mut data)?;
+                data
+            }
+            DcxFormat::Zstd => return Err(DcxError::UnsupportedFormat("ZSTD".to_owned())),
+        };
+
+        if data.len() != size {
+            return Err(DcxError::Decompress(format!(
+                "expected {size} bytes, got {}",
+                data.len()
+            )));
+        }
+
+        Ok(data)
+    }
+}
+
+/// Decompresses `bytes` if they're a DCX file, or returns them as they are.
+pub fn decompress_if_dcx(
+    bytes: Vec<u8>,
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<Vec<u8>, DcxError> {
+    if Dcx::is_dcx(&bytes) {
+        Dcx::parse(&bytes)?.decompress(kraken)
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Compresses `data` into a DFLT DCX file, which every supported game can read.
+pub fn compress_deflate(data: &[u8]) -> Vec<u8> {
+    let compressed = compress_to_vec_zlib(data, DFLT_LEVEL);
+
+    let mut out = Vec::with_capacity(DATA_OFFSET as usize + compressed.len());
+
+    out.extend_from_slice(DCX_MAGIC);
+    for field in [
+        DFLT_VERSION,
+        DCS_OFFSET,
+        DCP_OFFSET,
+        DCA_OFFSET,
+        DATA_OFFSET,
+    ] {
+        out.extend_from_slice(&field.to_be_bytes());
+    }
+
+    out.extend_from_slice(b"DCS\0");
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+
+    out.extend_from_slice(b"DCP\0");
+    out.extend_from_slice(b"DFLT");
+    out.extend_from_slice(&0x20u32.to_be_bytes());
+    out.extend_from_slice(&[DFLT_LEVEL, 0, 0, 0]);
+    out.extend_from_slice(&[0; 12]);
+    out.extend_from_slice(&0x00010100u32.to_be_bytes());
+
+    out.extend_from_slice(b"DCA\0");
+    out.extend_from_slice(&(DATA_OFFSET - DCA_OFFSET).to_be_bytes());
+
+    out.extend_from_slice(&compressed);
+
+    out
+}
+
+fn read_chunk(bytes: &[u8], offset: usize) -> Result<&[u8; 4], DcxError> {
+    bytes
+        .get(offset..offset + 4)
+        .and_then(|chunk| chunk.try_into().ok())
+        .ok_or(DcxError::Truncated)
+}
+
+fn expect_chunk(bytes: &[u8], offset: usize, magic: &[u8; 4]) -> Result<(), DcxError> {
+    let chunk = read_chunk(bytes, offset)?;
+
+    if chunk != magic {
+        return Err(DcxError::InvalidChunk(
+            String::from_utf8_lossy(chunk).into_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> Result<u32, DcxError> {
+    read_chunk(bytes, offset).map(|chunk| u32::from_be_bytes(*chunk))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        compress_deflate, decompress_if_dcx, Dcx, DcxError, DcxFormat, KrakenDecompressor,
+    };
+
+    /// Stands in for Oodle by treating "compressed" data as stored.
+    struct StoredKraken;
+
+    impl KrakenDecompressor for StoredKraken {
+        fn decompress(&self, compressed: &[u8], out: &mut [u8]) -> Result<(), DcxError> {
+            out.copy_from_slice(compressed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn deflate_roundtrip() {
+        let data = b"BND4".repeat(1000);
+        let dcx = compress_deflate(&data);
+
+        let parsed = Dcx::parse(&dcx).unwrap();
+
+        assert_eq!(parsed.format, DcxFormat::Deflate);
+        assert_eq!(parsed.uncompressed_size as usize, data.len());
+        assert!(parsed.compressed.len() < data.len());
+        assert_eq!(parsed.decompress(None).unwrap(), data);
+    }
+
+    #[test]
+    fn kraken_needs_decompressor() {
+        let data = b"uncompressed".to_vec();
+
+        let mut dcx = compress_deflate(&data);
+        dcx.truncate(0x4C);
+        dcx[0x28..0x2C].copy_from_slice(b"KRAK");
+        dcx[0x20..0x24].copy_from_slice(&(data.len() as u32).to_be_bytes());
+        dcx.extend_from_slice(&data);
+
+        let parsed = Dcx::parse(&dcx).unwrap();
+
+        assert_eq!(parsed.format, DcxFormat::Kraken);
+        assert!(matches!(
+            parsed.decompress(None),
+            Err(DcxError::NoKrakenDecompressor)
+        ));
+        assert_eq!(parsed.decompress(Some(&StoredKraken)).unwrap(), data);
+    }
+
+    #[test]
+    fn plain_files_are_passed_through() {
+        let data = b"BND4 not compressed".to_vec();
+
+        assert_eq!(decompress_if_dcx(data.clone(), None).unwrap(), data);
+        assert!(matches!(Dcx::parse(&data), Err(DcxError::NotDcx)));
+        assert!(matches!(
+            Dcx::parse(&compress_deflate(&data)[..0x30]),
+            Err(DcxError::Truncated)
+        ));
+    }
+}
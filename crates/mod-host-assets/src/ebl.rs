@@ -1,7 +1,7 @@
 use std::{mem, ptr::NonNull};
 
 use from_singleton::FromSingleton;
-use me3_binary_analysis::{pe, rtti::ClassMap};
+use me3_binary_analysis::{patterns, pe, rtti::ClassMap};
 use me3_mod_host_types::{alloc::DlStdAllocator, game::GAME};
 use me3_mod_protocol::Game;
 use pelite::pe::Pe;
@@ -97,44 +97,11 @@ where
         .ok()
         .and_then(|s| program.get_section_bytes(s).ok())?;
 
-    // Matches:
-    // mov    reg,QWORD PTR [rbp+??]
-    // mov    QWORD PTR [rsp+0x28],reg
-    // mov    QWORD PTR [rsp+0x20],reg
-    // mov    r9,QWORD PTR [rip+??]
-    // mov    rdx,reg
-    // mov    rcx,reg
-    // call   MountEbl
-    // movzx  ebx,al
-    // cmp    QWORD PTR [rbp/rsp+??],0x8
-    let mount_re = Regex::new(
-        r"(?s-u)\x48\x8b\x45.\x48\x89\x44\x24\x28[\x48|\x4c]\x89[\x44\x4c\x54\x5c\x64\x6c\x74\x7c]\x24\x20\x4c\x8b\x0d.{4}[\x48|\x49]\x8b[\xd0-\xd7][\x48|\x49]\x8b[\xc8-\xcf]\xe8(.{4})\x0f\xb6\xd8(?:(?:\x48\x83\x7d.\x08)|(?:\x48\x83\x7c\x24.\x08))\x72."
-    )
-    .unwrap();
-
-    // Not nearly as reliable (matches an entire function as-is), but works on all current game
-    // builds.
+    let mount_re = Regex::new(patterns::MOUNT_EBL.regex).unwrap();
+
+    // Not nearly as reliable, but works on all current game builds.
     let alt_mount_re = || {
-        // Matches:
-        // push   rbx
-        // sub    rsp,0x30
-        // mov    rax,QWORD PTR [rsp+0x70]
-        // mov    r10,r9
-        // mov    r9,QWORD PTR [rsp+0x60]
-        // mov    r11,r8
-        // mov    QWORD PTR [rsp+0x28],rax
-        // mov    rcx,rdx
-        // mov    rax,QWORD PTR [rsp+0x68]
-        // mov    r8,r10
-        // mov    rdx,r11
-        // mov    QWORD PTR [rsp+0x20],rax
-        // call   MountEbl
-        // add    rsp,0x30
-        // pop    rbx
-        // ret
-        let alt_mount_re = Regex::new(
-            r"(?s-u)\x53\x48\x83\xec\x30\x48\x8b\x44\x24\x70\x4d\x8b\xd1\x4c\x8b\x4c\x24\x60\x4d\x8b\xd8\x48\x89\x44\x24\x28\x48\x8b\xca\x48\x8b\x44\x24\x68\x4d\x8b\xc2\x49\x8b\xd3\x48\x89\x44\x24\x20\xe8(.{4})\x48\x83\xc4\x30\x5b\xc3"
-        ).unwrap();
+        let alt_mount_re = Regex::new(patterns::MOUNT_EBL_ALT.regex).unwrap();
 
         alt_mount_re.captures(text)
     };
@@ -0,0 +1,200 @@
+//! FMG, the text tables stored in `msgbnd` binders.
+//!
+//! Only the layout used by Dark Souls 3 and later games is supported, with 64-bit string offsets
+//! and UTF-16 text.
+
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+const VERSION: u8 = 2;
+const HEADER_SIZE: usize = 0x28;
+const GROUP_SIZE: usize = 0x10;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fmg {
+    /// Text entries by ID. Entries without text are kept, since the game treats them differently
+    /// from empty text.
+    pub entries: BTreeMap<i32, Option<String>>,
+}
+
+#[derive(Debug, Error)]
+pub enum FmgError {
+    #[error("FMG file is truncated")]
+    Truncated,
+
+    #[error("big endian FMG files aren't supported")]
+    BigEndian,
+
+    #[error("FMG version {0} isn't supported")]
+    UnsupportedVersion(u8),
+}
+
+impl Fmg {
+    pub fn parse(bytes: &[u8]) -> Result<Self, FmgError> {
+        let reader = Reader(bytes);
+
+        if reader.u8(0x01)? != 0 {
+            return Err(FmgError::BigEndian);
+        }
+
+        let version = reader.u8(0x02)?;
+        if version != VERSION {
+            return Err(FmgError::UnsupportedVersion(version));
+        }
+
+        let group_count = reader.u32(0x0C)? as usize;
+        let string_offsets = reader.u64(0x18)? as usize;
+
+        let mut entries = BTreeMap::new();
+
+        for group in 0..group_count {
+            let group = HEADER_SIZE + group * GROUP_SIZE;
+
+            let offset_index = reader.u32(group)? as usize;
+            let first_id = reader.u32(group + 4)? as i32;
+            let last_id = reader.u32(group + 8)? as i32;
+
+            for (index, id) in (first_id..=last_id).enumerate() {
+                let string_offset = reader.u64(string_offsets + (offset_index + index) * 8)?;
+
+                let text = match string_offset {
+                    0 => None,
+                    offset => Some(reader.utf16_c_str(offset as usize)?),
+                };
+
+                entries.insert(id, text);
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Consecutive IDs are stored in groups, each with the offset of its first string.
+        let mut groups = Vec::<(usize, i32, i32)>::new();
+
+        for (index, &id) in self.entries.keys().enumerate() {
+            match groups.last_mut() {
+                Some((_, _, last_id)) if last_id.checked_add(1) == Some(id) => *last_id = id,
+                _ => groups.push((index, id, id)),
+            }
+        }
+
+        let string_offsets = HEADER_SIZE + groups.len() * GROUP_SIZE;
+        let strings = string_offsets + self.entries.len() * 8;
+
+        let mut out = vec![0; strings];
+
+        out[0x02] = VERSION;
+        out[0x08] = 1;
+        write(&mut out, 0x0C, &(groups.len() as u32).to_le_bytes());
+        write(&mut out, 0x10, &(self.entries.len() as u32).to_le_bytes());
+        write(&mut out, 0x14, &0xFFu32.to_le_bytes());
+        write(&mut out, 0x18, &(string_offsets as u64).to_le_bytes());
+
+        for (group_index, (offset_index, first_id, last_id)) in groups.iter().enumerate() {
+            let group = HEADER_SIZE + group_index * GROUP_SIZE;
+
+            write(&mut out, group, &(*offset_index as u32).to_le_bytes());
+            write(&mut out, group + 4, &first_id.to_le_bytes());
+            write(&mut out, group + 8, &last_id.to_le_bytes());
+        }
+
+        for (index, text) in self.entries.values().enumerate() {
+            let Some(text) = text else {
+                continue;
+            };
+
+            let offset = out.len() as u64;
+            write(&mut out, string_offsets + index * 8, &offset.to_le_bytes());
+
+            for unit in text.encode_utf16().chain([0]) {
+                out.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+
+        let size = out.len() as u32;
+        write(&mut out, 0x04, &size.to_le_bytes());
+
+        out
+    }
+}
+
+fn write(out: &mut [u8], offset: usize, bytes: &[u8]) {
+    out[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], FmgError> {
+        self.0
+            .get(offset..offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(FmgError::Truncated)
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, FmgError> {
+        self.bytes::<1>(offset).map(|[byte]| byte)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, FmgError> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, FmgError> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+
+    fn utf16_c_str(&self, offset: usize) -> Result<String, FmgError> {
+        let units = self
+            .0
+            .get(offset..)
+            .ok_or(FmgError::Truncated)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+
+        let mut text = Vec::new();
+
+        for unit in units {
+            if unit == 0 {
+                return Ok(String::from_utf16_lossy(&text));
+            }
+
+            text.push(unit);
+        }
+
+        Err(FmgError::Truncated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fmg;
+
+    #[test]
+    fn fmg_roundtrip() {
+        let fmg = Fmg {
+            entries: [
+                (100, Some("Dagger".to_owned())),
+                (101, None),
+                (102, Some(String::new())),
+                (2000, Some("Ringed Knight's\nStraight Sword".to_owned())),
+                (i32::MAX, Some("Last".to_owned())),
+            ]
+            .into(),
+        };
+
+        let bytes = fmg.to_bytes();
+
+        // 100-102, 2000 and i32::MAX make three groups.
+        assert_eq!(u32::from_le_bytes(bytes[0x0C..0x10].try_into().unwrap()), 3);
+        assert_eq!(
+            u32::from_le_bytes(bytes[0x04..0x08].try_into().unwrap()) as usize,
+            bytes.len()
+        );
+
+        assert_eq!(Fmg::parse(&bytes).unwrap(), fmg);
+    }
+}
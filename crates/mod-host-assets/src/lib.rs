@@ -1,6 +1,21 @@
 pub mod bhd5;
+pub mod bnd4;
+pub mod bnk;
+pub mod conflict;
+pub mod dcx;
+#[cfg(windows)]
 pub mod dl_device;
+#[cfg(windows)]
 pub mod ebl;
+pub mod fmg;
 pub mod mapping;
+pub mod msgbnd;
+#[cfg(windows)]
+pub mod oodle;
+pub mod package_files;
+pub mod param;
 mod platform;
+pub mod regulation;
+pub mod scan_index;
+#[cfg(windows)]
 pub mod wwise;
@@ -1,36 +1,68 @@
 use std::{
     borrow::Borrow,
-    collections::HashMap,
+    collections::{hash_map::Entry, BTreeMap, HashMap},
     env,
     ffi::OsStr,
     fmt,
     fs::read_dir,
     io, iter,
-    os::windows::{ffi::OsStrExt as WinOsStrExt, fs::FileTypeExt},
     path::{Path, PathBuf, StripPrefixError},
+    sync::{Arc, RwLock},
 };
 
 use me3_mod_protocol::package::{AssetOverrideSource, Package};
-use normpath::PathExt;
 use rayon::iter::{ParallelBridge, ParallelIterator};
 use smallvec::{smallvec_inline, SmallVec};
 use thiserror::Error;
+use tracing::debug;
+#[cfg(windows)]
 use windows::core::{PCSTR, PCWSTR};
 
-use crate::platform::normalize_dos_path;
+pub use self::merge::MergeError;
+use self::merge::MergeInput;
+use crate::{
+    bhd5::CachedArchives,
+    conflict::EntryConflict,
+    dcx::KrakenDecompressor,
+    package_files::{is_relative_subpath, PackageFilter},
+    platform::{self, normalize_dos_path},
+    scan_index::{self, ScanIndex},
+};
 
+mod merge;
 mod savefile;
 
 pub struct VfsOverrideMapping {
     map: HashMap<VfsKey, VfsOverride>,
     current_dir: VfsKey,
     savefile_override: Option<savefile::SavefileOverrideMapping>,
+    runtime_overrides: RwLock<HashMap<VfsKey, &'static VfsOverride>>,
+    shadowed: HashMap<VfsKey, Vec<VfsOverride>>,
+    packages: Vec<Arc<str>>,
+    scan_index_dir: Option<PathBuf>,
 }
 
 pub struct VfsOverride {
     display: Box<str>,
     path_c_str: Box<Path>,
     wide_c_str: Box<[u16]>,
+    package: Option<Arc<str>>,
+}
+
+/// The outcome of merging a file overridden by several packages.
+#[derive(Debug)]
+pub struct MergeReport {
+    /// The path the merged file is served under, with `/` separators.
+    pub path: String,
+
+    /// The packages the file was merged from, in load order.
+    pub packages: Vec<String>,
+
+    /// The merged file, or why the file couldn't be merged.
+    pub merged: Result<PathBuf, MergeError>,
+
+    /// Entries changed by more than one package, taken from the last of them.
+    pub conflicts: Vec<EntryConflict>,
 }
 
 #[derive(Debug, Error)]
@@ -46,6 +78,15 @@ pub enum VfsOverrideMappingError {
 
     #[error("Could not acquire directory entry")]
     StripPrefix(#[from] StripPrefixError),
+
+    #[error("Package root {0:?} is not a subdirectory of the package")]
+    InvalidRoot(String),
+
+    #[error("Package mount point {0:?} is not a relative virtual directory")]
+    InvalidMount(String),
+
+    #[error("Invalid package include or exclude glob {0}")]
+    InvalidGlob(#[from] globset::Error),
 }
 
 impl VfsOverrideMapping {
@@ -58,9 +99,20 @@ impl VfsOverrideMapping {
             map: HashMap::new(),
             current_dir,
             savefile_override: None,
+            runtime_overrides: RwLock::default(),
+            shadowed: HashMap::new(),
+            packages: Vec::new(),
+            scan_index_dir: None,
         })
     }
 
+    /// Lists package files from indexes in `<cache_dir>/scan-index` when scanning, rebuilding
+    /// the indexes of packages that changed, instead of walking every package.
+    pub fn use_scan_index(&mut self, cache_dir: &Path) -> Result<(), VfsOverrideMappingError> {
+        self.scan_index_dir = Some(normalize_dos_path(cache_dir)?.join(scan_index::INDEX_DIR));
+        Ok(())
+    }
+
     /// Scans a set of directories, mapping discovered assets into itself.
     pub fn scan_directories<I>(&mut self, sources: I) -> Result<(), VfsOverrideMappingError>
     where
@@ -69,6 +121,9 @@ impl VfsOverrideMapping {
         fn scan_directories_inner(
             base_dir: &Path,
             root_key: &VfsKey,
+            mount: Option<&VfsKey>,
+            filter: &PackageFilter,
+            package: &Arc<str>,
         ) -> SmallVec<[Result<(VfsKey, VfsOverride), io::Error>; 1]> {
             let entries = match read_dir(base_dir) {
                 Ok(entries) => entries,
@@ -79,16 +134,30 @@ impl VfsOverrideMapping {
                 .flatten()
                 .par_bridge()
                 .flat_map_iter(|dir_entry| match dir_entry.file_type() {
-                    Ok(file_type) if file_type.is_dir() || file_type.is_symlink_dir() => {
-                        scan_directories_inner(&dir_entry.path(), root_key)
+                    Ok(file_type)
+                        if file_type.is_dir()
+                            || platform::is_symlink_dir(&dir_entry, file_type) =>
+                    {
+                        scan_directories_inner(&dir_entry.path(), root_key, mount, filter, package)
                     }
                     Ok(_) => {
                         let path = dir_entry.path();
 
                         let result = VfsKey::for_asset_path(&path, root_key)
-                            .map(|vfs_key| (vfs_key, VfsOverride::new(&path)));
+                            .map(|vfs_key| vfs_key.mounted_at(mount));
+
+                        if result
+                            .as_ref()
+                            .is_ok_and(|vfs_key| !filter.is_match(vfs_key))
+                        {
+                            return SmallVec::new();
+                        }
 
-                        smallvec_inline![result]
+                        smallvec_inline![result.map(|vfs_key| {
+                            let vfs_override =
+                                VfsOverride::new(&path).with_package(package.clone());
+                            (vfs_key, vfs_override)
+                        })]
                     }
                     Err(e) => smallvec_inline![Err(e)],
                 })
@@ -98,18 +167,82 @@ impl VfsOverrideMapping {
         }
 
         for source in sources {
-            let source_path = source.asset_path();
-            let normalized_path = normalize_dos_path(source_path)?;
+            let mut source_path = source.asset_path().to_path_buf();
+
+            if let Some(root) = source.root() {
+                if !is_relative_subpath(Path::new(root)) {
+                    return Err(VfsOverrideMappingError::InvalidRoot(root.to_owned()));
+                }
+
+                source_path.push(root);
+            }
+
+            let mount = source
+                .mount()
+                .map(|mount| {
+                    VfsKey::for_mount(mount)
+                        .ok_or_else(|| VfsOverrideMappingError::InvalidMount(mount.to_owned()))
+                })
+                .transpose()?;
+
+            let normalized_path = normalize_dos_path(&source_path)?;
             let root_key = VfsKey::for_disk_path(&normalized_path)
                 .map_err(VfsOverrideMappingError::ReadDir)?;
 
-            let scanned_directories = scan_directories_inner(&normalized_path, &root_key);
+            let filter = PackageFilter::new(&source)?;
+            let package: Arc<str> = source.name().into();
+
+            let indexed = self.scan_index_dir.as_ref().and_then(|index_dir| {
+                let index_path = index_dir.join(scan_index::index_file_name(&source));
+
+                ScanIndex::load_or_build(&index_path, &normalized_path)
+                    .inspect_err(|e| debug!("error" = %e, %package, "not using scan index"))
+                    .ok()
+            });
+
+            let scanned_directories = match indexed {
+                Some(index) => index
+                    .files()
+                    .filter_map(|relative| {
+                        let vfs_key = VfsKey::for_vfs_path(relative).mounted_at(mount.as_ref());
+
+                        filter.is_match(&vfs_key).then(|| {
+                            let path = scan_index::join(&normalized_path, relative);
+                            let vfs_override = VfsOverride::new(path).with_package(package.clone());
+                            Ok((vfs_key, vfs_override))
+                        })
+                    })
+                    .collect(),
+                None => scan_directories_inner(
+                    &normalized_path,
+                    &root_key,
+                    mount.as_ref(),
+                    &filter,
+                    &package,
+                ),
+            };
             self.map.reserve(scanned_directories.len());
 
             for result in scanned_directories {
                 let (vfs_key, vfs_override) = result.map_err(VfsOverrideMappingError::ReadDir)?;
-                self.map.insert(vfs_key, vfs_override);
+
+                // Files overridden by earlier packages are kept around to be merged or reported.
+                match self.map.entry(vfs_key) {
+                    Entry::Occupied(mut entry) => {
+                        let shadowed = entry.insert(vfs_override);
+
+                        self.shadowed
+                            .entry(entry.key().clone())
+                            .or_default()
+                            .push(shadowed);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(vfs_override);
+                    }
+                }
             }
+
+            self.packages.push(package);
         }
 
         Ok(())
@@ -123,6 +256,132 @@ impl VfsOverrideMapping {
         self.scan_directories(iter::once(&package))
     }
 
+    /// Are there files overridden by more than one package that can be merged, or text and WEM
+    /// patches?
+    pub fn has_mergeable_overrides(&self) -> bool {
+        self.shadowed.keys().any(merge::is_mergeable)
+            || self
+                .map
+                .keys()
+                .any(|key| merge::patch_target(key).is_some())
+    }
+
+    /// Files overridden by more than one package, with the paths they're served under and the
+    /// overriding files in load order. The last of them is served, unless the file is merged.
+    pub fn overlapping_files(&self) -> impl Iterator<Item = (String, Vec<&VfsOverride>)> {
+        self.shadowed.iter().filter_map(|(key, shadowed)| {
+            let last = self.map.get(key)?;
+            let overrides = shadowed.iter().chain(iter::once(last)).collect();
+
+            Some((key.to_slash_string(), overrides))
+        })
+    }
+
+    /// Merges binders and regulations overridden by more than one package, applies text and WEM
+    /// patches, and serves the merged files instead.
+    ///
+    /// Binder entries, regulation rows, text entries and WEMs from later packages take precedence.
+    /// Regulations are merged against the game's own regulation in `game_dir`, so only rows
+    /// packages changed are taken from them. The same goes for text, if the game's files are
+    /// unpacked. WEMs are patched into the game's soundbanks, read from `game_dir` or the
+    /// game's `archives`, unless a package overrides the whole bank.
+    ///
+    /// Merged files are cached in `<cache_dir>/merged`. Files that can't be merged are served
+    /// from the last package overriding them, like any other file.
+    pub fn merge_overrides(
+        &mut self,
+        cache_dir: &Path,
+        game_dir: Option<&Path>,
+        archives: Option<&CachedArchives>,
+        kraken: Option<&dyn KrakenDecompressor>,
+    ) -> Result<Vec<MergeReport>, VfsOverrideMappingError> {
+        let merged_dir = normalize_dos_path(cache_dir)?.join("merged");
+
+        let order = |vfs_override: &VfsOverride| {
+            self.packages
+                .iter()
+                .position(|package| Some(&**package) == vfs_override.package())
+                .unwrap_or(usize::MAX)
+        };
+
+        let inputs = |key: &VfsKey| {
+            self.shadowed
+                .get(key)
+                .into_iter()
+                .flatten()
+                .chain(self.map.get(key))
+                .map(|vfs_override| MergeInput::new(vfs_override, order(vfs_override)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut merges = BTreeMap::new();
+
+        for key in self.shadowed.keys() {
+            if merge::is_mergeable(key) && merge::patch_target(key).is_none() {
+                merges.insert(key.clone(), inputs(key));
+            }
+        }
+
+        // Text and WEM patches are merged into the file they patch, ordered by package.
+        for key in self.map.keys() {
+            if let Some(target) = merge::patch_target(key) {
+                merges
+                    .entry(target)
+                    .or_insert_with_key(inputs)
+                    .extend(inputs(key));
+            }
+        }
+
+        let mut reports = Vec::with_capacity(merges.len());
+
+        for (key, mut inputs) in merges {
+            // Whole files come before the patches of the same package, which are ordered by path
+            // so merged files are cached under the same name every time.
+            inputs.sort_by(|a, b| {
+                (a.order, a.is_patch(), &a.path).cmp(&(b.order, b.is_patch(), &b.path))
+            });
+
+            let mut packages = inputs
+                .iter()
+                .map(|input| input.package.clone())
+                .collect::<Vec<_>>();
+
+            packages.dedup();
+
+            let result = if merge::is_regulation(&key) {
+                merge::merge_regulation(&merged_dir, &key, game_dir, &inputs, kraken)
+            } else if merge::is_msgbnd(&key) {
+                merge::merge_text(&merged_dir, &key, game_dir, &inputs, kraken)
+            } else if merge::is_bank(&key) {
+                merge::merge_bank(&merged_dir, &key, game_dir, archives, &inputs)
+            } else {
+                merge::merge_binder(&merged_dir, &key, &inputs, kraken)
+            };
+
+            let (merged, conflicts) = match result {
+                Ok(merged) => {
+                    let vfs_override =
+                        VfsOverride::new(&merged.path).with_package(packages.join(" + ").into());
+
+                    self.map.insert(key.clone(), vfs_override);
+                    self.shadowed.remove(&key);
+
+                    (Ok(merged.path), merged.conflicts)
+                }
+                Err(e) => (Err(e), Vec::new()),
+            };
+
+            reports.push(MergeReport {
+                path: key.to_slash_string(),
+                packages,
+                merged,
+                conflicts,
+            });
+        }
+
+        Ok(reports)
+    }
+
     pub fn add_savefile_override<P, F>(&mut self, savefile_dir: P, f: F) -> Result<(), io::Error>
     where
         P: AsRef<Path>,
@@ -133,6 +392,26 @@ impl VfsOverrideMapping {
         Ok(())
     }
 
+    /// Maps `vfs_path` to `disk_path` after the mapping has been shared with the asset hooks.
+    ///
+    /// Overrides added this way take priority over scanned directories. They are never freed, so
+    /// lookups don't need to hold a lock.
+    pub fn add_runtime_override<P: AsRef<Path>>(
+        &self,
+        vfs_path: &str,
+        disk_path: P,
+    ) -> Result<(), VfsOverrideMappingError> {
+        let disk_path = normalize_dos_path(disk_path.as_ref())?;
+        let vfs_override = Box::leak(Box::new(VfsOverride::new(disk_path)));
+
+        self.runtime_overrides
+            .write()
+            .unwrap()
+            .insert(VfsKey::for_vfs_path(vfs_path), vfs_override);
+
+        Ok(())
+    }
+
     pub fn vfs_override<S: AsRef<OsStr>>(&self, path_str: S) -> Option<&VfsOverride> {
         let path = Path::new(&path_str);
 
@@ -144,12 +423,36 @@ impl VfsOverrideMapping {
         }
 
         let key = VfsKey::for_vfs_path(path);
-        self.map.get(&key)
+        self.runtime_override(&key).or_else(|| self.map.get(&key))
     }
 
     pub fn disk_override<S: AsRef<OsStr>>(&self, path_str: S) -> Option<&VfsOverride> {
         let key = VfsKey::for_asset_path(Path::new(&path_str), &self.current_dir).ok()?;
-        self.map.get(&key)
+        self.runtime_override(&key).or_else(|| self.map.get(&key))
+    }
+
+    /// The path [`VfsOverrideMapping::vfs_override`] looks up, with `/` separators.
+    pub fn vfs_lookup_path<S: AsRef<OsStr>>(&self, path_str: S) -> String {
+        VfsKey::for_vfs_path(Path::new(&path_str)).to_slash_string()
+    }
+
+    /// The path [`VfsOverrideMapping::disk_override`] looks up, with `/` separators, if `path_str`
+    /// is in the game directory.
+    pub fn disk_lookup_path<S: AsRef<OsStr>>(&self, path_str: S) -> Option<String> {
+        VfsKey::for_asset_path(Path::new(&path_str), &self.current_dir)
+            .ok()
+            .map(|key| key.to_slash_string())
+    }
+
+    /// Files scanned from packages, with the paths they're served under.
+    pub fn package_files(&self) -> impl Iterator<Item = (String, &VfsOverride)> {
+        self.map
+            .iter()
+            .map(|(key, vfs_override)| (key.to_slash_string(), vfs_override))
+    }
+
+    fn runtime_override(&self, key: &VfsKey) -> Option<&'static VfsOverride> {
+        self.runtime_overrides.read().unwrap().get(key).copied()
     }
 }
 
@@ -162,7 +465,7 @@ impl VfsOverride {
             os_str.push("\0");
 
             (
-                Vec::into_boxed_slice(os_str.encode_wide().collect()),
+                Vec::into_boxed_slice(platform::encode_wide(&os_str)),
                 PathBuf::into_boxed_path(os_str.into()),
             )
         };
@@ -171,9 +474,20 @@ impl VfsOverride {
             display,
             path_c_str,
             wide_c_str,
+            package: None,
         }
     }
 
+    fn with_package(mut self, package: Arc<str>) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Name of the package this file was scanned from, if any.
+    pub fn package(&self) -> Option<&str> {
+        self.package.as_deref()
+    }
+
     pub fn as_str_lossy(&self) -> &str {
         &self.display
     }
@@ -199,10 +513,12 @@ impl VfsOverride {
         self.wide_c_str.as_ptr()
     }
 
+    #[cfg(windows)]
     pub fn as_pcstr(&self) -> PCSTR {
         PCSTR::from_raw(self.as_c_str())
     }
 
+    #[cfg(windows)]
     pub fn as_pcwstr(&self) -> PCWSTR {
         PCWSTR::from_raw(self.as_wide_c_str())
     }
@@ -213,6 +529,7 @@ impl fmt::Debug for VfsOverride {
         f.debug_struct("VfsOverride")
             .field("display", &self.display)
             .field("path", &self.as_path())
+            .field("package", &self.package)
             .finish()
     }
 }
@@ -235,12 +552,14 @@ impl AsRef<[u16]> for VfsOverride {
     }
 }
 
+#[cfg(windows)]
 impl From<&VfsOverride> for PCSTR {
     fn from(value: &VfsOverride) -> Self {
         value.as_pcstr()
     }
 }
 
+#[cfg(windows)]
 impl From<&VfsOverride> for PCWSTR {
     fn from(value: &VfsOverride) -> Self {
         value.as_pcwstr()
@@ -253,9 +572,7 @@ struct VfsKey(Box<Path>);
 impl VfsKey {
     /// Turns a disk path into an asset lookup key that includes the root directory.
     fn for_disk_path<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
-        let normalized = path
-            .as_ref()
-            .normalize_virtually()?
+        let normalized = platform::normalize_virtually(path.as_ref())?
             .components()
             .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
             .collect();
@@ -280,6 +597,34 @@ impl VfsKey {
         Self::for_disk_path(path)?.strip_prefix(base)
     }
 
+    /// Turns a package mount point into a lookup key prefix, if it's a relative virtual path.
+    fn for_mount(mount: &str) -> Option<Self> {
+        let path = Path::new(mount);
+
+        if !is_relative_subpath(path) || mount.contains(':') {
+            return None;
+        }
+
+        Some(Self::for_vfs_path(path))
+    }
+
+    /// Prefixes an asset lookup key with a package mount point, if there is one.
+    fn mounted_at(self, mount: Option<&Self>) -> Self {
+        match mount {
+            Some(mount) => Self(mount.0.join(&self.0).into_boxed_path()),
+            None => self,
+        }
+    }
+
+    /// Joins the components of the key with `/`, regardless of platform.
+    fn to_slash_string(&self) -> String {
+        self.0
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     /// Strips the root directory from a disk asset lookup key.
     fn strip_prefix(&self, base: &Self) -> Result<Self, io::Error> {
         let stripped = self
@@ -305,9 +650,17 @@ impl Borrow<Path> for VfsKey {
 
 #[cfg(test)]
 mod test {
-    use std::path::Path;
+    use std::{iter, path::Path};
+
+    use me3_mod_protocol::package::Package;
 
     use super::{VfsKey, VfsOverrideMapping};
+    use crate::{
+        bnd4::{Bnd4, Bnd4Entry},
+        bnk::Bnk,
+        dcx::{self, Dcx},
+        fmg::Fmg,
+    };
 
     #[test]
     fn asset_path_lookup_keys() {
@@ -375,4 +728,306 @@ mod test {
             "event/common.emevd.dcx was found incorrectly under the regulation root"
         );
     }
+
+    #[test]
+    fn scan_directory_with_package_filters() {
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
+
+        let mut package = Package::new(test_mod_dir);
+        package.include = vec!["sd/**".to_owned(), "Regulation.bin".to_owned()];
+        package.exclude = vec!["sd/enus/**".to_owned()];
+
+        asset_mapping
+            .scan_directories(iter::once(&package))
+            .unwrap();
+
+        for included in ["regulation.bin", "sd/init.bnk", "sd/wem/48/485927883.wem"] {
+            assert!(
+                asset_mapping
+                    .vfs_override(format!("data0:/{included}"))
+                    .is_some(),
+                "override for {included} was not found"
+            );
+        }
+
+        for excluded in ["event/common.emevd.dcx", "sd/enus/wem/10/1000519763.wem"] {
+            assert!(
+                asset_mapping
+                    .vfs_override(format!("data0:/{excluded}"))
+                    .is_none(),
+                "override for {excluded} was found despite the package filters"
+            );
+        }
+    }
+
+    #[test]
+    fn scan_directory_with_root_and_mount() {
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
+
+        let mut package = Package::new(test_mod_dir.clone());
+        package.root = Some("event".to_owned());
+        package.mount = Some("Script/".to_owned());
+
+        asset_mapping
+            .scan_directories(iter::once(&package))
+            .unwrap();
+
+        assert!(
+            asset_mapping
+                .vfs_override("data0:/script/common.emevd.dcx")
+                .is_some(),
+            "override for event/common.emevd.dcx was not mounted under script"
+        );
+        assert!(
+            asset_mapping
+                .vfs_override("data0:/event/common.emevd.dcx")
+                .is_none(),
+            "event/common.emevd.dcx was found outside of its mount point"
+        );
+
+        for (root, mount) in [(Some("../test-mod"), None), (None, Some("data0:/event"))] {
+            let mut package = Package::new(test_mod_dir.clone());
+            package.root = root.map(str::to_owned);
+            package.mount = mount.map(str::to_owned);
+
+            assert!(asset_mapping
+                .scan_directories(iter::once(&package))
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn scan_index_lists_the_same_files() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_dir = test_dir.path();
+        let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
+
+        let mut package = Package::new(test_mod_dir);
+        package.mount = Some("data0".to_owned());
+        package.exclude = vec!["data0/sd/**".to_owned()];
+
+        let files = |mapping: &VfsOverrideMapping| {
+            let mut files = mapping
+                .package_files()
+                .map(|(path, vfs_override)| (path, vfs_override.as_path().to_owned()))
+                .collect::<Vec<_>>();
+
+            files.sort();
+            files
+        };
+
+        let mut scanned = VfsOverrideMapping::new().unwrap();
+        scanned.scan_directories(iter::once(&package)).unwrap();
+
+        // Once to build the index and once to load it.
+        for _ in 0..2 {
+            let mut indexed = VfsOverrideMapping::new().unwrap();
+            indexed.use_scan_index(test_dir).unwrap();
+            indexed.scan_directories(iter::once(&package)).unwrap();
+
+            assert_eq!(files(&indexed), files(&scanned));
+        }
+    }
+
+    #[test]
+    fn package_files_have_package_names() {
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
+
+        let package_name = test_mod_dir.to_string_lossy().into_owned();
+        let package = Package::new(test_mod_dir);
+
+        asset_mapping
+            .scan_directories(iter::once(&package))
+            .unwrap();
+
+        let files = asset_mapping.package_files().collect::<Vec<_>>();
+
+        assert!(files
+            .iter()
+            .any(|(path, _)| path == "event/common.emevd.dcx"));
+        assert!(files
+            .iter()
+            .all(|(_, vfs_override)| vfs_override.package() == Some(&*package_name)));
+
+        assert_eq!(
+            asset_mapping.vfs_lookup_path("data0:/Event/common.emevd.dcx"),
+            "event/common.emevd.dcx"
+        );
+    }
+
+    #[test]
+    fn overlapping_binders_are_merged() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_dir = test_dir.path();
+        let binder_path = "chr/c0000.anibnd.dcx";
+
+        let packages = [("first", "a.hkx"), ("second", "b.hkx")].map(|(name, entry)| {
+            let mut binder = Bnd4::new();
+            binder.entries.push(Bnd4Entry {
+                flags: 0x40,
+                id: 0,
+                name: entry.to_owned(),
+                data: name.as_bytes().to_vec(),
+                uncompressed_size: None,
+            });
+
+            let package_dir = test_dir.join(name);
+            let path = package_dir.join(binder_path);
+
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, dcx::compress_deflate(&binder.to_bytes().unwrap())).unwrap();
+
+            Package::new(package_dir)
+        });
+
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        asset_mapping.scan_directories(packages.iter()).unwrap();
+        asset_mapping
+            .merge_overrides(&test_dir.join("cache"), Some(test_dir), None, None)
+            .unwrap();
+
+        let merged = asset_mapping
+            .vfs_override(format!("data0:/{binder_path}"))
+            .expect("merged binder was not served");
+
+        assert!(merged.as_path().starts_with(test_dir.join("cache/merged")));
+
+        let merged = std::fs::read(merged.as_path()).unwrap();
+        let merged = Dcx::parse(&merged).unwrap().decompress(None).unwrap();
+        let merged = Bnd4::parse(&merged).unwrap();
+
+        assert_eq!(merged.entry("a.hkx").unwrap().data, b"first");
+        assert_eq!(merged.entry("b.hkx").unwrap().data, b"second");
+    }
+
+    #[test]
+    fn text_patches_are_applied() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_dir = test_dir.path();
+
+        let fmg = Fmg {
+            entries: [
+                (100, Some("Dagger".to_owned())),
+                (101, Some("Club".to_owned())),
+            ]
+            .into(),
+        };
+
+        let mut binder = Bnd4::new();
+        binder.entries.push(Bnd4Entry {
+            flags: 0x40,
+            id: 0,
+            name: "N:\\GR\\data\\INTERROOT_win64\\msg\\engUS\\WeaponName.fmg".to_owned(),
+            data: fmg.to_bytes(),
+            uncompressed_size: None,
+        });
+
+        let files = [
+            (
+                "first",
+                "msg/engus/item.msgbnd.dcx",
+                binder.to_bytes().unwrap(),
+            ),
+            (
+                "second",
+                "msg/engus/item.msgbnd.json",
+                br#"{ "WeaponName": { "101": "Great Club" } }"#.to_vec(),
+            ),
+        ];
+
+        let packages = files.map(|(name, path, contents)| {
+            let package_dir = test_dir.join(name);
+            let path = package_dir.join(path);
+
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+
+            Package::new(package_dir)
+        });
+
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        asset_mapping.scan_directories(packages.iter()).unwrap();
+        assert!(asset_mapping.has_mergeable_overrides());
+
+        let reports = asset_mapping
+            .merge_overrides(&test_dir.join("cache"), None, None, None)
+            .unwrap();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].path, "msg/engus/item.msgbnd.dcx");
+
+        let merged = asset_mapping
+            .vfs_override("data0:/msg/engus/item.msgbnd.dcx")
+            .unwrap();
+
+        let merged = Bnd4::parse(&std::fs::read(merged.as_path()).unwrap()).unwrap();
+        let merged = Fmg::parse(&merged.entries[0].data).unwrap();
+
+        assert_eq!(merged.entries[&100].as_deref(), Some("Dagger"));
+        assert_eq!(merged.entries[&101].as_deref(), Some("Great Club"));
+    }
+
+    #[test]
+    fn wem_patches_are_applied_to_game_banks() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_dir = test_dir.path();
+        let game_dir = test_dir.join("game");
+
+        // A bank embedding WEMs 100 and 200, with a DIDX entry per WEM and aligned DATA.
+        let mut game_bank = b"DIDX\x18\0\0\0".to_vec();
+        for (id, offset) in [(100u32, 0u32), (200, 16)] {
+            game_bank.extend_from_slice(&id.to_le_bytes());
+            game_bank.extend_from_slice(&offset.to_le_bytes());
+            game_bank.extend_from_slice(&1u32.to_le_bytes());
+        }
+        game_bank.extend_from_slice(b"DATA\x11\0\0\0a");
+        game_bank.extend_from_slice(&[0; 15]);
+        game_bank.push(b'b');
+
+        let bank_path = "sd/enus/cs_main.bnk";
+        let files = [
+            ("game", bank_path.to_owned(), game_bank),
+            ("first", format!("{bank_path}/100.wem"), b"first".to_vec()),
+            ("first", format!("{bank_path}/200.wem"), b"first".to_vec()),
+            ("second", format!("{bank_path}/200.wem"), b"second".to_vec()),
+        ];
+
+        for (dir, path, contents) in files {
+            let path = test_dir.join(dir).join(path);
+
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let packages = ["first", "second"].map(|name| Package::new(test_dir.join(name)));
+
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        asset_mapping.scan_directories(packages.iter()).unwrap();
+        assert!(asset_mapping.has_mergeable_overrides());
+
+        let reports = asset_mapping
+            .merge_overrides(&test_dir.join("cache"), Some(&game_dir), None, None)
+            .unwrap();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].path, bank_path);
+
+        let merged = asset_mapping
+            .vfs_override(bank_path)
+            .expect("patched bank was not served");
+
+        let merged = Bnk::parse(&std::fs::read(merged.as_path()).unwrap()).unwrap();
+
+        assert_eq!(merged.wems[0].data, b"first");
+        assert_eq!(merged.wems[1].data, b"second");
+    }
 }
@@ -0,0 +1,405 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{
+    bhd5::CachedArchives,
+    bnd4::{self, Bnd4Error},
+    bnk::{self, BnkError},
+    conflict::EntryConflict,
+    dcx::KrakenDecompressor,
+    mapping::{VfsKey, VfsOverride},
+    msgbnd::{self, TextMergeError, TextPatch, TextSource},
+    regulation::{self, RegulationError},
+};
+
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("failed to read or write a merged file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("the game directory is needed to merge regulations")]
+    NoGameDir,
+
+    #[error(transparent)]
+    Bnd4(#[from] Bnd4Error),
+
+    #[error(transparent)]
+    Regulation(#[from] RegulationError),
+
+    #[error(transparent)]
+    Text(#[from] TextMergeError),
+
+    #[error(transparent)]
+    Bnk(#[from] BnkError),
+
+    #[error("the game's soundbank wasn't found, launch the game once with boot boost enabled")]
+    NoGameBank,
+}
+
+/// A package file to merge.
+pub struct MergeInput {
+    pub path: PathBuf,
+    pub package: String,
+
+    /// Position of the package in the load order.
+    pub order: usize,
+}
+
+/// A merged file, with the entries more than one package changed.
+pub struct Merged {
+    pub path: PathBuf,
+    pub conflicts: Vec<EntryConflict>,
+}
+
+impl MergeInput {
+    pub fn new(vfs_override: &VfsOverride, order: usize) -> Self {
+        Self {
+            path: vfs_override.as_path().to_owned(),
+            package: vfs_override
+                .package()
+                .unwrap_or(vfs_override.as_str_lossy())
+                .to_owned(),
+            order,
+        }
+    }
+
+    /// Is this a text or WEM patch, rather than a whole file?
+    pub fn is_patch(&self) -> bool {
+        self.path.extension().is_some_and(|extension| {
+            ["json", "xml", "wem"]
+                .iter()
+                .any(|patch| extension.eq_ignore_ascii_case(patch))
+        })
+    }
+}
+
+/// Is `key` the path of a file that's merged when several packages override it?
+pub fn is_mergeable(key: &VfsKey) -> bool {
+    is_binder(key) || is_regulation(key) || patch_target(key).is_some()
+}
+
+/// Is `key` the path of a binder, e.g. `chr/c0000.anibnd.dcx`?
+fn is_binder(key: &VfsKey) -> bool {
+    let file_name = file_name(key);
+    let file_name = file_name.strip_suffix(".dcx").unwrap_or(&file_name);
+
+    file_name.ends_with("bnd")
+}
+
+pub fn is_regulation(key: &VfsKey) -> bool {
+    key.0.as_os_str() == "regulation.bin"
+}
+
+/// Is `key` the path of a binder of text, e.g. `msg/engus/item_dlc02.msgbnd.dcx`?
+pub fn is_msgbnd(key: &VfsKey) -> bool {
+    let file_name = file_name(key);
+    let file_name = file_name.strip_suffix(".dcx").unwrap_or(&file_name);
+
+    file_name.ends_with(".msgbnd")
+}
+
+/// Is `key` the path of a soundbank, e.g. `sd/enus/cs_main.bnk`?
+pub fn is_bank(key: &VfsKey) -> bool {
+    file_name(key).ends_with(".bnk")
+}
+
+/// The file patched by the text or WEM patch at `key`, if it is one.
+pub fn patch_target(key: &VfsKey) -> Option<VfsKey> {
+    text_patch_target(key).or_else(|| wem_patch_target(key))
+}
+
+/// The binder patched by the text patch at `key`, if it is one.
+///
+/// Patches are either `<binder>.msgbnd.json` or `<binder>.msgbnd/<FMG>.fmg.xml`, next to where
+/// `<binder>.msgbnd.dcx` would be.
+pub fn text_patch_target(key: &VfsKey) -> Option<VfsKey> {
+    let file_name = file_name(key);
+
+    if let Some(binder) = file_name.strip_suffix(".msgbnd.json") {
+        let target = key.0.with_file_name(format!("{binder}.msgbnd.dcx"));
+        return Some(VfsKey(target.into_boxed_path()));
+    }
+
+    let binder_dir = key.0.parent()?;
+    let binder = binder_dir.file_name()?.to_string_lossy();
+
+    if !binder.ends_with(".msgbnd") || !file_name.ends_with(".fmg.xml") {
+        return None;
+    }
+
+    let target = binder_dir.with_file_name(format!("{binder}.dcx"));
+    Some(VfsKey(target.into_boxed_path()))
+}
+
+/// The soundbank patched by the WEM at `key`, if it is one, e.g. `sd/enus/cs_main.bnk` for
+/// `sd/enus/cs_main.bnk/12345.wem`.
+fn wem_patch_target(key: &VfsKey) -> Option<VfsKey> {
+    let bank = key.0.parent()?;
+
+    if wem_id(&key.0).is_none() || !bank.file_name()?.to_string_lossy().ends_with(".bnk") {
+        return None;
+    }
+
+    Some(VfsKey(bank.into()))
+}
+
+/// The ID of the WEM at `path`, if it's named after one, e.g. `12345.wem`.
+fn wem_id(path: &Path) -> Option<u32> {
+    let file_name = path.file_name()?.to_string_lossy();
+    file_name.strip_suffix(".wem")?.parse().ok()
+}
+
+/// Merges `binders`, in load order, into `<merged_dir>/<hash>.<file name>`.
+pub fn merge_binder(
+    merged_dir: &Path,
+    key: &VfsKey,
+    binders: &[MergeInput],
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<Merged, MergeError> {
+    let inputs = read_all(binders)?;
+
+    merge_cached(merged_dir, key, &inputs, || {
+        let merged = bnd4::merge(inputs.iter().map(Vec::as_slice), kraken)?;
+        Ok((merged, Vec::new()))
+    })
+}
+
+/// Merges the rows of `regulations`, in load order, with the game's regulation in `game_dir`,
+/// into `<merged_dir>/<hash>.regulation.bin`.
+pub fn merge_regulation(
+    merged_dir: &Path,
+    key: &VfsKey,
+    game_dir: Option<&Path>,
+    regulations: &[MergeInput],
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<Merged, MergeError> {
+    let game_dir = game_dir.ok_or(MergeError::NoGameDir)?;
+
+    let mut inputs = read_all(regulations)?;
+    inputs.push(fs::read(game_dir.join("regulation.bin"))?);
+
+    merge_cached(merged_dir, key, &inputs, || {
+        let (vanilla, inputs) = inputs.split_last().unwrap();
+
+        let packages = regulations
+            .iter()
+            .zip(inputs)
+            .map(|(regulation, bytes)| (regulation.package.as_str(), bytes.as_slice()));
+
+        let merged = regulation::merge(vanilla, packages, kraken)?;
+        Ok((merged.bytes, merged.conflicts))
+    })
+}
+
+/// Merges the text of `sources`, binders and text patches in load order, into
+/// `<merged_dir>/<hash>.<file name>`.
+///
+/// Text is only compared against the game's own if its files are unpacked into `game_dir`.
+pub fn merge_text(
+    merged_dir: &Path,
+    key: &VfsKey,
+    game_dir: Option<&Path>,
+    sources: &[MergeInput],
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<Merged, MergeError> {
+    let mut inputs = read_all(sources)?;
+
+    let vanilla = game_dir
+        .map(|game_dir| game_dir.join(&key.0))
+        .filter(|path| path.is_file())
+        .map(fs::read)
+        .transpose()?;
+
+    if let Some(vanilla) = &vanilla {
+        inputs.push(vanilla.clone());
+    }
+
+    merge_cached(merged_dir, key, &inputs, || {
+        let sources = sources
+            .iter()
+            .zip(&inputs)
+            .map(|(source, bytes)| {
+                let extension = source.path.extension().unwrap_or_default();
+
+                let text_source = if extension.eq_ignore_ascii_case("json") {
+                    TextSource::Patch(TextPatch::from_json(&String::from_utf8_lossy(bytes))?)
+                } else if extension.eq_ignore_ascii_case("xml") {
+                    TextSource::Patch(TextPatch::from_fmg_xml(&String::from_utf8_lossy(bytes))?)
+                } else {
+                    TextSource::Binder(bytes)
+                };
+
+                Ok((source.package.as_str(), text_source))
+            })
+            .collect::<Result<Vec<_>, TextMergeError>>()?;
+
+        let merged = msgbnd::merge(vanilla.as_deref(), sources, kraken)?;
+        Ok((merged.bytes, merged.conflicts))
+    })
+}
+
+/// Patches the WEMs of `sources`, in load order, into the last soundbank among them, or the
+/// game's own, into `<merged_dir>/<hash>.<file name>`.
+///
+/// The game's soundbank is read from `game_dir` if the game is unpacked, or else from its
+/// `archives`.
+pub fn merge_bank(
+    merged_dir: &Path,
+    key: &VfsKey,
+    game_dir: Option<&Path>,
+    archives: Option<&CachedArchives>,
+    sources: &[MergeInput],
+) -> Result<Merged, MergeError> {
+    let mut inputs = read_all(sources)?;
+
+    if sources.iter().all(MergeInput::is_patch) {
+        inputs.push(read_game_bank(key, game_dir, archives)?);
+    }
+
+    merge_cached(merged_dir, key, &inputs, || {
+        let bank = sources
+            .iter()
+            .zip(&inputs)
+            .rfind(|(source, _)| !source.is_patch())
+            .map_or(inputs.last().unwrap(), |(_, bytes)| bytes);
+
+        let wems = sources
+            .iter()
+            .zip(&inputs)
+            .filter_map(|(source, bytes)| Some((wem_id(&source.path)?, bytes.as_slice())));
+
+        let patched = bnk::patch(bank, wems)?;
+        Ok((patched, Vec::new()))
+    })
+}
+
+fn read_game_bank(
+    key: &VfsKey,
+    game_dir: Option<&Path>,
+    archives: Option<&CachedArchives>,
+) -> Result<Vec<u8>, MergeError> {
+    if let Some(path) = game_dir
+        .map(|game_dir| game_dir.join(&key.0))
+        .filter(|path| path.is_file())
+    {
+        return Ok(fs::read(path)?);
+    }
+
+    let Some(archives) = archives else {
+        return Err(MergeError::NoGameBank);
+    };
+
+    // Sound archives are mounted at `sd`, but some games store their full path.
+    let path = key.to_slash_string();
+    let paths = [path.strip_prefix("sd/"), Some(&*path)];
+
+    for path in paths.into_iter().flatten() {
+        if let Some(bank) = archives.read(path)? {
+            return Ok(bank);
+        }
+    }
+
+    Err(MergeError::NoGameBank)
+}
+
+fn read_all(inputs: &[MergeInput]) -> Result<Vec<Vec<u8>>, io::Error> {
+    inputs.iter().map(|input| fs::read(&input.path)).collect()
+}
+
+/// Merges files into `<merged_dir>/<hash>.<file name>`, named after a hash of the contents of
+/// `inputs`, so the merged file is reused until one of them changes.
+///
+/// Conflicts are cached next to the merged file, so they're reported every time it's used.
+fn merge_cached<F>(
+    merged_dir: &Path,
+    key: &VfsKey,
+    inputs: &[Vec<u8>],
+    merge: F,
+) -> Result<Merged, MergeError>
+where
+    F: FnOnce() -> Result<(Vec<u8>, Vec<EntryConflict>), MergeError>,
+{
+    let mut hasher = Xxh3::new();
+
+    for input in inputs {
+        hasher.update(&(input.len() as u64).to_le_bytes());
+        hasher.update(input);
+    }
+
+    let path = merged_dir.join(format!("{:032x}.{}", hasher.digest128(), file_name(key)));
+
+    let mut conflicts_path = path.clone().into_os_string();
+    conflicts_path.push(".conflicts");
+    let conflicts_path = PathBuf::from(conflicts_path);
+
+    if let Ok(conflicts) = fs::read_to_string(&conflicts_path)
+        && path.exists()
+    {
+        return Ok(Merged {
+            path,
+            conflicts: read_conflicts(&conflicts),
+        });
+    }
+
+    let (merged, conflicts) = merge()?;
+
+    write_merged(&path, &merged)?;
+    write_merged(&conflicts_path, write_conflicts(&conflicts).as_bytes())?;
+
+    Ok(Merged { path, conflicts })
+}
+
+/// Writes to a temporary file first, so an interrupted write isn't reused.
+fn write_merged(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut partial_path = path.as_os_str().to_owned();
+    partial_path.push(".partial");
+
+    fs::write(&partial_path, contents)?;
+    fs::rename(partial_path, path)
+}
+
+/// Writes one conflict per line, as tab separated file, entry ID and packages.
+fn write_conflicts(conflicts: &[EntryConflict]) -> String {
+    conflicts
+        .iter()
+        .map(|conflict| {
+            format!(
+                "{}\t{}\t{}\n",
+                conflict.file,
+                conflict.id,
+                conflict.packages.join("\t")
+            )
+        })
+        .collect()
+}
+
+fn read_conflicts(conflicts: &str) -> Vec<EntryConflict> {
+    conflicts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+
+            Some(EntryConflict {
+                file: fields.next()?.to_owned(),
+                id: fields.next()?.parse().ok()?,
+                packages: fields.map(str::to_owned).collect(),
+            })
+        })
+        .collect()
+}
+
+fn file_name(key: &VfsKey) -> String {
+    key.0
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
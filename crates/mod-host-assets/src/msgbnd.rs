@@ -0,0 +1,442 @@
+//! Merging the text of `msgbnd` binders, entry by entry.
+//!
+//! Besides whole binders, packages can ship text patches with only the entries they change:
+//!
+//! - `<binder>.msgbnd.json`, an object of FMG names to objects of IDs to text, e.g.
+//!   `{ "WeaponName": { "1000000": "Dagger" } }`.
+//! - `<binder>.msgbnd/<FMG name>.fmg.xml`, an FMG exported as XML by WitchyBND.
+
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+use crate::{
+    bnd4::{Bnd4, Bnd4Error},
+    conflict::{Conflicts, EntryConflict},
+    dcx::{self, Dcx, DcxError, KrakenDecompressor},
+    fmg::{Fmg, FmgError},
+};
+
+/// Text entries changed by a patch, by FMG file name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextPatch {
+    pub fmgs: BTreeMap<String, BTreeMap<i32, Option<String>>>,
+}
+
+/// Where a package's text for a binder comes from.
+#[derive(Debug)]
+pub enum TextSource<'a> {
+    /// A whole binder, possibly DCX compressed.
+    Binder(&'a [u8]),
+    Patch(TextPatch),
+}
+
+/// A binder with text merged from several packages.
+#[derive(Debug)]
+pub struct MergedText {
+    pub bytes: Vec<u8>,
+
+    /// Conflicting text entries, ordered by FMG and ID.
+    pub conflicts: Vec<EntryConflict>,
+}
+
+#[derive(Debug, Error)]
+pub enum TextMergeError {
+    #[error("no package provides the binder to patch, and the game's copy isn't unpacked")]
+    NoBinder,
+
+    #[error("text patch changes {0}, which isn't in the binder")]
+    UnknownFmg(String),
+
+    #[error("FMG {name}: {source}")]
+    Fmg { name: String, source: FmgError },
+
+    #[error("invalid JSON text patch: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid FMG XML text patch: {0}")]
+    Xml(String),
+
+    #[error(transparent)]
+    Bnd4(#[from] Bnd4Error),
+
+    #[error(transparent)]
+    Dcx(#[from] DcxError),
+}
+
+impl TextPatch {
+    pub fn from_json(json: &str) -> Result<Self, TextMergeError> {
+        let fmgs: BTreeMap<String, BTreeMap<i32, Option<String>>> = serde_json::from_str(json)?;
+
+        Ok(Self {
+            fmgs: fmgs
+                .into_iter()
+                .map(|(name, entries)| (fmg_file_name(&name), entries))
+                .collect(),
+        })
+    }
+
+    /// Parses an FMG exported as XML by WitchyBND, which stores entries without text as
+    /// `%null%`.
+    pub fn from_fmg_xml(xml: &str) -> Result<Self, TextMergeError> {
+        let document =
+            roxmltree::Document::parse(xml).map_err(|e| TextMergeError::Xml(e.to_string()))?;
+
+        let root = document.root_element();
+
+        let name = xml_child(root, "filename")?.text().unwrap_or_default();
+
+        let entries = xml_child(root, "entries")?
+            .children()
+            .filter(|node| node.has_tag_name("text"))
+            .map(|node| {
+                let id = node
+                    .attribute("id")
+                    .and_then(|id| id.parse().ok())
+                    .ok_or_else(|| TextMergeError::Xml("<text> without a valid id".to_owned()))?;
+
+                let text = match node.text().unwrap_or_default() {
+                    "%null%" => None,
+                    text => Some(text.to_owned()),
+                };
+
+                Ok((id, text))
+            })
+            .collect::<Result<_, TextMergeError>>()?;
+
+        Ok(Self {
+            fmgs: [(fmg_file_name(name), entries)].into(),
+        })
+    }
+}
+
+fn xml_child<'a, 'input>(
+    parent: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Result<roxmltree::Node<'a, 'input>, TextMergeError> {
+    parent
+        .children()
+        .find(|node| node.has_tag_name(name))
+        .ok_or_else(|| TextMergeError::Xml(format!("missing <{name}>")))
+}
+
+/// Merges the text of several packages, in load order, into one binder.
+///
+/// Entries are compared to `vanilla`, the game's own binder, if it's available, so only the
+/// entries packages changed are taken from them. Otherwise the first binder is used as the base
+/// and every entry of every binder counts as a change. Entries changed by more than one package
+/// are taken from the last of them and reported as conflicts.
+///
+/// The merged binder is DCX compressed if the base binder was.
+pub fn merge<'a, I>(
+    vanilla: Option<&[u8]>,
+    sources: I,
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<MergedText, TextMergeError>
+where
+    I: IntoIterator<Item = (&'a str, TextSource<'a>)>,
+{
+    let parse_binder = |bytes: &[u8]| -> Result<(Bnd4, bool), TextMergeError> {
+        if Dcx::is_dcx(bytes) {
+            Ok((Bnd4::parse(&Dcx::parse(bytes)?.decompress(kraken)?)?, true))
+        } else {
+            Ok((Bnd4::parse(bytes)?, false))
+        }
+    };
+
+    let vanilla = vanilla.map(parse_binder).transpose()?;
+    let vanilla_text = match &vanilla {
+        Some((binder, _)) => fmgs(binder)?
+            .into_iter()
+            .map(|(name, fmg)| (name.to_lowercase(), fmg))
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let mut merged = vanilla;
+    let mut changed_by = HashMap::<(String, i32), (String, Option<String>)>::new();
+    let mut conflicts = Conflicts::default();
+
+    for (package, source) in sources {
+        let patch = match source {
+            TextSource::Binder(bytes) => {
+                let (binder, compressed) = parse_binder(bytes)?;
+                let patch = TextPatch {
+                    fmgs: fmgs(&binder)?
+                        .into_iter()
+                        .map(|(name, fmg)| (name, fmg.entries))
+                        .collect(),
+                };
+
+                match &mut merged {
+                    Some((merged, _)) => {
+                        // Files the base binder doesn't have are added.
+                        for entry in binder.entries {
+                            if merged.entry(&entry.name).is_none() {
+                                merged.merge_entry(entry);
+                            }
+                        }
+                    }
+                    None => merged = Some((binder, compressed)),
+                }
+
+                patch
+            }
+            TextSource::Patch(patch) => patch,
+        };
+
+        for (name, entries) in patch.fmgs {
+            let key = name.to_lowercase();
+            let vanilla_entries = vanilla_text.get(&key).map(|fmg| &fmg.entries);
+
+            for (id, text) in entries {
+                if vanilla_entries.and_then(|entries| entries.get(&id)) == Some(&text) {
+                    continue;
+                }
+
+                if let Some((earlier, earlier_text)) = changed_by.get(&(key.clone(), id))
+                    && *earlier_text != text
+                    && earlier != package
+                {
+                    conflicts.record(&name, id, earlier, package);
+                }
+
+                changed_by.insert((key.clone(), id), (package.to_owned(), text));
+            }
+        }
+    }
+
+    let (mut merged, compressed) = merged.ok_or(TextMergeError::NoBinder)?;
+
+    let mut changes = HashMap::<String, Vec<(i32, Option<String>)>>::new();
+    for ((key, id), (_, text)) in changed_by {
+        changes.entry(key).or_default().push((id, text));
+    }
+
+    for entry in &mut merged.entries {
+        let Some(changes) = changes.remove(&fmg_file_name(&entry.name).to_lowercase()) else {
+            continue;
+        };
+
+        let mut fmg = parse_fmg(&entry.name, &entry.data)?;
+        fmg.entries.extend(changes);
+
+        entry.data = fmg.to_bytes();
+    }
+
+    if let Some(name) = changes.into_keys().next() {
+        return Err(TextMergeError::UnknownFmg(name));
+    }
+
+    let bytes = merged.to_bytes()?;
+
+    Ok(MergedText {
+        bytes: if compressed {
+            dcx::compress_deflate(&bytes)
+        } else {
+            bytes
+        },
+        conflicts: conflicts.into_vec(),
+    })
+}
+
+/// The FMGs in `binder`, with their file names.
+fn fmgs(binder: &Bnd4) -> Result<Vec<(String, Fmg)>, TextMergeError> {
+    binder
+        .entries
+        .iter()
+        .filter(|entry| entry.name.to_lowercase().ends_with(".fmg"))
+        .map(|entry| {
+            let fmg = parse_fmg(&entry.name, &entry.data)?;
+            Ok((fmg_file_name(&entry.name), fmg))
+        })
+        .collect()
+}
+
+fn parse_fmg(name: &str, bytes: &[u8]) -> Result<Fmg, TextMergeError> {
+    Fmg::parse(bytes).map_err(|source| TextMergeError::Fmg {
+        name: fmg_file_name(name),
+        source,
+    })
+}
+
+/// The file name of an FMG, with the `.fmg` extension, e.g. `WeaponName.fmg` for
+/// `N:\GR\data\INTERROOT_win64\msg\engUS\WeaponName.fmg` or `WeaponName`.
+fn fmg_file_name(name: &str) -> String {
+    let file_name = name.rsplit(['\\', '/']).next().unwrap_or(name);
+
+    if file_name.to_lowercase().ends_with(".fmg") {
+        file_name.to_owned()
+    } else {
+        format!("{file_name}.fmg")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{merge, TextPatch, TextSource};
+    use crate::{
+        bnd4::{Bnd4, Bnd4Entry},
+        conflict::EntryConflict,
+        dcx::{self, Dcx},
+        fmg::Fmg,
+    };
+
+    const WEAPON_NAME: &str = "N:\\GR\\data\\INTERROOT_win64\\msg\\engUS\\WeaponName.fmg";
+    const GOODS_NAME: &str = "N:\\GR\\data\\INTERROOT_win64\\msg\\engUS\\GoodsName.fmg";
+
+    fn msgbnd(fmgs: &[(&str, &[(i32, &str)])]) -> Vec<u8> {
+        let mut binder = Bnd4::new();
+
+        for (id, (name, entries)) in fmgs.iter().enumerate() {
+            let fmg = Fmg {
+                entries: entries
+                    .iter()
+                    .map(|&(id, text)| (id, Some(text.to_owned())))
+                    .collect(),
+            };
+
+            binder.entries.push(Bnd4Entry {
+                flags: 0x40,
+                id: id as i32,
+                name: name.to_string(),
+                data: fmg.to_bytes(),
+                uncompressed_size: None,
+            });
+        }
+
+        dcx::compress_deflate(&binder.to_bytes().unwrap())
+    }
+
+    fn text(msgbnd: &[u8], name: &str) -> BTreeMap<i32, Option<String>> {
+        let binder = Dcx::parse(msgbnd).unwrap().decompress(None).unwrap();
+        let binder = Bnd4::parse(&binder).unwrap();
+
+        Fmg::parse(&binder.entry(name).unwrap().data)
+            .unwrap()
+            .entries
+    }
+
+    #[test]
+    fn text_is_merged_against_vanilla() {
+        let vanilla = msgbnd(&[
+            (WEAPON_NAME, &[(100, "Dagger"), (200, "Sword")]),
+            (GOODS_NAME, &[(1, "Flask")]),
+        ]);
+
+        let fixes = msgbnd(&[
+            (WEAPON_NAME, &[(100, "Dagger"), (200, "Longsword")]),
+            (GOODS_NAME, &[(1, "Flask")]),
+        ]);
+
+        let translation = msgbnd(&[
+            (WEAPON_NAME, &[(100, "Dague"), (200, "Épée")]),
+            (GOODS_NAME, &[(1, "Fiole")]),
+        ]);
+
+        let patch =
+            TextPatch::from_json(r#"{ "GoodsName": { "1": "Flask of Tears", "2": null } }"#)
+                .unwrap();
+
+        let merged = merge(
+            Some(&vanilla),
+            [
+                ("fixes", TextSource::Binder(&fixes)),
+                ("translation", TextSource::Binder(&translation)),
+                ("patch", TextSource::Patch(patch)),
+            ],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            text(&merged.bytes, WEAPON_NAME),
+            [
+                (100, Some("Dague".to_owned())),
+                (200, Some("Épée".to_owned()))
+            ]
+            .into()
+        );
+        assert_eq!(
+            text(&merged.bytes, GOODS_NAME),
+            [(1, Some("Flask of Tears".to_owned())), (2, None)].into()
+        );
+
+        assert_eq!(
+            merged.conflicts,
+            [
+                EntryConflict {
+                    file: "GoodsName.fmg".to_owned(),
+                    id: 1,
+                    packages: vec!["translation".to_owned(), "patch".to_owned()],
+                },
+                EntryConflict {
+                    file: "WeaponName.fmg".to_owned(),
+                    id: 200,
+                    packages: vec!["fixes".to_owned(), "translation".to_owned()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn patches_need_a_binder() {
+        let patch = TextPatch::from_json(r#"{ "WeaponName.fmg": { "100": "Knife" } }"#).unwrap();
+        assert!(merge(None, [("patch", TextSource::Patch(patch.clone()))], None).is_err());
+
+        let base = msgbnd(&[(WEAPON_NAME, &[(100, "Dagger")])]);
+        let merged = merge(
+            None,
+            [
+                ("base", TextSource::Binder(&base)),
+                ("patch", TextSource::Patch(patch)),
+            ],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            text(&merged.bytes, WEAPON_NAME),
+            [(100, Some("Knife".to_owned()))].into()
+        );
+
+        let unknown = TextPatch::from_json(r#"{ "NpcName": { "1": "Melina" } }"#).unwrap();
+        assert!(merge(
+            None,
+            [
+                ("base", TextSource::Binder(&base)),
+                ("patch", TextSource::Patch(unknown)),
+            ],
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn fmg_xml_patches_are_parsed() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<fmg>
+  <filename>WeaponName.fmg</filename>
+  <compression>None</compression>
+  <version>DarkSouls3</version>
+  <bigendian>False</bigendian>
+  <entries>
+    <text id="100">Dagger &amp; Knife</text>
+    <text id="101">%null%</text>
+  </entries>
+</fmg>"#;
+
+        let patch = TextPatch::from_fmg_xml(xml).unwrap();
+
+        assert_eq!(
+            patch.fmgs,
+            [(
+                "WeaponName.fmg".to_owned(),
+                [(100, Some("Dagger & Knife".to_owned())), (101, None)].into()
+            )]
+            .into()
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use std::{fs, mem, path::Path};
+
+use windows::{
+    core::{s, HSTRING},
+    Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW},
+};
+
+use crate::dcx::{DcxError, KrakenDecompressor};
+
+type OodleLzDecompress = unsafe extern "C" fn(
+    comp_buf: *const u8,
+    comp_buf_size: isize,
+    raw_buf: *mut u8,
+    raw_len: isize,
+    fuzz_safe: i32,
+    check_crc: i32,
+    verbosity: i32,
+    dec_buf_base: *mut u8,
+    dec_buf_size: isize,
+    fp_callback: *const (),
+    callback_user_data: *const (),
+    decoder_memory: *mut u8,
+    decoder_memory_size: isize,
+    thread_phase: i32,
+) -> isize;
+
+/// Decodes in a single thread, without the thread phases used to split up decoding.
+const THREAD_PHASE_ALL: i32 = 3;
+
+/// The Oodle library shipped with the game, used to decompress Kraken compressed DCX files.
+pub struct Oodle {
+    decompress: OodleLzDecompress,
+}
+
+impl Oodle {
+    /// Loads the `oo2core_*_win64.dll` library in `game_dir`, if there is one.
+    ///
+    /// The library is never unloaded, since the game loads it as well.
+    pub fn load(game_dir: &Path) -> Option<Self> {
+        let library_path = fs::read_dir(game_dir).ok()?.flatten().find_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_lowercase();
+
+            (file_name.starts_with("oo2core_") && file_name.ends_with("_win64.dll"))
+                .then(|| entry.path())
+        })?;
+
+        unsafe {
+            let module = LoadLibraryW(&HSTRING::from(library_path.as_os_str())).ok()?;
+            let decompress = GetProcAddress(module, s!("OodleLZ_Decompress"))?;
+
+            Some(Self {
+                decompress: mem::transmute::<_, OodleLzDecompress>(decompress),
+            })
+        }
+    }
+}
+
+impl KrakenDecompressor for Oodle {
+    fn decompress(&self, compressed: &[u8], out: &mut [u8]) -> Result<(), DcxError> {
+        let decompressed = unsafe {
+            (self.decompress)(
+                compressed.as_ptr(),
+                compressed.len() as isize,
+                out.as_mut_ptr(),
+                out.len() as isize,
+                1,
+                0,
+                0,
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+                THREAD_PHASE_ALL,
+            )
+        };
+
+        if decompressed != out.len() as isize {
+            return Err(DcxError::Decompress(format!(
+                "OodleLZ_Decompress returned {decompressed}, expected {}",
+                out.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! Selecting the files of a package and the virtual paths they override, on any platform.
+
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use me3_mod_protocol::package::AssetOverrideSource;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PackageFilesError {
+    #[error("Could not read package directory {0}: {1}")]
+    ReadDir(PathBuf, io::Error),
+
+    #[error("Package root {0:?} is not a subdirectory of the package")]
+    InvalidRoot(String),
+
+    #[error("Package mount point {0:?} is not a relative virtual directory")]
+    InvalidMount(String),
+
+    #[error("Invalid package include or exclude glob {0}")]
+    InvalidGlob(#[from] globset::Error),
+}
+
+/// The `include` and `exclude` globs of a package, matched against the VFS paths of its files.
+pub struct PackageFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PackageFilter {
+    pub fn new<S: AssetOverrideSource>(source: &S) -> Result<Self, globset::Error> {
+        fn glob_set(globs: &[String]) -> Result<GlobSet, globset::Error> {
+            let mut builder = GlobSetBuilder::new();
+
+            for glob in globs {
+                // Lookup keys are lowercase, so globs have to match regardless of case.
+                builder.add(
+                    GlobBuilder::new(glob)
+                        .case_insensitive(true)
+                        .literal_separator(true)
+                        .build()?,
+                );
+            }
+
+            builder.build()
+        }
+
+        let include = match source.include() {
+            [] => None,
+            globs => Some(glob_set(globs)?),
+        };
+
+        Ok(Self {
+            include,
+            exclude: glob_set(source.exclude())?,
+        })
+    }
+
+    pub fn is_match<P: AsRef<Path>>(&self, vfs_path: P) -> bool {
+        let vfs_path = vfs_path.as_ref();
+
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(vfs_path))
+            && !self.exclude.is_match(vfs_path)
+    }
+}
+
+/// Is `path` relative, without leaving the directory it's relative to?
+pub fn is_relative_subpath(path: &Path) -> bool {
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Lists the files of a package selected by its `root`, `mount`, `include` and `exclude`
+/// options, sorted by the VFS path they override, lowercase with `/` separators.
+pub fn package_files<S: AssetOverrideSource>(
+    source: S,
+) -> Result<Vec<(String, PathBuf)>, PackageFilesError> {
+    let mut base_dir = source.asset_path().to_path_buf();
+
+    if let Some(root) = source.root() {
+        if !is_relative_subpath(Path::new(root)) {
+            return Err(PackageFilesError::InvalidRoot(root.to_owned()));
+        }
+
+        base_dir.push(root);
+    }
+
+    let mount = match source.mount() {
+        Some(mount) if !is_relative_subpath(Path::new(mount)) || mount.contains(':') => {
+            return Err(PackageFilesError::InvalidMount(mount.to_owned()));
+        }
+        Some(mount) => vfs_path(Path::new(mount)),
+        None => String::new(),
+    };
+
+    let filter = PackageFilter::new(&source)?;
+
+    let mut files = Vec::new();
+    let mut dirs = vec![base_dir.clone()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| PackageFilesError::ReadDir(dir.clone(), e))?;
+
+        for entry in entries {
+            let path = entry
+                .map_err(|e| PackageFilesError::ReadDir(dir.clone(), e))?
+                .path();
+
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            let relative = vfs_path(path.strip_prefix(&base_dir).unwrap());
+
+            let vfs_path = match mount.as_str() {
+                "" => relative,
+                mount => format!("{mount}/{relative}"),
+            };
+
+            if filter.is_match(&vfs_path) {
+                files.push((vfs_path, path));
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+fn vfs_path(path: &Path) -> String {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use me3_mod_protocol::package::Package;
+
+    use super::package_files;
+
+    #[test]
+    fn package_files_are_filtered_and_mounted() {
+        let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
+
+        let mut package = Package::new(test_mod_dir);
+        package.root = Some("sd".to_owned());
+        package.mount = Some("Sound/".to_owned());
+        package.exclude = vec!["sound/enus/**".to_owned()];
+
+        let files = package_files(&package).unwrap();
+        let vfs_paths = files
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vfs_paths, ["sound/init.bnk", "sound/wem/48/485927883.wem"]);
+        assert!(files[0].1.ends_with("sd/init.bnk"));
+    }
+}
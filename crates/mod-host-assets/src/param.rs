@@ -0,0 +1,361 @@
+//! PARAM, the tables of game data stored in the regulation.
+//!
+//! Rows are kept as raw bytes, since their layout is only described by paramdefs that differ
+//! between games and game versions. That's all merging rows needs.
+
+use thiserror::Error;
+
+/// Flags of the `format_2d` header field.
+mod format {
+    pub const FLAG01: u8 = 0b0000_0001;
+    pub const INT_DATA_OFFSET: u8 = 0b0000_0010;
+    pub const LONG_DATA_OFFSET: u8 = 0b0000_0100;
+    pub const OFFSET_PARAM_TYPE: u8 = 0b1000_0000;
+
+    /// Flag of the `format_2e` header field.
+    pub const UNICODE_ROW_NAMES: u8 = 0b0000_0001;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    /// The raw header, with offsets and the row count filled in when writing.
+    header: Vec<u8>,
+
+    /// The param type, if it's stored after the row data rather than in the header.
+    param_type: Option<Vec<u8>>,
+
+    pub row_size: usize,
+    pub rows: Vec<ParamRow>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParamRow {
+    pub id: i32,
+    pub data: Vec<u8>,
+
+    /// The encoded name of the row, without its terminator, if it has one.
+    pub name: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParamError {
+    #[error("PARAM file is truncated")]
+    Truncated,
+
+    #[error("big endian PARAM files aren't supported")]
+    BigEndian,
+
+    #[error("PARAM rows aren't all the same size")]
+    RowSize,
+}
+
+impl Param {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ParamError> {
+        let reader = Reader(bytes);
+
+        if reader.u8(0x2C)? != 0 {
+            return Err(ParamError::BigEndian);
+        }
+
+        let format_2d = reader.u8(0x2D)?;
+        let format_2e = reader.u8(0x2E)?;
+        let row_count = reader.u16(0x0A)? as usize;
+        let strings_offset = reader.u32(0x00)? as usize;
+
+        let header_size = header_size(format_2d);
+        let long_offsets = format_2d & format::LONG_DATA_OFFSET != 0;
+        let row_header_size = row_header_size(format_2d);
+
+        let param_type = if format_2d & format::OFFSET_PARAM_TYPE != 0 {
+            Some(reader.c_str(reader.u64(0x10)? as usize)?.to_vec())
+        } else {
+            None
+        };
+
+        let mut row_headers = Vec::with_capacity(row_count);
+
+        for index in 0..row_count {
+            let offset = header_size + index * row_header_size;
+
+            let (data_offset, name_offset) = if long_offsets {
+                (reader.u64(offset + 8)?, reader.u64(offset + 16)?)
+            } else {
+                (
+                    reader.u32(offset + 4)? as u64,
+                    reader.u32(offset + 8)? as u64,
+                )
+            };
+
+            row_headers.push((
+                reader.u32(offset)? as i32,
+                data_offset as usize,
+                name_offset as usize,
+            ));
+        }
+
+        let row_size = match row_headers.as_slice() {
+            [] => 0,
+            // Strings (and the param type) are stored after the row data.
+            [(_, data_offset, _)] => strings_offset
+                .checked_sub(*data_offset)
+                .ok_or(ParamError::RowSize)?,
+            [(_, first, _), (_, second, _), ..] => {
+                second.checked_sub(*first).ok_or(ParamError::RowSize)?
+            }
+        };
+
+        let rows = row_headers
+            .into_iter()
+            .map(|(id, data_offset, name_offset)| {
+                let data = reader
+                    .0
+                    .get(data_offset..data_offset + row_size)
+                    .ok_or(ParamError::Truncated)?
+                    .to_vec();
+
+                let name = match name_offset {
+                    0 => None,
+                    offset if format_2e & format::UNICODE_ROW_NAMES != 0 => {
+                        Some(reader.utf16_c_str(offset)?.to_vec())
+                    }
+                    offset => Some(reader.c_str(offset)?.to_vec()),
+                };
+
+                Ok(ParamRow { id, data, name })
+            })
+            .collect::<Result<_, ParamError>>()?;
+
+        Ok(Self {
+            header: reader
+                .0
+                .get(..header_size)
+                .ok_or(ParamError::Truncated)?
+                .to_vec(),
+            param_type,
+            row_size,
+            rows,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ParamError> {
+        if self.rows.iter().any(|row| row.data.len() != self.row_size) {
+            return Err(ParamError::RowSize);
+        }
+
+        let format_2d = self.header[0x2D];
+        let unicode_names = self.header[0x2E] & format::UNICODE_ROW_NAMES != 0;
+        let long_offsets = format_2d & format::LONG_DATA_OFFSET != 0;
+        let row_header_size = row_header_size(format_2d);
+
+        let mut out = self.header.clone();
+        let row_headers_offset = out.len();
+
+        out.resize(row_headers_offset + self.rows.len() * row_header_size, 0);
+
+        // Some older games pad the row headers.
+        if format_2d == format::FLAG01 {
+            out.resize(out.len() + 0x20, 0);
+        }
+
+        let data_start = out.len();
+
+        for row in &self.rows {
+            out.extend_from_slice(&row.data);
+        }
+
+        let strings_offset = out.len();
+
+        if let Some(param_type) = &self.param_type {
+            write(&mut out, 0x10, &(strings_offset as u64).to_le_bytes());
+
+            out.extend_from_slice(param_type);
+            out.push(0);
+        }
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let name_offset = match &row.name {
+                Some(name) => {
+                    let offset = out.len();
+
+                    out.extend_from_slice(name);
+                    out.extend_from_slice(if unicode_names { &[0, 0] } else { &[0] });
+
+                    offset
+                }
+                None => 0,
+            };
+
+            let data_offset = data_start + index * self.row_size;
+            let header = row_headers_offset + index * row_header_size;
+
+            write(&mut out, header, &row.id.to_le_bytes());
+
+            if long_offsets {
+                write(&mut out, header + 4, &0u32.to_le_bytes());
+                write(&mut out, header + 8, &(data_offset as u64).to_le_bytes());
+                write(&mut out, header + 16, &(name_offset as u64).to_le_bytes());
+            } else {
+                write(&mut out, header + 4, &(data_offset as u32).to_le_bytes());
+                write(&mut out, header + 8, &(name_offset as u32).to_le_bytes());
+            }
+        }
+
+        write(&mut out, 0x00, &(strings_offset as u32).to_le_bytes());
+        write(&mut out, 0x0A, &(self.rows.len() as u16).to_le_bytes());
+
+        if long_offsets {
+            write(&mut out, 0x30, &(data_start as u64).to_le_bytes());
+        } else if format_2d & format::FLAG01 != 0 && format_2d & format::INT_DATA_OFFSET != 0 {
+            write(&mut out, 0x30, &(data_start as u32).to_le_bytes());
+        } else {
+            write(&mut out, 0x04, &(data_start as u16).to_le_bytes());
+        }
+
+        Ok(out)
+    }
+}
+
+fn header_size(format_2d: u8) -> usize {
+    let int_data_offset =
+        format_2d & format::FLAG01 != 0 && format_2d & format::INT_DATA_OFFSET != 0;
+
+    if int_data_offset || format_2d & format::LONG_DATA_OFFSET != 0 {
+        0x40
+    } else {
+        0x30
+    }
+}
+
+fn row_header_size(format_2d: u8) -> usize {
+    if format_2d & format::LONG_DATA_OFFSET != 0 {
+        0x18
+    } else {
+        0x0C
+    }
+}
+
+fn write(out: &mut [u8], offset: usize, bytes: &[u8]) {
+    out[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], ParamError> {
+        self.0
+            .get(offset..offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(ParamError::Truncated)
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, ParamError> {
+        self.bytes::<1>(offset).map(|[byte]| byte)
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, ParamError> {
+        self.bytes(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, ParamError> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, ParamError> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+
+    fn c_str(&self, offset: usize) -> Result<&[u8], ParamError> {
+        let bytes = self.0.get(offset..).ok_or(ParamError::Truncated)?;
+        let len = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(ParamError::Truncated)?;
+
+        Ok(&bytes[..len])
+    }
+
+    fn utf16_c_str(&self, offset: usize) -> Result<&[u8], ParamError> {
+        let bytes = self.0.get(offset..).ok_or(ParamError::Truncated)?;
+        let len = bytes
+            .chunks_exact(2)
+            .position(|unit| unit == [0, 0])
+            .ok_or(ParamError::Truncated)?;
+
+        Ok(&bytes[..len * 2])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{Param, ParamRow};
+
+    /// Builds a param with the header layout Elden Ring uses.
+    pub fn param(param_type: &str, rows: &[(i32, &[u8])]) -> Param {
+        let mut header = vec![0; 0x40];
+        header[0x2D] = 0x85;
+        header[0x2E] = 0x01;
+        header[0x2F] = 0x06;
+
+        let param = Param {
+            header,
+            param_type: Some(param_type.as_bytes().to_vec()),
+            row_size: rows.first().map_or(0, |(_, data)| data.len()),
+            rows: rows
+                .iter()
+                .map(|&(id, data)| ParamRow {
+                    id,
+                    data: data.to_vec(),
+                    name: None,
+                })
+                .collect(),
+        };
+
+        // Fills in the header.
+        Param::parse(&param.to_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn param_roundtrip() {
+        let mut param = param(
+            "EQUIP_PARAM_WEAPON_ST",
+            &[
+                (0, &[1, 2, 3, 4]),
+                (100, &[5, 6, 7, 8]),
+                (100, &[9, 10, 11, 12]),
+            ],
+        );
+        param.rows[1].name = Some("D\0a\0g\0".as_bytes().to_vec());
+
+        let bytes = param.to_bytes().unwrap();
+        let parsed = Param::parse(&bytes).unwrap();
+
+        assert_eq!(parsed, param);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn single_row_size_is_detected() {
+        let param = param("GAME_AREA_PARAM_ST", &[(10, &[0xAA; 7])]);
+        let parsed = Param::parse(&param.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(parsed.row_size, 7);
+        assert_eq!(parsed.rows, param.rows);
+    }
+
+    #[test]
+    fn short_offsets_roundtrip() {
+        let mut param = param("", &[(1, b"ab"), (2, b"cd")]);
+        param.header.truncate(0x30);
+        param.header[0x2D] = 0x00;
+        param.header[0x2E] = 0x00;
+        param.header[0x0C..0x0C + 9].copy_from_slice(b"NPC_PARAM");
+        param.param_type = None;
+        param.rows[0].name = Some(b"Knight".to_vec());
+
+        let bytes = param.to_bytes().unwrap();
+        let parsed = Param::parse(&bytes).unwrap();
+
+        assert_eq!(parsed.rows, param.rows);
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+}
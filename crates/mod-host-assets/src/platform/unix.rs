@@ -0,0 +1,41 @@
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fs::{DirEntry, FileType},
+    io,
+    path::{self, Component, Path, PathBuf},
+};
+
+use crate::mapping::VfsOverrideMappingError;
+
+/// Paths only need converting to DOS paths when running under WINE.
+pub fn normalize_dos_path(path: &Path) -> Result<Cow<'_, Path>, VfsOverrideMappingError> {
+    Ok(Cow::Borrowed(path))
+}
+
+/// Makes `path` absolute and resolves `.` and `..` without touching the file system, like
+/// `GetFullPathNameW` does on Windows.
+pub fn normalize_virtually(path: &Path) -> io::Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+
+    for component in path::absolute(path)?.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    Ok(normalized)
+}
+
+pub fn is_symlink_dir(dir_entry: &DirEntry, file_type: FileType) -> bool {
+    file_type.is_symlink() && dir_entry.path().is_dir()
+}
+
+/// Encodes `os_str` as UTF-16, replacing bytes that aren't valid UTF-8.
+pub fn encode_wide(os_str: &OsStr) -> Vec<u16> {
+    os_str.to_string_lossy().encode_utf16().collect()
+}
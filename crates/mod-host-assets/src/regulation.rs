@@ -0,0 +1,441 @@
+//! The regulation, an encrypted binder of the params that define most game data.
+//!
+//! Regulations are encrypted with AES-256-CBC, with the IV stored in front of the encrypted
+//! data. Once decrypted, they're a DCX compressed BND4 binder of PARAM files.
+
+use std::collections::{btree_map::Entry, BTreeMap, HashMap};
+
+use aes::{
+    cipher::{block_padding::NoPadding, BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes256,
+};
+use thiserror::Error;
+
+use crate::{
+    bnd4::{Bnd4, Bnd4Error},
+    conflict::{Conflicts, EntryConflict},
+    dcx::{self, Dcx, DcxError, KrakenDecompressor},
+    param::{Param, ParamError, ParamRow},
+};
+
+const BLOCK_SIZE: usize = 16;
+
+/// Regulation keys of the supported games, tried in order until one decrypts a DCX file.
+const KEYS: &[[u8; 32]] = &[
+    // ELDEN RING
+    [
+        0x99, 0xBF, 0xFC, 0x36, 0x6A, 0x6B, 0xC8, 0xC6, 0xF5, 0x82, 0x7D, 0x09, 0x36, 0x02, 0xD6,
+        0x76, 0xC4, 0x28, 0x92, 0xA0, 0x1C, 0x20, 0x7F, 0xB0, 0x24, 0xD3, 0xAF, 0x4E, 0x49, 0x3F,
+        0xEF, 0x99,
+    ],
+];
+
+#[derive(Clone, Debug)]
+pub struct Regulation {
+    key: [u8; 32],
+    pub binder: Bnd4,
+}
+
+#[derive(Debug, Error)]
+pub enum RegulationError {
+    #[error("regulation is truncated")]
+    Truncated,
+
+    #[error("regulation isn't encrypted with a known key")]
+    UnknownKey,
+
+    #[error("param {name}: {source}")]
+    Param { name: String, source: ParamError },
+
+    #[error("param {0} has a different row size than the game's")]
+    RowSize(String),
+
+    #[error(transparent)]
+    Bnd4(#[from] Bnd4Error),
+
+    #[error(transparent)]
+    Dcx(#[from] DcxError),
+}
+
+/// A regulation merged from several packages.
+#[derive(Debug)]
+pub struct MergedRegulation {
+    pub bytes: Vec<u8>,
+
+    /// Conflicting rows, ordered by param and row.
+    pub conflicts: Vec<EntryConflict>,
+}
+
+impl Regulation {
+    pub fn decrypt(
+        bytes: &[u8],
+        kraken: Option<&dyn KrakenDecompressor>,
+    ) -> Result<Self, RegulationError> {
+        let Some((iv, encrypted)) = bytes.split_first_chunk::<BLOCK_SIZE>() else {
+            return Err(RegulationError::Truncated);
+        };
+
+        // Trailing bytes that don't fill a block aren't part of the encrypted data.
+        let encrypted = &encrypted[..encrypted.len() - encrypted.len() % BLOCK_SIZE];
+
+        for key in KEYS {
+            let decryptor = cbc::Decryptor::<Aes256>::new(key.into(), iv.into());
+
+            let mut decrypted = encrypted.to_vec();
+            let decrypted = decryptor
+                .decrypt_padded_mut::<NoPadding>(&mut decrypted)
+                .map_err(|_| RegulationError::Truncated)?;
+
+            if Dcx::is_dcx(decrypted) {
+                let binder = Dcx::parse(decrypted)?.decompress(kraken)?;
+
+                return Ok(Self {
+                    key: *key,
+                    binder: Bnd4::parse(&binder)?,
+                });
+            }
+        }
+
+        Err(RegulationError::UnknownKey)
+    }
+
+    /// Compresses and encrypts the regulation, with the same key it was decrypted with.
+    ///
+    /// The IV is derived from the contents, so the same regulation always encrypts the same way.
+    pub fn encrypt(&self) -> Result<Vec<u8>, RegulationError> {
+        let mut data = dcx::compress_deflate(&self.binder.to_bytes()?);
+        data.resize(data.len().next_multiple_of(BLOCK_SIZE), 0);
+
+        let iv = xxhash_rust::xxh3::xxh3_128(&data).to_le_bytes();
+        let encryptor = cbc::Encryptor::<Aes256>::new((&self.key).into(), (&iv).into());
+
+        let len = data.len();
+        encryptor
+            .encrypt_padded_mut::<NoPadding>(&mut data, len)
+            .map_err(|_| RegulationError::Truncated)?;
+
+        let mut out = Vec::with_capacity(BLOCK_SIZE + len);
+        out.extend_from_slice(&iv);
+        out.extend_from_slice(&data);
+
+        Ok(out)
+    }
+
+    fn param(&self, name: &str) -> Result<Option<Param>, RegulationError> {
+        self.binder
+            .entry(name)
+            .map(|entry| parse_param(name, &entry.data))
+            .transpose()
+    }
+}
+
+/// Merges the regulations of several packages, in load order, with the game's own regulation.
+///
+/// Each package's changes to the game's regulation are applied row by row, so packages changing
+/// different rows of the same param don't override each other. Rows changed by more than one
+/// package are taken from the last of them and reported as conflicts.
+pub fn merge<'a, I>(
+    vanilla: &[u8],
+    packages: I,
+    kraken: Option<&dyn KrakenDecompressor>,
+) -> Result<MergedRegulation, RegulationError>
+where
+    I: IntoIterator<Item = (&'a str, &'a [u8])>,
+{
+    let vanilla = Regulation::decrypt(vanilla, kraken)?;
+    let mut merged = vanilla.clone();
+
+    let mut params = BTreeMap::<String, MergedParam>::new();
+    let mut conflicts = Conflicts::default();
+
+    for (package, bytes) in packages {
+        let regulation = Regulation::decrypt(bytes, kraken)?;
+
+        for entry in regulation.binder.entries {
+            if !entry.name.to_lowercase().ends_with(".param") {
+                let vanilla_entry = vanilla.binder.entry(&entry.name);
+
+                if vanilla_entry.is_none_or(|vanilla_entry| vanilla_entry.data != entry.data) {
+                    merged.binder.merge_entry(entry);
+                }
+
+                continue;
+            }
+
+            let name = file_name(&entry.name).to_owned();
+
+            let merged_param = match params.entry(name.clone()) {
+                Entry::Occupied(occupied) => occupied.into_mut(),
+                Entry::Vacant(vacant) => match vanilla.param(&entry.name)? {
+                    Some(param) => vacant.insert(MergedParam::new(entry.name.clone(), param)),
+                    None => {
+                        // Params the game doesn't have can't conflict with anything.
+                        merged.binder.merge_entry(entry);
+                        continue;
+                    }
+                },
+            };
+
+            let param = parse_param(&name, &entry.data)?;
+
+            if param.row_size != merged_param.vanilla.row_size {
+                return Err(RegulationError::RowSize(name));
+            }
+
+            for (key, row) in merged_param.changes(param) {
+                if let Some((earlier, earlier_row)) = merged_param.changed_by.get(&key)
+                    && *earlier_row != row
+                {
+                    conflicts.record(&name, key.0, earlier, package);
+                }
+
+                merged_param
+                    .changed_by
+                    .insert(key, (package.to_owned(), row));
+            }
+        }
+    }
+
+    for merged_param in params.into_values() {
+        let name = merged_param.entry_name.clone();
+        let param = merged_param.finish();
+
+        let data = param.to_bytes().map_err(|source| RegulationError::Param {
+            name: name.clone(),
+            source,
+        })?;
+
+        if let Some(entry) = merged.binder.entry_mut(&name) {
+            entry.data = data;
+        }
+    }
+
+    Ok(MergedRegulation {
+        bytes: merged.encrypt()?,
+        conflicts: conflicts.into_vec(),
+    })
+}
+
+/// Identifies a row by its ID and, for the rare params with duplicate IDs, its position among the
+/// rows with the same ID.
+type RowKey = (i32, usize);
+
+struct MergedParam {
+    entry_name: String,
+    vanilla: Param,
+    vanilla_rows: HashMap<RowKey, ParamRow>,
+
+    /// The last package to change each row, and the row it changed it to (`None` if it was
+    /// removed).
+    changed_by: HashMap<RowKey, (String, Option<ParamRow>)>,
+}
+
+impl MergedParam {
+    fn new(entry_name: String, vanilla: Param) -> Self {
+        let vanilla_rows = keyed_rows(&vanilla.rows).collect();
+
+        Self {
+            entry_name,
+            vanilla,
+            vanilla_rows,
+            changed_by: HashMap::new(),
+        }
+    }
+
+    /// Rows that `param` adds, changes or removes compared to the game's param.
+    fn changes(&self, param: Param) -> Vec<(RowKey, Option<ParamRow>)> {
+        let rows = keyed_rows(&param.rows).collect::<HashMap<_, _>>();
+
+        let mut changes = rows
+            .iter()
+            .filter(|(key, row)| self.vanilla_rows.get(key) != Some(row))
+            .map(|(key, row)| (*key, Some(row.clone())))
+            .chain(
+                self.vanilla_rows
+                    .keys()
+                    .filter(|key| !rows.contains_key(key))
+                    .map(|key| (*key, None)),
+            )
+            .collect::<Vec<_>>();
+
+        changes.sort_unstable_by_key(|(key, _)| *key);
+        changes
+    }
+
+    /// Applies the changes to the game's param, keeping rows sorted by ID like the game expects.
+    fn finish(self) -> Param {
+        let mut rows = self
+            .vanilla_rows
+            .into_iter()
+            .collect::<BTreeMap<RowKey, ParamRow>>();
+
+        for (key, (_, row)) in self.changed_by {
+            match row {
+                Some(row) => rows.insert(key, row),
+                None => rows.remove(&key),
+            };
+        }
+
+        let mut param = self.vanilla;
+        param.rows = rows.into_values().collect();
+        param
+    }
+}
+
+fn keyed_rows(rows: &[ParamRow]) -> impl Iterator<Item = (RowKey, ParamRow)> + '_ {
+    let mut occurrences = HashMap::<i32, usize>::new();
+
+    rows.iter().map(move |row| {
+        let occurrence = occurrences.entry(row.id).or_default();
+        let key = (row.id, *occurrence);
+        *occurrence += 1;
+
+        (key, row.clone())
+    })
+}
+
+fn parse_param(name: &str, bytes: &[u8]) -> Result<Param, RegulationError> {
+    Param::parse(bytes).map_err(|source| RegulationError::Param {
+        name: file_name(name).to_owned(),
+        source,
+    })
+}
+
+/// The file name of a binder entry, e.g. `EquipParamWeapon.param` for
+/// `N:\GR\data\Param\param\GameParam\EquipParamWeapon.param`.
+fn file_name(name: &str) -> &str {
+    name.rsplit(['\\', '/']).next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{merge, Regulation, KEYS};
+    use crate::{
+        bnd4::{Bnd4, Bnd4Entry},
+        conflict::EntryConflict,
+        param::{test::param, Param},
+    };
+
+    const WEAPONS: &str = "N:\\GR\\data\\Param\\param\\GameParam\\EquipParamWeapon.param";
+    const GOODS: &str = "N:\\GR\\data\\Param\\param\\GameParam\\EquipParamGoods.param";
+
+    fn regulation(params: &[(&str, Param)]) -> Vec<u8> {
+        let mut binder = Bnd4::new();
+
+        for (id, (name, param)) in params.iter().enumerate() {
+            binder.entries.push(Bnd4Entry {
+                flags: 0x40,
+                id: id as i32,
+                name: name.to_string(),
+                data: param.to_bytes().unwrap(),
+                uncompressed_size: None,
+            });
+        }
+
+        Regulation {
+            key: KEYS[0],
+            binder,
+        }
+        .encrypt()
+        .unwrap()
+    }
+
+    fn rows(regulation: &[u8], name: &str) -> Vec<(i32, Vec<u8>)> {
+        let regulation = Regulation::decrypt(regulation, None).unwrap();
+
+        regulation
+            .param(name)
+            .unwrap()
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| (row.id, row.data))
+            .collect()
+    }
+
+    #[test]
+    fn regulation_roundtrip() {
+        let weapons = param(
+            "EQUIP_PARAM_WEAPON_ST",
+            &[(100, b"dagger"), (200, b"sword!")],
+        );
+        let bytes = regulation(&[(WEAPONS, weapons.clone())]);
+
+        let decrypted = Regulation::decrypt(&bytes, None).unwrap();
+
+        assert_eq!(decrypted.param(WEAPONS).unwrap(), Some(weapons));
+        assert_eq!(decrypted.encrypt().unwrap(), bytes);
+        assert!(Regulation::decrypt(&bytes[..16], None).is_err());
+    }
+
+    #[test]
+    fn rows_are_merged_in_load_order() {
+        let vanilla = regulation(&[
+            (
+                WEAPONS,
+                param(
+                    "EQUIP_PARAM_WEAPON_ST",
+                    &[(100, b"dagger"), (200, b"sword!"), (300, b"spear!")],
+                ),
+            ),
+            (GOODS, param("EQUIP_PARAM_GOODS_ST", &[(1, b"flask")])),
+        ]);
+
+        let first = regulation(&[
+            (
+                WEAPONS,
+                param(
+                    "EQUIP_PARAM_WEAPON_ST",
+                    &[(100, b"DAGGER"), (200, b"sword!"), (300, b"spear!")],
+                ),
+            ),
+            (GOODS, param("EQUIP_PARAM_GOODS_ST", &[(1, b"FLASK")])),
+        ]);
+
+        let second = regulation(&[(
+            WEAPONS,
+            param(
+                "EQUIP_PARAM_WEAPON_ST",
+                &[(100, b"dagger"), (150, b"rapier"), (200, b"SWORD!")],
+            ),
+        )]);
+
+        let third = regulation(&[(
+            WEAPONS,
+            param(
+                "EQUIP_PARAM_WEAPON_ST",
+                &[(100, b"Dagger"), (200, b"sword!"), (300, b"spear!")],
+            ),
+        )]);
+
+        let merged = merge(
+            &vanilla,
+            [
+                ("first", &first[..]),
+                ("second", &second),
+                ("third", &third),
+            ],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rows(&merged.bytes, WEAPONS),
+            [
+                (100, b"Dagger".to_vec()),
+                (150, b"rapier".to_vec()),
+                (200, b"SWORD!".to_vec()),
+            ]
+        );
+        assert_eq!(rows(&merged.bytes, GOODS), [(1, b"FLASK".to_vec())]);
+
+        assert_eq!(
+            merged.conflicts,
+            [EntryConflict {
+                file: "EquipParamWeapon.param".to_owned(),
+                id: 100,
+                packages: vec!["first".to_owned(), "third".to_owned()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! A cached listing of the files of a package, so large packages don't have to be walked on every
+//! launch.
+//!
+//! The index records the modification time of every directory it lists. Adding, removing or
+//! renaming a file changes the time of its directory, so the index is still valid as long as
+//! every directory it recorded is unchanged, which only takes one `stat` per directory.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use me3_mod_protocol::package::AssetOverrideSource;
+use xxhash_rust::xxh3::Xxh3;
+
+/// Subdirectory of the cache directory indexes are stored in.
+pub const INDEX_DIR: &str = "scan-index";
+
+const HEADER: &str = "me3 scan index 1";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanIndex {
+    /// Directories relative to the package root, with `/` separators, and their modification
+    /// times in 100ns ticks, the precision Windows reports them with.
+    dirs: Vec<(String, u64)>,
+
+    /// Files relative to the package root, with `/` separators, in their original case.
+    files: Vec<String>,
+}
+
+impl ScanIndex {
+    /// Walks `root` and its subdirectories, following symbolic links.
+    pub fn build(root: &Path) -> io::Result<Self> {
+        let mut index = Self {
+            dirs: Vec::new(),
+            files: Vec::new(),
+        };
+
+        let mut dirs = vec![String::new()];
+
+        while let Some(dir) = dirs.pop() {
+            let dir_path = join(root, &dir);
+
+            index.dirs.push((dir.clone(), modified_ticks(&dir_path)?));
+
+            for entry in fs::read_dir(&dir_path)? {
+                let entry = entry?;
+
+                let name = entry
+                    .file_name()
+                    .into_string()
+                    .ok()
+                    .filter(|name| !name.contains(['\n', '\t']))
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, "unindexable file name")
+                    })?;
+
+                let path = match dir.as_str() {
+                    "" => name,
+                    dir => format!("{dir}/{name}"),
+                };
+
+                if entry.path().is_dir() {
+                    dirs.push(path);
+                } else {
+                    index.files.push(path);
+                }
+            }
+        }
+
+        index.dirs.sort();
+        index.files.sort();
+
+        Ok(index)
+    }
+
+    /// Is the index still valid for `root`, i.e. have none of its directories changed?
+    pub fn is_fresh(&self, root: &Path) -> bool {
+        self.dirs
+            .iter()
+            .all(|(dir, ticks)| modified_ticks(&join(root, dir)).is_ok_and(|now| now == *ticks))
+    }
+
+    /// Loads the index at `index_path` if `root` hasn't changed since it was built, or else
+    /// rebuilds and saves it.
+    pub fn load_or_build(index_path: &Path, root: &Path) -> io::Result<Self> {
+        if let Ok(text) = fs::read_to_string(index_path)
+            && let Some(index) = Self::parse(&text)
+            && index.is_fresh(root)
+        {
+            return Ok(index);
+        }
+
+        let index = Self::build(root)?;
+
+        if let Some(parent) = index_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Written to a temporary file first, so an interrupted write isn't loaded.
+        let mut partial_path = index_path.as_os_str().to_owned();
+        partial_path.push(".partial");
+
+        fs::write(&partial_path, index.to_text())?;
+        fs::rename(partial_path, index_path)?;
+
+        Ok(index)
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+
+        if lines.next()? != HEADER {
+            return None;
+        }
+
+        let mut index = Self {
+            dirs: Vec::new(),
+            files: Vec::new(),
+        };
+
+        for line in lines {
+            let (kind, rest) = line.split_once('\t')?;
+
+            match kind {
+                "d" => {
+                    let (ticks, dir) = rest.split_once('\t')?;
+                    index.dirs.push((dir.to_owned(), ticks.parse().ok()?));
+                }
+                "f" => index.files.push(rest.to_owned()),
+                _ => return None,
+            }
+        }
+
+        Some(index)
+    }
+
+    /// One directory or file per line, tab separated.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\n");
+
+        for (dir, ticks) in &self.dirs {
+            text.push_str(&format!("d\t{ticks}\t{dir}\n"));
+        }
+
+        for file in &self.files {
+            text.push_str(&format!("f\t{file}\n"));
+        }
+
+        text
+    }
+
+    /// Files relative to the package root, with `/` separators.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(String::as_str)
+    }
+}
+
+/// The directory the files of `source` are scanned from, i.e. its path joined with its `root`.
+pub fn source_dir<S: AssetOverrideSource>(source: &S) -> PathBuf {
+    let mut dir = source.asset_path().to_path_buf();
+    dir.extend(source.root());
+    dir
+}
+
+/// Name of the index of `source` in [`INDEX_DIR`].
+///
+/// It's named after the path and root of the package as they're configured, rather than the
+/// directory they resolve to, so the CLI and a game running under Proton agree on it.
+pub fn index_file_name<S: AssetOverrideSource>(source: &S) -> String {
+    let mut hasher = Xxh3::new();
+
+    hasher.update(source.asset_path().as_os_str().as_encoded_bytes());
+    hasher.update(b"\0");
+    hasher.update(source.root().unwrap_or_default().as_bytes());
+
+    format!("{:032x}.idx", hasher.digest128())
+}
+
+/// Joins a `/` separated relative path to `root` one component at a time, so the separators
+/// match the platform's.
+pub fn join(root: &Path, relative: &str) -> PathBuf {
+    let mut path = root.to_path_buf();
+    path.extend(relative.split('/').filter(|c| !c.is_empty()));
+    path
+}
+
+fn modified_ticks(path: &Path) -> io::Result<u64> {
+    let modified = fs::metadata(path)?.modified()?;
+
+    let since_epoch = modified
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok((since_epoch.as_nanos() / 100) as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{join, ScanIndex};
+
+    #[test]
+    fn indexes_are_reused_until_a_directory_changes() {
+        let test_dir = tempfile::tempdir().unwrap();
+        let test_dir = test_dir.path();
+        let root = test_dir.join("package");
+        let index_path = test_dir.join("package.idx");
+
+        for file in [
+            "regulation.bin",
+            "chr/c0000.anibnd.dcx",
+            "sd/enus/cs_main.bnk",
+        ] {
+            let path = join(&root, file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+
+        let index = ScanIndex::load_or_build(&index_path, &root).unwrap();
+
+        assert_eq!(
+            index.files().collect::<Vec<_>>(),
+            [
+                "chr/c0000.anibnd.dcx",
+                "regulation.bin",
+                "sd/enus/cs_main.bnk"
+            ]
+        );
+
+        let saved = ScanIndex::parse(&fs::read_to_string(&index_path).unwrap()).unwrap();
+
+        assert_eq!(saved, index);
+        assert!(saved.is_fresh(&root));
+
+        fs::remove_dir_all(root.join("sd")).unwrap();
+        assert!(!saved.is_fresh(&root));
+
+        let index = ScanIndex::load_or_build(&index_path, &root).unwrap();
+        assert_eq!(
+            index.files().collect::<Vec<_>>(),
+            ["chr/c0000.anibnd.dcx", "regulation.bin"]
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use std::mem;
+use std::{iter, mem};
 
-use me3_binary_analysis::rtti::ClassMap;
+use me3_binary_analysis::{patterns, rtti::ClassMap};
 use pelite::pe::Pe;
 use regex::bytes::Regex;
 use windows::core::PCWSTR;
@@ -17,7 +17,59 @@ struct FilePackageLowLevelIOBlockingVtable {
     open_by_name: WwiseOpenFileByName,
 }
 
-const PREFIXES: &[&str] = &["sd", "sd/enus", "sd/ja"];
+/// The directories sounds are looked up in: `sd`, then the language directories packages
+/// provide, e.g. `sd/enus` or `sd/frfr`, starting with the game's language.
+pub struct SoundPrefixes(Vec<String>);
+
+impl SoundPrefixes {
+    /// `language` is the sound directory of the language the game is played in, e.g. `enus`.
+    /// Other languages are tried in alphabetical order if a sound isn't overridden for it.
+    pub fn new(mapping: &VfsOverrideMapping, language: Option<&str>) -> Self {
+        let mut languages = mapping
+            .package_files()
+            .filter_map(|(path, _)| {
+                let (language, _) = path.strip_prefix("sd/")?.split_once('/')?;
+
+                // Not WEM directories, or the directories of WEMs patched into soundbanks.
+                (language != "wem" && !language.contains('.')).then(|| language.to_owned())
+            })
+            .collect::<Vec<_>>();
+
+        // The game's language first, then the others alphabetically.
+        let order = |other: &String| (Some(other.as_str()) != language, other.to_owned());
+        languages.sort_by_cached_key(order);
+        languages.dedup();
+
+        let languages = languages
+            .into_iter()
+            .map(|language| format!("sd/{language}"));
+
+        Self(iter::once("sd".to_owned()).chain(languages).collect())
+    }
+}
+
+/// The sound directory of a Steam game language, e.g. `enus` for `english`.
+pub fn sound_language(steam_language: &str) -> Option<&'static str> {
+    let language = match steam_language {
+        "english" => "enus",
+        "japanese" => "jajp",
+        "french" => "frfr",
+        "german" => "dede",
+        "italian" => "itit",
+        "spanish" => "eses",
+        "latam" => "esmx",
+        "brazilian" => "ptbr",
+        "polish" => "plpl",
+        "russian" => "ruru",
+        "koreana" => "kokr",
+        "schinese" => "zhcn",
+        "tchinese" => "zhtw",
+        "thai" => "thth",
+        _ => return None,
+    };
+
+    Some(language)
+}
 
 /// Strip sd:/ and sd_dlc02:/ prefixes from the input string.
 pub fn strip_prefix(input: &str) -> &str {
@@ -49,11 +101,18 @@ pub enum AkOpenMode {
 }
 
 /// Tries to find an override for a sound archive entry.
-pub fn find_override<'a>(mapping: &'a VfsOverrideMapping, input: &str) -> Option<&'a VfsOverride> {
+///
+/// Soundbanks with patched WEMs are served as merged by
+/// [`VfsOverrideMapping::merge_overrides`].
+pub fn find_override<'a>(
+    mapping: &'a VfsOverrideMapping,
+    prefixes: &SoundPrefixes,
+    input: &str,
+) -> Option<&'a VfsOverride> {
     let input = strip_prefix(input);
     if input.ends_with(".wem") {
         let wem_path = format!("wem/{input}");
-        if let Some(replacement) = get_override(mapping, &wem_path) {
+        if let Some(replacement) = get_override(mapping, prefixes, &wem_path) {
             return Some(replacement);
         }
 
@@ -61,18 +120,27 @@ pub fn find_override<'a>(mapping: &'a VfsOverrideMapping, input: &str) -> Option
         // location too.
         let folder = input.split_at(2).0;
         let wem_path = format!("wem/{folder}/{input}");
-        if let Some(replacement) = get_override(mapping, &wem_path) {
+        if let Some(replacement) = get_override(mapping, prefixes, &wem_path) {
             return Some(replacement);
         }
-    } else if let Some(replacement) = get_override(mapping, input) {
+
+        // Loose WEMs, e.g. from a package mounted at `sd`.
+        if let Some(replacement) = get_override(mapping, prefixes, input) {
+            return Some(replacement);
+        }
+    } else if let Some(replacement) = get_override(mapping, prefixes, input) {
         return Some(replacement);
     }
 
     None
 }
 
-fn get_override<'a>(mapping: &'a VfsOverrideMapping, input: &str) -> Option<&'a VfsOverride> {
-    for prefix in PREFIXES {
+fn get_override<'a>(
+    mapping: &'a VfsOverrideMapping,
+    prefixes: &SoundPrefixes,
+    input: &str,
+) -> Option<&'a VfsOverride> {
+    for prefix in &prefixes.0 {
         let prefixed = format!("{prefix}/{input}");
         if let Some(replacement) = mapping.vfs_override(&prefixed) {
             return Some(replacement);
@@ -83,9 +151,14 @@ fn get_override<'a>(mapping: &'a VfsOverrideMapping, input: &str) -> Option<&'a
 
 #[cfg(test)]
 mod test {
-    use std::path::Path;
+    use std::{fs, iter, path::Path};
 
-    use crate::{mapping::VfsOverrideMapping, wwise::find_override};
+    use me3_mod_protocol::package::Package;
+
+    use crate::{
+        mapping::VfsOverrideMapping,
+        wwise::{find_override, sound_language, SoundPrefixes},
+    };
 
     #[test]
     fn scan_directory_and_overrides() {
@@ -94,19 +167,71 @@ mod test {
         let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
         asset_mapping.scan_directory(test_mod_dir).unwrap();
 
+        let prefixes = SoundPrefixes::new(&asset_mapping, None);
+        assert_eq!(prefixes.0, ["sd", "sd/enus"]);
+
         assert!(
-            find_override(&asset_mapping, "sd:/init.bnk").is_some(),
+            find_override(&asset_mapping, &prefixes, "sd:/init.bnk").is_some(),
             "override for init.bnk was not found"
         );
         assert!(
-            find_override(&asset_mapping, "sd:/1000519763.wem").is_some(),
+            find_override(&asset_mapping, &prefixes, "sd:/1000519763.wem").is_some(),
             "override for sd:/1000519763.wem not found"
         );
         assert!(
-            find_override(&asset_mapping, "sd:/485927883.wem").is_some(),
+            find_override(&asset_mapping, &prefixes, "sd:/485927883.wem").is_some(),
             "override for sd:/485927883.wem not found"
         );
     }
+
+    #[test]
+    fn game_language_is_tried_first() {
+        let test_dir = tempfile::tempdir().unwrap();
+
+        for language in ["enus", "frfr", "jajp"] {
+            let dir = test_dir.path().join("sd").join(language);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("1000519763.wem"), language).unwrap();
+        }
+
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+        asset_mapping.scan_directory(test_dir.path()).unwrap();
+
+        let prefixes = SoundPrefixes::new(&asset_mapping, sound_language("japanese"));
+        assert_eq!(prefixes.0, ["sd", "sd/jajp", "sd/enus", "sd/frfr"]);
+
+        let found = find_override(&asset_mapping, &prefixes, "sd:/1000519763.wem").unwrap();
+        assert_eq!(fs::read_to_string(found.as_path()).unwrap(), "jajp");
+
+        let prefixes = SoundPrefixes::new(&asset_mapping, sound_language("klingon"));
+        assert_eq!(prefixes.0, ["sd", "sd/enus", "sd/frfr", "sd/jajp"]);
+    }
+
+    #[test]
+    fn mounted_loose_wems() {
+        let mut asset_mapping = VfsOverrideMapping::new().unwrap();
+
+        let test_mod_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/test-mod");
+
+        let mut package = Package::new(test_mod_dir);
+        package.root = Some("sd/enus/wem/10".to_owned());
+        package.mount = Some("sd".to_owned());
+
+        asset_mapping
+            .scan_directories(iter::once(&package))
+            .unwrap();
+
+        let prefixes = SoundPrefixes::new(&asset_mapping, None);
+
+        assert!(
+            find_override(&asset_mapping, &prefixes, "sd:/1000519763.wem").is_some(),
+            "override for loose sd:/1000519763.wem not found"
+        );
+        assert!(
+            find_override(&asset_mapping, &prefixes, "sd:/init.bnk").is_none(),
+            "init.bnk was found outside of the package root"
+        );
+    }
 }
 
 pub fn find_wwise_open_file<'a, P>(program: P, class_map: &ClassMap) -> Option<WwiseOpenFileByName>
@@ -141,16 +266,7 @@ where
         .by_name(".text")
         .and_then(|s| program.get_section_bytes(s).ok())?;
 
-    // Matches:
-    // call   WwiseOpenFileByName
-    // cmp    eax,0x1
-    // je     ??
-    // add    reg,0x38
-    // cmp    QWORD PTR [rbp+0x0],0x8
-    let open_file_re = Regex::new(
-        r"(?s-u)\xe8(.{4})\x83\xf8\x01(?:(?:\x74.)|(?:\x0f\x84.{4}))[\x48-\x4f]\x83[\xc0-\xc7]\x38[\x48-\x4f]\x83(?:(?:\x7d.)|(?:\xbd.{4}))\x08",
-    )
-    .unwrap();
+    let open_file_re = Regex::new(patterns::WWISE_OPEN_FILE.regex).unwrap();
 
     let call_disp32 = open_file_re
         .captures(text)
@@ -1,7 +1,7 @@
 use std::{collections::BTreeMap, mem, ptr::NonNull, slice, sync::OnceLock};
 
 use eyre::{eyre, OptionExt};
-use me3_binary_analysis::{pe, rtti::ClassMap};
+use me3_binary_analysis::{patterns, pe, rtti::ClassMap};
 use me3_launcher_attach_protocol::AttachConfig;
 use me3_mod_host_types::alloc::DlAllocator;
 use me3_mod_protocol::Game;
@@ -30,10 +30,7 @@ pub fn hook_system_allocator(
     exe: Executable,
 ) -> Result<(), eyre::Error> {
     fn hook_system_allocator_inner(exe: Executable) -> Result<(), eyre::Error> {
-        let re = Regex::new(
-            r"(?s-u)\xe8.{4}\x48\x8b\x74\x24\x30\x48\x8b\x5c\x24\x38\x48\x83\xc4\x20\x5f\xe9(.{4})",
-        )
-        .unwrap();
+        let re = Regex::new(patterns::SYSTEM_ALLOCATOR.regex).unwrap();
 
         let text_section =
             pe::section(exe, ".text").map_err(|_| eyre!(".text section is missing"))?;
@@ -142,10 +139,7 @@ pub fn hook_heap_allocators(
 }
 
 fn find_cs_memory_vtable(exe: Executable) -> Result<&'static CSMemoryVtable, eyre::Error> {
-    let vtable_re = Regex::new(
-        r"(?s-u)\xe8.{4}\x90\x48\x8d\x05(.{4})\x48\x89\x03\xc6\x83.\x02\x00\x00\x00\x48\x8b\xc3\x48\x83\xc4.\x5b\xc3"
-    )
-    .unwrap();
+    let vtable_re = Regex::new(patterns::CS_MEMORY_VTABLE.regex).unwrap();
 
     let Some((_, [disp32 @ &[b0, b1, b2, b3]])) =
         vtable_re.captures(exe.image()).map(|c| c.extract())
@@ -168,9 +162,7 @@ fn allocator_table_mut(
     attach_config: &AttachConfig,
     exe: Executable,
 ) -> Result<&'static mut [Option<NonNull<DlAllocator>>], eyre::Error> {
-    let first_re =
-        Regex::new(r"(?s-u)\x48\x89\x05(.{4})\x4c\x8b\xc0\xba\x08\x00\x00\x00\x8d\x4a\x08")
-            .unwrap();
+    let first_re = Regex::new(patterns::FIRST_ALLOCATOR.regex).unwrap();
 
     let Some((_, [disp32 @ &[b0, b1, b2, b3]])) =
         first_re.captures(exe.image()).map(|c| c.extract())
@@ -187,11 +179,9 @@ fn allocator_table_mut(
     };
 
     let re_str = match attach_config.game {
-        Game::DarkSouls3 => r"(?s-u)\x48\x89\x05(.{4})\x4c\x8b\xc0\xba\x08\x00\x00\x00\x8d\x4a\x78",
-        Game::Sekiro => r"(?s-u)\x48\x89\x05(.{4})\x4c\x8b\xc0\xba\x08\x00\x00\x00\x8d\x4a\x70",
-        _ => {
-            r"(?s-u)\x48\x89\x3d(.{4})\xc7\x44\x24\x20\xff\xff\xff\xff\x45\x33\xc9\x4c\x8b\xc7\x48\x8d\x15.{4}"
-        }
+        Game::DarkSouls3 => patterns::LAST_ALLOCATOR_DS3.regex,
+        Game::Sekiro => patterns::LAST_ALLOCATOR_SEKIRO.regex,
+        _ => patterns::LAST_ALLOCATOR.regex,
     };
 
     let last_re = Regex::new(re_str).unwrap();
@@ -227,10 +217,7 @@ fn allocator_table_mut(
 }
 
 fn patch_ds3(exe: Executable) -> Result<(), eyre::Error> {
-    let re = Regex::new(
-        r"(?s-u)\x48\x8b\x1d.{4}\x48\x8b\x0d.{4}\xe8(.{4})\x48\x8b\xd0\x45\x33\xc0\x48\x8b\xcb\xe8.{4}\xe8.{4}",
-    )
-    .unwrap();
+    let re = Regex::new(patterns::DEBUG_ALLOCATOR_DS3.regex).unwrap();
 
     let text_section = pe::section(exe, ".text").map_err(|_| eyre!(".text section is missing"))?;
     let text = exe.get_section_bytes(text_section)?;
@@ -264,10 +251,7 @@ fn patch_ds3(exe: Executable) -> Result<(), eyre::Error> {
 }
 
 fn patch_sdt(exe: Executable) -> Result<(), eyre::Error> {
-    let re = Regex::new(
-        r"(?s-u)\xe8(.{4})\x48\x89\x44\x24.\x4c\x8b\xc0\xba\x08\x00\x00\x00\xb9\x90\x00\x00\x00\xe8.{4}",
-    )
-    .unwrap();
+    let re = Regex::new(patterns::DEBUG_ALLOCATOR_SEKIRO.regex).unwrap();
 
     let text_section = pe::section(exe, ".text").map_err(|_| eyre!(".text section is missing"))?;
     let text = exe.get_section_bytes(text_section)?;
@@ -1,41 +1,49 @@
 use std::{
     alloc::{GlobalAlloc, Layout},
-    ffi::OsString,
-    fs::OpenOptions,
-    io::{Read, Write},
+    ffi::{c_char, c_void, CStr, OsString},
+    io::Write,
+    mem,
     os::windows::ffi::{OsStrExt, OsStringExt},
     path::Path,
     ptr::NonNull,
     slice,
-    sync::{Arc, Mutex, OnceLock},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
 };
 
-use base64::{prelude::BASE64_STANDARD, Engine};
 use eyre::{eyre, OptionExt};
-use me3_binary_analysis::{fd4_step::Fd4StepTables, rtti::ClassMap};
+use me3_binary_analysis::{
+    fd4_step::{Fd4StepFunction, Fd4StepTables},
+    rtti::ClassMap,
+};
 use me3_launcher_attach_protocol::AttachConfig;
 use me3_mod_host_assets::{
-    bhd5::Bhd5Header,
-    dl_device::{self, DlDeviceManager, DlFileOperator, VfsMounts},
+    bhd5::{self, rsa::RsaPublicKey},
+    dl_device::{self, DlDevice, DlDeviceManager, DlDeviceManagerGuard, DlFileOperator, VfsMounts},
     ebl::{mount_ebl, DlDeviceEblExt, EblFileManager},
     mapping::VfsOverrideMapping,
-    wwise::{self, find_wwise_open_file, AkOpenMode},
+    wwise::{self, find_wwise_open_file, AkOpenMode, SoundPrefixes},
 };
 use me3_mod_host_types::{alloc::DlStdAllocator, string::DlUtf16String};
 use me3_mod_protocol::Game;
-use miniz_oxide::{
-    deflate::compress_to_vec,
-    inflate::stream::{inflate, InflateState},
-    DataFormat, MZFlush,
-};
-use pkcs1::der::Decode;
 use rdvec::{RawVec, Vec as DynVec};
 use tempfile::NamedTempFile;
 use tracing::{debug, error, info, info_span, instrument, warn};
-use windows::core::{PCSTR, PCWSTR};
-use xxhash_rust::xxh3;
+use windows::{
+    core::{s, w, PCSTR, PCWSTR},
+    Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+};
 
-use crate::{alloc_hooks::MIMALLOC_DLALLOC, executable::Executable, host::ModHost};
+use crate::{
+    alloc_hooks::MIMALLOC_DLALLOC,
+    asset_trace,
+    deferred::{defer_init, Deferred},
+    event,
+    executable::Executable,
+    host::ModHost,
+};
 
 static VFS_MOUNTS: Mutex<VfsMounts> = Mutex::new(VfsMounts::new());
 
@@ -85,6 +93,13 @@ fn enable_loose_params(attach_config: &AttachConfig, mapping: &VfsOverrideMappin
     }
 }
 
+/// Finds the step function that initializes the game's file system.
+pub fn file_init_step(step_tables: &Fd4StepTables) -> Option<Fd4StepFunction> {
+    step_tables
+        .by_name("CSFileStep::STEP_Init")
+        .or_else(|| step_tables.by_name("SprjFileStep::STEP_Init"))
+}
+
 #[instrument(name = "file_step", skip_all)]
 fn hook_file_init(
     attach_config: Arc<AttachConfig>,
@@ -93,32 +108,38 @@ fn hook_file_init(
     step_tables: &Fd4StepTables,
     mapping: Arc<VfsOverrideMapping>,
 ) -> Result<(), eyre::Error> {
-    let init_fn = step_tables
-        .by_name("CSFileStep::STEP_Init")
-        .or_else(|| step_tables.by_name("SprjFileStep::STEP_Init"))
-        .ok_or_eyre("FileStep::STEP_Init not found")?;
+    let init_fn = file_init_step(step_tables).ok_or_eyre("FileStep::STEP_Init not found")?;
 
     debug!("FileStep::STEP_Init" = ?init_fn);
 
-    ModHost::get_attached()
-        .hook(init_fn)
-        .with_span(info_span!("hook"))
-        .with_closure(move |p1, trampoline| {
-            let result = hook_device_manager(exe, mapping.clone())
-                .and_then(|_| hook_mount_ebl(attach_config.clone(), exe))
-                .inspect_err(|e| error!("error" = &**e, "failed apply pre-hooks"));
+    let pre_hooks_applied = Arc::new(AtomicBool::new(false));
 
-            unsafe {
-                trampoline(p1);
-            }
+    defer_init(info_span!("hook"), Deferred::BeforeStep(init_fn), {
+        let pre_hooks_applied = pre_hooks_applied.clone();
+        let mapping = mapping.clone();
+
+        move || {
+            let result =
+                hook_device_manager(exe, mapping).and_then(|_| hook_mount_ebl(attach_config, exe));
 
-            if result.is_ok()
-                && let Err(e) = hook_ebl_utility(exe, &class_map, mapping.clone())
+            match result {
+                Ok(()) => pre_hooks_applied.store(true, Ordering::Relaxed),
+                Err(e) => error!("error" = &*e, "failed apply pre-hooks"),
+            }
+        }
+    })?;
+
+    defer_init(
+        info_span!("hook"),
+        Deferred::AfterStep(init_fn),
+        move || {
+            if pre_hooks_applied.load(Ordering::Relaxed)
+                && let Err(e) = hook_ebl_utility(exe, &class_map, mapping)
             {
                 error!("error" = &*e, "failed to apply post-hooks");
             }
-        })
-        .install()?;
+        },
+    )?;
 
     Ok(())
 }
@@ -142,11 +163,12 @@ fn hook_ebl_utility(
             let mut device_manager = DlDeviceManager::lock(device_manager);
 
             let expanded = unsafe { device_manager.expand_path(path.as_wide()) };
+            let expanded = OsString::from_wide(&expanded);
 
-            if mapping
-                .vfs_override(OsString::from_wide(&expanded))
-                .is_some()
-            {
+            let mapped_override = mapping.vfs_override(&expanded);
+            asset_trace::record_vfs_path(&expanded, mapped_override);
+
+            if mapped_override.is_some() {
                 return None;
             }
 
@@ -176,10 +198,15 @@ fn hook_device_manager(
         move |path: &DlUtf16String| {
             let path = path.get().ok()?;
             let expanded = DlDeviceManager::lock(device_manager).expand_path(path.as_slice());
+            let expanded = OsString::from_wide(&expanded);
+
+            let mapped_override = mapping.vfs_override(&expanded);
+            asset_trace::record_vfs_path(&expanded, mapped_override);
 
-            let mapped_override = mapping.vfs_override(OsString::from_wide(&expanded))?;
+            let mapped_override = mapped_override?;
 
             info!("override" = %mapped_override);
+            event::count_override(mapped_override.as_str_lossy());
 
             let mut path = path.clone();
 
@@ -297,159 +324,169 @@ fn hook_mount_ebl(attach_config: Arc<AttachConfig>, exe: Executable) -> Result<(
         let bhd_path = OsString::from_wide(&expanded);
 
         // Parse the public RSA key to know the block size for decryption.
-        let pub_key_size = key_size_from_pem_c_str(key_c_str)?;
+        let key = public_key_from_pem_c_str(key_c_str)?;
 
         // Read the original file for hashing to use as the cached file name.
-        let original = Arc::new(std::fs::read(&bhd_path)?);
+        let original = std::fs::read(&bhd_path)?;
+
+        let cached_bhd_path = cache_path.as_ref().join(bhd5::cache_file_name(&original));
+
+        // Decompress the cached header before mounting anything, so the device the game creates
+        // for the stub is never left without its contents.
+        if let Some(buf) = read_cached_bhd(&cached_bhd_path, &allocator)? {
+            let (new_mounts, mut device) = match mount_stub_ebl(
+                &mut device_manager,
+                &cache_path,
+                &original,
+                &key,
+                &trampoline,
+            ) {
+                Ok(mounted) => mounted,
+                Err(e) => {
+                    unsafe {
+                        allocator.dealloc(buf.as_mut_ptr(), bhd_layout(buf.len())?);
+                    }
 
-        // When changing storage or compression defaults, don't forget to change the seed.
-        let hash = std::thread::spawn({
-            let original = original.clone();
-            move || xxh3::xxh3_128_with_seed(&original, 1)
-        });
+                    return Err(e);
+                }
+            };
 
-        // Write a temporary file with the size of a single block and have the
-        // game decrypt it, which creates an EblFileDevice and lets us read
-        // the original file size.
-        let mut stub_file = NamedTempFile::new_in(cache_path.as_ref())?;
+            unsafe {
+                device
+                    .as_mut()
+                    .as_mut_bhd_holder_unchecked()
+                    .assign_bhd_contents(buf.as_mut_ptr().cast());
+            }
 
-        stub_file.write_all(&original[..Ord::min(pub_key_size, original.len())])?;
+            VFS_MOUNTS.lock().unwrap().append(new_mounts);
+
+            return Ok(());
+        }
 
+        // Let the game decrypt the original, before caching it.
         let snap = device_manager.snapshot()?;
 
-        invoke_trampoline(&trampoline, &stub_file)?;
+        invoke_trampoline(&trampoline, &bhd_path)?;
 
         let new_mounts = device_manager.extract_new(snap);
 
-        let mut device = new_mounts
+        let device = new_mounts
             .devices()
             .next()
             .ok_or_eyre("no devices were added")?;
 
-        let original_len = unsafe {
+        let decrypted = unsafe {
             device
                 .as_ref()
                 .as_bhd_holder_unchecked()
                 .bhd_header()
-                .map(Bhd5Header::file_size)
+                .ok_or_eyre("BHD header is null")?
+                .as_slice()
+                .to_vec()
         };
 
-        let hash = hash.join().expect("thread panicked");
+        VFS_MOUNTS.lock().unwrap().append(new_mounts);
 
-        let cached_bhd_path = cache_path.as_ref().join(format!("{hash:032x?}.bhd.zz"));
+        // Successfully mounted the ebl, compress and write the cache off the game's boot path
+        // and do not report subsequent caching errors.
+        std::thread::spawn(move || {
+            if let Err(e) = bhd5::cache::write(&cached_bhd_path, &decrypted) {
+                debug!(error = %e, path = %cached_bhd_path.display(), "failed to cache BHD");
+            }
+        });
 
-        // Create or open the cache file.
-        let mut cached = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .truncate(false)
-            .open(cached_bhd_path)?;
+        Ok(())
+    }
 
-        let cached_len = {
-            let mut cached_len_bytes = [0; 4];
-            cached
-                .read_exact(&mut cached_len_bytes)
-                .map(|_| u32::from_le_bytes(cached_len_bytes))
-                .ok()
+    /// Decompresses the cached header into a buffer allocated with the game's own allocator, as
+    /// it will be freed with it later. Returns `None` if the cache is missing, outdated or
+    /// corrupted.
+    fn read_cached_bhd(
+        cached_bhd_path: &Path,
+        allocator: &DlStdAllocator,
+    ) -> Result<Option<&'static mut [u8]>, eyre::Error> {
+        let Ok(cached) = std::fs::read(cached_bhd_path) else {
+            return Ok(None);
         };
 
-        // If the length of the cached file is zero the file has just been created.
-        // If the length does not match there was an error writing the cache or a hash collision.
-        if let Some(cached_len) = cached_len
-            && let Some(original_len) = original_len
-            && cached_len == original_len
-        {
-            let cached_len = cached_len as usize;
-
-            let mut compressed = vec![];
-            cached.read_to_end(&mut compressed)?;
-
-            // Opened a cached decrypted file, read, decompress and assign its contents.
-            // Use the game's own allocator as it will be freed with it later.
-            let buf = unsafe {
-                let ptr = NonNull::new(
-                    allocator.alloc(Layout::from_size_align_unchecked(cached_len, 4096)),
-                )
-                .ok_or_eyre("failed to allocate buffer for cached file")?;
-
-                slice::from_raw_parts_mut(ptr.as_ptr(), cached_len)
-            };
-
-            let mut state = InflateState::new_boxed(DataFormat::Raw);
-            inflate(&mut state, &compressed, buf, MZFlush::Finish)
-                .status
-                .map_err(|e| eyre!("`miniz_oxide::inflate` failed with status={e:?}"))?;
-
-            unsafe {
-                device
-                    .as_mut()
-                    .as_mut_bhd_holder_unchecked()
-                    .assign_bhd_contents(buf.as_mut_ptr().cast());
+        let size = match bhd5::cache::CacheHeader::parse(&cached) {
+            Ok(header) if header.size != 0 => header.size,
+            Ok(_) => {
+                warn!(path = %cached_bhd_path.display(), "discarding empty cached BHD");
+                return Ok(None);
             }
+            Err(e) => {
+                warn!(error = %e, path = %cached_bhd_path.display(), "discarding cached BHD");
+                return Ok(None);
+            }
+        };
 
-            VFS_MOUNTS.lock().unwrap().append(new_mounts);
-        } else {
-            // Clear the file and let the game decrypt the original, before caching it.
-            cached.set_len(0)?;
-
-            let snap = device_manager.snapshot()?;
-
-            invoke_trampoline(&trampoline, &bhd_path)?;
+        let layout = bhd_layout(size)?;
 
-            let new_mounts = device_manager.extract_new(snap);
+        let buf = unsafe {
+            let ptr = NonNull::new(allocator.alloc(layout))
+                .ok_or_eyre("failed to allocate buffer for cached BHD")?;
 
-            let device = new_mounts
-                .devices()
-                .next()
-                .ok_or_eyre("no devices were added")?;
+            slice::from_raw_parts_mut(ptr.as_ptr(), size)
+        };
 
-            let header = unsafe {
-                device
-                    .as_ref()
-                    .as_bhd_holder_unchecked()
-                    .bhd_header()
-                    .ok_or_eyre("BHD header is null")?
-            };
+        match bhd5::cache::decode_into(&cached, buf) {
+            Ok(()) => Ok(Some(buf)),
+            Err(e) => {
+                unsafe {
+                    allocator.dealloc(buf.as_mut_ptr(), layout);
+                }
 
-            VFS_MOUNTS.lock().unwrap().append(new_mounts);
+                warn!(error = %e, path = %cached_bhd_path.display(), "discarding cached BHD");
 
-            // Successfully mounted the ebl, do not report subsequent caching errors.
-            let _ = cached
-                .write_all(&header.file_size().to_le_bytes())
-                .and_then(|_| cached.write_all(&compress_to_vec(header.as_slice(), 7)))
-                .and_then(|_| cached.flush());
+                Ok(None)
+            }
         }
+    }
 
-        Ok(())
+    fn bhd_layout(size: usize) -> Result<Layout, eyre::Error> {
+        Ok(Layout::from_size_align(size, 4096)?)
     }
 
-    fn key_size_from_pem_c_str(key_c_str: PCSTR) -> Result<usize, eyre::Error> {
-        let key_str = unsafe { str::from_utf8(key_c_str.as_bytes())? };
+    /// Has the game decrypt a temporary file with the size of a single block, which creates an
+    /// EblFileDevice for the archive without decrypting the whole header.
+    fn mount_stub_ebl<P, F>(
+        device_manager: &mut DlDeviceManagerGuard,
+        cache_path: P,
+        original: &[u8],
+        key: &RsaPublicKey,
+        trampoline: &F,
+    ) -> Result<(VfsMounts, NonNull<DlDevice>), eyre::Error>
+    where
+        P: AsRef<Path>,
+        F: Fn(PCWSTR) -> bool,
+    {
+        let mut stub_file = NamedTempFile::new_in(cache_path.as_ref())?;
 
-        let mut lines = key_str.lines();
+        let block = original
+            .get(..key.size())
+            .ok_or_eyre("BHD is smaller than a single block")?;
 
-        let _ = lines
-            .next()
-            .filter(|str| *str == "-----BEGIN RSA PUBLIC KEY-----")
-            .ok_or_eyre("malformed PEM")?;
+        stub_file.write_all(block)?;
+
+        let snap = device_manager.snapshot()?;
 
-        let _ = lines
-            .next_back()
-            .filter(|str| *str == "-----END RSA PUBLIC KEY-----")
-            .ok_or_eyre("malformed PEM")?;
+        invoke_trampoline(trampoline, &stub_file)?;
 
-        let is_base64char = |c: &char| c.is_ascii_alphanumeric() | ['+', '/', '='].contains(c);
+        let new_mounts = device_manager.extract_new(snap);
 
-        let mut normalized = String::with_capacity(key_str.len());
-        normalized.extend(lines.flat_map(|line| line.chars().filter(is_base64char)));
+        let device = new_mounts
+            .devices()
+            .next()
+            .ok_or_eyre("no devices were added")?;
 
-        let der = BASE64_STANDARD.decode(&normalized)?;
-        let pub_key = pkcs1::RsaPublicKey::from_der(&der)?;
+        Ok((new_mounts, device))
+    }
 
-        let size = pub_key.modulus.len().try_into()?;
+    fn public_key_from_pem_c_str(key_c_str: PCSTR) -> Result<RsaPublicKey, eyre::Error> {
+        let key_str = unsafe { str::from_utf8(key_c_str.as_bytes())? };
 
-        Ok(size)
+        Ok(RsaPublicKey::from_pem(key_str)?)
     }
 
     fn invoke_trampoline<S, F>(trampoline: &F, bhd_path: S) -> Result<(), eyre::Error>
@@ -525,14 +562,36 @@ fn try_hook_wwise(
     let wwise_open_file =
         find_wwise_open_file(exe, class_map).ok_or_eyre("WwiseOpenFileByName not found")?;
 
+    // Steam isn't initialized yet, so the game's language is looked up when the first sound is
+    // opened.
+    let prefixes = OnceLock::new();
+
     ModHost::get_attached()
         .hook(wwise_open_file)
         .with_span(info_span!("hook"))
         .with_closure(move |p1, path, open_mode, p4, p5, p6, trampoline| {
             let path_string = unsafe { path.to_string().unwrap() };
 
-            if let Some(mapped_override) = wwise::find_override(&mapping, &path_string) {
+            let prefixes = prefixes.get_or_init(|| {
+                let language = steam_game_language();
+                debug!(?language, "sound language");
+
+                SoundPrefixes::new(
+                    &mapping,
+                    language.as_deref().and_then(wwise::sound_language),
+                )
+            });
+
+            let mapped_override = wwise::find_override(&mapping, prefixes, &path_string);
+
+            asset_trace::record_vfs_path(
+                format!("sd/{}", wwise::strip_prefix(&path_string)),
+                mapped_override,
+            );
+
+            if let Some(mapped_override) = mapped_override {
                 info!("override" = %mapped_override);
+                event::count_override(mapped_override.as_str_lossy());
 
                 // Force lookup to wwise's ordinary read (from disk) mode instead of the EBL read.
                 unsafe {
@@ -556,6 +615,35 @@ fn try_hook_wwise(
     Ok(())
 }
 
+/// The language the game is played in, as set in Steam, e.g. `english`.
+fn steam_game_language() -> Option<String> {
+    unsafe {
+        let steam_dll = GetModuleHandleW(w!("steam_api64.dll")).ok()?;
+
+        let steam_apps = GetProcAddress(steam_dll, s!("SteamAPI_SteamApps_v008"))?;
+        let get_language =
+            GetProcAddress(steam_dll, s!("SteamAPI_ISteamApps_GetCurrentGameLanguage"))?;
+
+        let steam_apps: unsafe extern "C" fn() -> *mut c_void = mem::transmute(steam_apps);
+        let get_language: unsafe extern "C" fn(*mut c_void) -> *const c_char =
+            mem::transmute(get_language);
+
+        let steam_apps = steam_apps();
+
+        if steam_apps.is_null() {
+            return None;
+        }
+
+        let language = get_language(steam_apps);
+
+        if language.is_null() {
+            return None;
+        }
+
+        Some(CStr::from_ptr(language).to_string_lossy().into_owned())
+    }
+}
+
 fn locate_device_manager(
     exe: Executable,
 ) -> Result<NonNull<DlDeviceManager>, dl_device::FindError> {
@@ -0,0 +1,117 @@
+//! Records the assets requested by the game when it's launched with `--trace-assets`, along with
+//! the files served instead of them, and writes them to a file when the game exits.
+
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use me3_launcher_attach_protocol::{trace::AssetTraceRecorder, AttachConfig};
+use me3_mod_host_assets::mapping::{VfsOverride, VfsOverrideMapping};
+use tracing::{info, warn};
+
+static TRACER: OnceLock<AssetTracer> = OnceLock::new();
+
+struct AssetTracer {
+    path: PathBuf,
+    mapping: Arc<VfsOverrideMapping>,
+    started: Instant,
+    recorder: Mutex<Option<AssetTraceRecorder>>,
+}
+
+/// Starts recording asset requests if the attach config asks for a trace.
+pub fn attach(attach_config: &AttachConfig, mapping: Arc<VfsOverrideMapping>) {
+    let Some(path) = &attach_config.asset_trace else {
+        return;
+    };
+
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or_default();
+
+    let mut recorder = AssetTraceRecorder::new(started_at);
+
+    // Package files are recorded up front, so files the game never requests show up too.
+    for (vfs_path, vfs_override) in mapping.package_files() {
+        recorder.add_file(
+            &vfs_path,
+            vfs_override.as_str_lossy(),
+            vfs_override.package(),
+        );
+    }
+
+    let tracer = AssetTracer {
+        path: path.clone(),
+        mapping,
+        started: Instant::now(),
+        recorder: Mutex::new(Some(recorder)),
+    };
+
+    if TRACER.set(tracer).is_ok() {
+        info!(?path, "tracing asset requests");
+    }
+}
+
+/// Records a request for a virtual path, as looked up by [`VfsOverrideMapping::vfs_override`].
+pub fn record_vfs_path<S: AsRef<OsStr>>(path: S, served_by: Option<&VfsOverride>) {
+    if let Some(tracer) = TRACER.get() {
+        tracer.record(tracer.mapping.vfs_lookup_path(path), served_by);
+    }
+}
+
+/// Records a request for a file on disk, as looked up by [`VfsOverrideMapping::disk_override`].
+///
+/// Files outside of the game directory are ignored.
+pub fn record_disk_path<S: AsRef<OsStr>>(path: S, served_by: Option<&VfsOverride>) {
+    if let Some(tracer) = TRACER.get()
+        && let Some(path) = tracer.mapping.disk_lookup_path(path)
+    {
+        tracer.record(path, served_by);
+    }
+}
+
+/// Writes the recorded asset requests, if they're traced. Later requests aren't recorded.
+pub fn write() {
+    let Some(tracer) = TRACER.get() else {
+        return;
+    };
+
+    // Taken before writing, since the write goes through the hooks doing the recording.
+    let Some(recorder) = tracer.recorder.lock().unwrap().take() else {
+        return;
+    };
+
+    let trace = recorder.finish();
+
+    let result = trace
+        .to_bytes()
+        .map_err(io::Error::other)
+        .and_then(|bytes| fs::write(&tracer.path, bytes));
+
+    match result {
+        Ok(()) => info!(
+            path = ?tracer.path,
+            requests = trace.accesses.len(),
+            "wrote asset trace"
+        ),
+        Err(error) => warn!(%error, path = ?tracer.path, "failed to write asset trace"),
+    }
+}
+
+impl AssetTracer {
+    fn record(&self, path: String, served_by: Option<&VfsOverride>) {
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+
+        if let Some(recorder) = &mut *self.recorder.lock().unwrap() {
+            recorder.record(
+                &path,
+                served_by.map(|vfs_override| (vfs_override.as_str_lossy(), vfs_override.package())),
+                elapsed_ms,
+            );
+        }
+    }
+}
@@ -1,9 +1,14 @@
 use std::{
+    collections::{hash_map::Entry, HashMap},
     mem,
-    sync::{LazyLock, Mutex, Once},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock, Mutex, Once,
+    },
 };
 
 use eyre::{eyre, OptionExt};
+use me3_binary_analysis::fd4_step::Fd4StepFunction;
 use tracing::{info, instrument, Level, Span};
 use windows::{
     core::{s, w},
@@ -15,6 +20,10 @@ use crate::host::ModHost;
 pub enum Deferred {
     BeforeMain,
     AfterMain,
+    /// Before the first run of an FD4 step function.
+    BeforeStep(Fd4StepFunction),
+    /// After the first run of an FD4 step function returns.
+    AfterStep(Fd4StepFunction),
 }
 
 type DeferredOnce = Option<Vec<Box<dyn FnOnce() + Send>>>;
@@ -22,6 +31,18 @@ type DeferredOnce = Option<Vec<Box<dyn FnOnce() + Send>>>;
 static DEFERRED_BEFORE_MAIN: Mutex<DeferredOnce> = Mutex::new(Some(Vec::new()));
 static DEFERRED_AFTER_MAIN: Mutex<DeferredOnce> = Mutex::new(Some(Vec::new()));
 
+/// Closures deferred until before and after a step function, keyed by its address.
+static DEFERRED_STEPS: LazyLock<Mutex<HashMap<usize, Arc<DeferredStep>>>> =
+    LazyLock::new(Default::default);
+
+/// Closures deferred until before (`[0]`) and after (`[1]`) the first run of a step function.
+///
+/// Steps run every frame, so once their closures have run the hook only checks `ran`.
+struct DeferredStep {
+    deferred: Mutex<[DeferredOnce; 2]>,
+    ran: [AtomicBool; 2],
+}
+
 /// Defers execution of a closure.
 ///
 /// Trying to defer a closure's execution after the point of initialization returns an error.
@@ -30,12 +51,14 @@ pub fn defer_init<F>(span: Span, until: Deferred, f: F) -> Result<(), eyre::Erro
 where
     F: FnOnce() + Send + 'static,
 {
-    let deferred = match until {
+    let f = Box::new(move || span.in_scope(f));
+
+    let mut deferred = match until {
         Deferred::BeforeMain => {
             static SCHEDULED_AFTER_ARXAN: Once = Once::new();
             SCHEDULED_AFTER_ARXAN.call_once(schedule_after_arxan);
 
-            &DEFERRED_BEFORE_MAIN
+            DEFERRED_BEFORE_MAIN.lock().unwrap()
         }
         Deferred::AfterMain => {
             static HOOKED_STEAM_INIT: LazyLock<Result<(), eyre::Error>> =
@@ -43,18 +66,80 @@ where
 
             HOOKED_STEAM_INIT.as_ref().map_err(|e| eyre!(e))?;
 
-            &DEFERRED_AFTER_MAIN
+            DEFERRED_AFTER_MAIN.lock().unwrap()
         }
+        Deferred::BeforeStep(step) => return defer_step(step, false, f),
+        Deferred::AfterStep(step) => return defer_step(step, true, f),
     };
 
     deferred
-        .lock()
-        .unwrap()
         .as_mut()
-        .map(|deferred| deferred.push(Box::new(move || span.in_scope(f))))
+        .map(|deferred| deferred.push(f))
         .ok_or_eyre("tried to defer function after init")
 }
 
+fn defer_step(
+    step: Fd4StepFunction,
+    after: bool,
+    f: Box<dyn FnOnce() + Send>,
+) -> Result<(), eyre::Error> {
+    let mut steps = DEFERRED_STEPS.lock().unwrap();
+
+    let deferred_step = match steps.entry(step as usize) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let mut deferred = [Some(Vec::new()), Some(Vec::new())];
+            deferred[after as usize] = Some(vec![f]);
+
+            let deferred_step = Arc::new(DeferredStep {
+                deferred: Mutex::new(deferred),
+                ran: [AtomicBool::new(false), AtomicBool::new(false)],
+            });
+
+            // The closure is added before the hook is installed, so the step can't run first.
+            hook_step(step, deferred_step.clone())?;
+            entry.insert(deferred_step);
+
+            return Ok(());
+        }
+    };
+
+    deferred_step.deferred.lock().unwrap()[after as usize]
+        .as_mut()
+        .map(|deferred| deferred.push(f))
+        .ok_or_eyre("tried to defer function after step")
+}
+
+#[instrument(skip_all)]
+fn hook_step(step: Fd4StepFunction, deferred_step: Arc<DeferredStep>) -> Result<(), eyre::Error> {
+    let run_deferred = move |after: bool| {
+        let ran = &deferred_step.ran[after as usize];
+
+        if ran.load(Ordering::Acquire) || ran.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let deferred = deferred_step.deferred.lock().unwrap()[after as usize].take();
+
+        deferred.into_iter().flatten().for_each(|f| f());
+    };
+
+    ModHost::get_attached()
+        .hook(step)
+        .with_closure(move |p1, trampoline| {
+            run_deferred(false);
+
+            unsafe {
+                trampoline(p1);
+            }
+
+            run_deferred(true);
+        })
+        .install()?;
+
+    Ok(())
+}
+
 #[instrument]
 fn hook_steam_init() -> Result<(), eyre::Error> {
     ModHost::get_attached()
@@ -0,0 +1,49 @@
+use std::{collections::BTreeMap, mem, sync::Mutex, thread, time::Duration};
+
+use me3_launcher_attach_protocol::event::HostEvent;
+
+/// How often the overrides served since the last report are sent to the launcher.
+const OVERRIDE_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+static OVERRIDES_SERVED: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// Reports a [`HostEvent`] to the launcher, which forwards it to the CLI.
+///
+/// Events are best-effort: if the bridge is unavailable or full the event is dropped.
+pub fn emit(event: HostEvent) {
+    if let Ok(bridge) = me3_ipc::bridge::to_parent() {
+        let _ = bridge.send_event(event);
+    }
+}
+
+/// Counts a file served from a package.
+///
+/// This is called from the file open hooks, so rather than sending an event per file open the
+/// counts are sent as [`HostEvent::AssetOverride`]s by [`report_overrides`].
+pub fn count_override(path: &str) {
+    let mut overrides = OVERRIDES_SERVED.lock().unwrap();
+
+    match overrides.get_mut(path) {
+        Some(count) => *count += 1,
+        None => {
+            overrides.insert(path.to_owned(), 1);
+        }
+    }
+}
+
+/// Sends the counts of the overrides served since the last report.
+pub fn report_overrides() {
+    let overrides = mem::take(&mut *OVERRIDES_SERVED.lock().unwrap());
+
+    for (path, count) in overrides {
+        emit(HostEvent::AssetOverride { path, count });
+    }
+}
+
+/// Calls [`report_overrides`] periodically for the rest of the game session.
+pub fn spawn_override_reporter() {
+    thread::spawn(|| loop {
+        thread::sleep(OVERRIDE_REPORT_INTERVAL);
+        report_overrides();
+    });
+}
@@ -0,0 +1,325 @@
+//! Host side of the extension API from `me3-ext`, handed to natives that export `me3_ext_init`.
+
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr, CString},
+    mem,
+    path::Path,
+    sync::{Arc, LazyLock, Mutex, OnceLock},
+};
+
+use libloading::{Library, Symbol};
+use me3_ext::{
+    Me3Api, Me3ExtInit, Me3Game, Me3GameInfo, Me3LogLevel, Me3Region, Me3Result, ME3_API_VERSION,
+    ME3_EXT_INIT_SYMBOL,
+};
+use me3_mod_host_assets::mapping::VfsOverrideMapping;
+use me3_mod_protocol::Game;
+use tracing::{
+    callsite::{self, Callsite, Identifier},
+    field::{self, FieldSet, Value},
+    info, info_span,
+    metadata::{Kind, Metadata},
+    subscriber::Interest,
+    warn, Event, Level,
+};
+
+use crate::{
+    detour::DetourError,
+    executable::{Region, Version},
+    host::ModHost,
+};
+
+static API: Me3Api = Me3Api {
+    log,
+    game_info,
+    add_file_override,
+    hook,
+    override_game_property,
+};
+
+static GAME_INFO: OnceLock<GameInfo> = OnceLock::new();
+
+static OVERRIDE_MAPPING: OnceLock<Arc<VfsOverrideMapping>> = OnceLock::new();
+
+/// File overrides added by natives that were loaded before the override mapping was created.
+static PENDING_OVERRIDES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+struct GameInfo {
+    game: Game,
+    version: Option<(Version, CString)>,
+}
+
+/// Records the game and executable version reported to natives.
+pub fn attach_game(game: Game, version: Option<Version>) {
+    let version = version.map(|version| {
+        let product = CString::new(version.product.as_bytes()).unwrap_or_default();
+        (version, product)
+    });
+
+    let _ = GAME_INFO.set(GameInfo { game, version });
+}
+
+/// Applies file overrides to `mapping`, including any that were added before it existed.
+pub fn attach_override_mapping(mapping: Arc<VfsOverrideMapping>) {
+    let mut pending = PENDING_OVERRIDES.lock().unwrap();
+
+    for (vfs_path, disk_path) in pending.drain(..) {
+        if let Err(e) = mapping.add_runtime_override(&vfs_path, &disk_path) {
+            warn!("error" = %e, vfs_path, disk_path, "failed to add file override");
+        }
+    }
+
+    let _ = OVERRIDE_MAPPING.set(mapping);
+}
+
+/// Hands the extension API to the `me3_ext_init` export of a native, if it has one.
+pub fn init_native(path: &Path, module: &Library) {
+    let ext_init: Option<Symbol<Me3ExtInit>> =
+        unsafe { module.get(ME3_EXT_INIT_SYMBOL.as_bytes()).ok() };
+
+    let Some(ext_init) = ext_init else {
+        return;
+    };
+
+    if unsafe { ext_init(&API, ME3_API_VERSION) } {
+        info!(
+            ?path,
+            version = ME3_API_VERSION,
+            "initialized extension API"
+        );
+    } else {
+        warn!(?path, "native failed to initialize the extension API");
+    }
+}
+
+unsafe extern "C" fn log(level: Me3LogLevel, target: *const c_char, message: *const c_char) {
+    if let (Some(target), Some(message)) = (unsafe { c_str(target) }, unsafe { c_str(message) }) {
+        log_as(level, target, message);
+    }
+}
+
+/// Logs `message` under a `target` that is only known at runtime, such as the name of a native.
+fn log_as(level: Me3LogLevel, target: &str, message: &str) {
+    let callsite = ExtCallsite::get(target, level);
+    let metadata = callsite.metadata();
+
+    tracing::dispatcher::get_default(|dispatch| {
+        if !dispatch.enabled(metadata) {
+            return;
+        }
+
+        let fields = metadata.fields();
+        let message_field = fields
+            .field("message")
+            .expect("callsite has a message field");
+        let message = field::display(message);
+
+        dispatch.event(&Event::new(
+            metadata,
+            &fields.value_set(&[(&message_field, Some(&message as &dyn Value))]),
+        ));
+    });
+}
+
+unsafe extern "C" fn game_info(info: *mut Me3GameInfo) -> Me3Result {
+    let Some(info) = (unsafe { info.as_mut() }) else {
+        return Me3Result::InvalidArgument;
+    };
+
+    let Some(GameInfo {
+        game,
+        version: Some((version, product)),
+    }) = GAME_INFO.get()
+    else {
+        warn!("a native requested the game version, but it is unknown");
+        return Me3Result::Failed;
+    };
+
+    *info = Me3GameInfo {
+        game: match game {
+            Game::DarkSouls3 => Me3Game::DarkSouls3,
+            Game::Sekiro => Me3Game::Sekiro,
+            Game::EldenRing => Me3Game::EldenRing,
+            Game::ArmoredCore6 => Me3Game::ArmoredCore6,
+            Game::Nightreign => Me3Game::Nightreign,
+        },
+        product: product.as_ptr(),
+        major: version.major,
+        minor: version.minor,
+        patch: version.patch,
+        build: version.build,
+        region: match version.region {
+            Region::Worldwide => Me3Region::Worldwide,
+            Region::Japan => Me3Region::Japan,
+        },
+    };
+
+    Me3Result::Ok
+}
+
+unsafe extern "C" fn add_file_override(
+    vfs_path: *const c_char,
+    disk_path: *const c_char,
+) -> Me3Result {
+    let (Some(vfs_path), Some(disk_path)) =
+        (unsafe { c_str(vfs_path) }, unsafe { c_str(disk_path) })
+    else {
+        return Me3Result::InvalidArgument;
+    };
+
+    // Holding the lock keeps the mapping from being attached between the check and the push.
+    let mut pending = PENDING_OVERRIDES.lock().unwrap();
+
+    let Some(mapping) = OVERRIDE_MAPPING.get() else {
+        pending.push((vfs_path.to_owned(), disk_path.to_owned()));
+        return Me3Result::Ok;
+    };
+
+    match mapping.add_runtime_override(vfs_path, disk_path) {
+        Ok(()) => {
+            info!(vfs_path, disk_path, "native added file override");
+            Me3Result::Ok
+        }
+        Err(e) => {
+            warn!("error" = %e, vfs_path, disk_path, "failed to add file override");
+            Me3Result::Failed
+        }
+    }
+}
+
+unsafe extern "C" fn hook(
+    target: *const c_void,
+    detour: *const c_void,
+    trampoline: *mut *const c_void,
+) -> Me3Result {
+    if target.is_null() || detour.is_null() || trampoline.is_null() {
+        return Me3Result::InvalidArgument;
+    }
+
+    match unsafe { install_hook(target, detour) } {
+        Ok(original) => {
+            unsafe { *trampoline = original };
+            Me3Result::Ok
+        }
+        Err(e) => {
+            warn!("error" = %e, ?target, "failed to install hook for native");
+            Me3Result::Failed
+        }
+    }
+}
+
+/// Detours `target` to `detour` on behalf of a native, returning a pointer to the original.
+///
+/// The hook is removed before the native whose module contains `detour` is unloaded.
+///
+/// # Safety
+///
+/// Both pointers must be non-null and point to functions with the same signature.
+unsafe fn install_hook(
+    target: *const c_void,
+    detour: *const c_void,
+) -> Result<*const c_void, DetourError> {
+    type RawFn = unsafe extern "C" fn();
+
+    let detour_ptr = detour;
+
+    let (target, detour) = unsafe {
+        (
+            mem::transmute::<*const c_void, RawFn>(target),
+            mem::transmute::<*const c_void, RawFn>(detour),
+        )
+    };
+
+    let host = ModHost::get_attached();
+
+    let installed = host
+        .hook(target)
+        .with(detour)
+        .with_span(info_span!("ext_hook"))
+        .install()?;
+
+    host.track_ext_hook(detour_ptr, unsafe { mem::transmute(installed.clone()) });
+
+    Ok(installed.trampoline() as *const c_void)
+}
+
+unsafe extern "C" fn override_game_property(property: *const c_char, state: bool) -> Me3Result {
+    let Some(property) = (unsafe { c_str(property) }) else {
+        return Me3Result::InvalidArgument;
+    };
+
+    ModHost::get_attached().override_game_property(property, state);
+
+    info!(property, state, "native overrode game property");
+
+    Me3Result::Ok
+}
+
+unsafe fn c_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// A tracing callsite for messages logged by natives with a given target and level.
+///
+/// Targets aren't known until natives log, so callsites are leaked as they're first used instead
+/// of being declared statically like the ones created by `tracing` macros.
+struct ExtCallsite {
+    metadata: OnceLock<Metadata<'static>>,
+}
+
+impl ExtCallsite {
+    fn get(target: &str, level: Me3LogLevel) -> &'static Self {
+        static CALLSITES: LazyLock<Mutex<HashMap<(String, Me3LogLevel), &'static ExtCallsite>>> =
+            LazyLock::new(Default::default);
+
+        let mut callsites = CALLSITES.lock().unwrap();
+
+        if let Some(callsite) = callsites.get(&(target.to_owned(), level)) {
+            return callsite;
+        }
+
+        let callsite: &'static Self = Box::leak(Box::new(Self {
+            metadata: OnceLock::new(),
+        }));
+
+        let tracing_level = match level {
+            Me3LogLevel::Error => Level::ERROR,
+            Me3LogLevel::Warn => Level::WARN,
+            Me3LogLevel::Info => Level::INFO,
+            Me3LogLevel::Debug => Level::DEBUG,
+            Me3LogLevel::Trace => Level::TRACE,
+        };
+
+        let _ = callsite.metadata.set(Metadata::new(
+            "native",
+            Box::leak(target.into()),
+            tracing_level,
+            None,
+            None,
+            None,
+            FieldSet::new(&["message"], Identifier(callsite)),
+            Kind::EVENT,
+        ));
+
+        callsite::register(callsite);
+
+        callsites.insert((target.to_owned(), level), callsite);
+
+        callsite
+    }
+}
+
+impl Callsite for ExtCallsite {
+    fn set_interest(&self, _: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'static> {
+        self.metadata
+            .get()
+            .expect("callsite metadata is set before registering")
+    }
+}
@@ -21,7 +21,7 @@ use windows::{
     },
 };
 
-use crate::host::ModHost;
+use crate::{asset_trace, event, host::ModHost};
 
 #[instrument(name = "filesystem", skip_all)]
 pub fn attach_override(mapping: Arc<VfsOverrideMapping>) -> Result<(), eyre::Error> {
@@ -92,12 +92,16 @@ fn hook_create_file(kb: HMODULE, mapping: Arc<VfsOverrideMapping>) -> Result<(),
                     return trampoline(p1, p2, p3, p4, p5, p6, p7);
                 }
 
-                if let Ok(path) = p1.to_string()
-                    && let Some(mapped_override) = mapping.disk_override(path)
-                {
-                    info!("override" = %mapped_override);
+                if let Ok(path) = p1.to_string() {
+                    let mapped_override = mapping.disk_override(&path);
+                    asset_trace::record_disk_path(&path, mapped_override);
 
-                    return trampoline(mapped_override.into(), p2, p3, p4, p5, p6, p7);
+                    if let Some(mapped_override) = mapped_override {
+                        info!("override" = %mapped_override);
+                        event::count_override(mapped_override.as_str_lossy());
+
+                        return trampoline(mapped_override.into(), p2, p3, p4, p5, p6, p7);
+                    }
                 }
 
                 trampoline(p1, p2, p3, p4, p5, p6, p7)
@@ -118,8 +122,12 @@ fn hook_create_file(kb: HMODULE, mapping: Arc<VfsOverrideMapping>) -> Result<(),
 
                 let path = OsString::from_wide(p1.as_wide());
 
-                if let Some(mapped_override) = mapping.disk_override(path) {
+                let mapped_override = mapping.disk_override(&path);
+                asset_trace::record_disk_path(&path, mapped_override);
+
+                if let Some(mapped_override) = mapped_override {
                     info!("override" = %mapped_override);
+                    event::count_override(mapped_override.as_str_lossy());
 
                     return trampoline(mapped_override.into(), p2, p3, p4, p5, p6, p7);
                 }
@@ -142,8 +150,12 @@ fn hook_create_file(kb: HMODULE, mapping: Arc<VfsOverrideMapping>) -> Result<(),
 
                 let path = OsString::from_wide(p1.as_wide());
 
-                if let Some(mapped_override) = mapping.disk_override(path) {
+                let mapped_override = mapping.disk_override(&path);
+                asset_trace::record_disk_path(&path, mapped_override);
+
+                if let Some(mapped_override) = mapped_override {
                     info!("override" = %mapped_override);
+                    event::count_override(mapped_override.as_str_lossy());
 
                     return trampoline(mapped_override.into(), p2, p3, p4, p5);
                 }
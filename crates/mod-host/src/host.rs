@@ -1,39 +1,110 @@
 use std::{
     collections::HashMap,
-    ffi::CString,
+    ffi::{c_char, c_void, CString},
     fmt::Debug,
     marker::Tuple,
-    panic,
+    mem, panic,
     path::Path,
-    sync::{Arc, Mutex, OnceLock},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
     time::Duration,
 };
 
 use closure_ffi::traits::FnPtr;
+use eyre::{bail, eyre};
 use libloading::{Library, Symbol};
-use me3_launcher_attach_protocol::AttachConfig;
-use me3_mod_protocol::{native::NativeInitializerCondition, Game, ModProfile};
+use me3_launcher_attach_protocol::{event::HostEvent, AttachConfig};
+use me3_mod_protocol::{
+    native::{Native, NativeInitializerCondition},
+    Game, ModProfile,
+};
 use retour::Function;
+use serde_json::{Map, Value};
 use tracing::{error, info, warn};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::HMODULE,
+        System::LibraryLoader::{
+            GetModuleHandleExW, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+            GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+        },
+    },
+};
 
-use self::hook::HookInstaller;
+use self::{hook::HookInstaller, shadow_copy::ShadowCopies};
 use crate::{
-    detour::UntypedDetour,
-    native::{ModEngineConnectorShim, ModEngineExtension, ModEngineInitializer},
+    detour::{DetourError, UntypedDetour},
+    event, ext,
+    native::{Me2Extension, ModEngineInitializer},
 };
 
 mod append;
 pub mod game_properties;
 pub mod hook;
+mod shadow_copy;
 
 static ATTACHED_INSTANCE: OnceLock<ModHost> = OnceLock::new();
 
+struct LoadedNative {
+    native: Native,
+    module: Library,
+    /// Base address of `module`, which the hooks installed through the extension API are matched
+    /// against.
+    base: usize,
+    me2_extension: Option<Me2Extension>,
+}
+
+/// A hook a native installed through the extension API, removed before the native is unloaded.
+struct ExtHook {
+    /// Base address of the module containing the detour.
+    module: usize,
+    detour: Arc<UntypedDetour>,
+}
+
+impl LoadedNative {
+    /// Detaches the Mod Engine 2 extension and calls the finalizer of this native.
+    ///
+    /// Returns the result of the finalizer, if there is one. The module stays loaded either way,
+    /// it's up to the caller to free or leak it.
+    fn finalize(&mut self, timeout: Duration) -> Option<Result<(), String>> {
+        if let Some(extension) = self.me2_extension.take() {
+            extension.detach();
+        }
+
+        let symbol = self.native.finalizer.as_ref()?;
+        let path = self.native.path.display().to_string();
+
+        let error = match call_finalizer(&self.module, symbol, timeout) {
+            Finalized::Ok => {
+                info!(path, symbol, "native finalized successfully");
+                return Some(Ok(()));
+            }
+            Finalized::Failed(e) => {
+                warn!(error = &*e, path, symbol, "native failed to finalize");
+                format!("{e:#}")
+            }
+            Finalized::TimedOut => {
+                warn!(path, symbol, ?timeout, "native finalizer timed out");
+                format!("{symbol} did not return within {timeout:?}")
+            }
+        };
+
+        Some(Err(error))
+    }
+}
+
 #[derive(Default)]
 pub struct ModHost {
     hooks: Mutex<Vec<Arc<UntypedDetour>>>,
-    native_modules: Mutex<Vec<Library>>,
+    ext_hooks: Mutex<Vec<ExtHook>>,
+    native_modules: Mutex<Vec<LoadedNative>>,
     profiles: Vec<ModProfile>,
     property_overrides: Mutex<HashMap<Vec<u16>, bool>>,
+    shadow_copies: Option<ShadowCopies>,
     pub disable_arxan: bool,
 }
 
@@ -43,6 +114,7 @@ impl Debug for ModHost {
             .field("hooks", &self.hooks)
             .field("profiles", &self.profiles)
             .field("property_overrides", &self.property_overrides)
+            .field("shadow_copies", &self.shadow_copies)
             .finish()
     }
 }
@@ -53,21 +125,148 @@ impl ModHost {
     pub fn new(attach_config: &AttachConfig) -> Self {
         // Unconditionally disable Arxan in Dark Souls 3.
         let disable_arxan = attach_config.disable_arxan || attach_config.game == Game::DarkSouls3;
+
+        let shadow_copies = match (attach_config.shadow_copy_natives, &attach_config.cache_path) {
+            (true, Some(cache_path)) => Some(ShadowCopies::new(cache_path)),
+            (true, None) => {
+                warn!("natives won't be shadow copied, because there is no cache directory");
+                None
+            }
+            (false, _) => None,
+        };
+
         Self {
             disable_arxan,
+            shadow_copies,
             ..Default::default()
         }
     }
 
-    pub fn load_native(
-        &self,
-        path: &Path,
-        condition: &Option<NativeInitializerCondition>,
-    ) -> eyre::Result<()> {
-        let result = panic::catch_unwind(|| {
-            let module = unsafe { libloading::Library::new(path)? };
+    pub fn load_native(&self, native: &Native) -> eyre::Result<()> {
+        let path = &*native.path;
+
+        let failed = |error: String| {
+            event::emit(HostEvent::NativeFailed {
+                path: path.display().to_string(),
+                error,
+                optional: native.optional,
+            })
+        };
+
+        match self.load_module(native) {
+            Err(exception) => {
+                warn!("an error occurred while loading {path:?}, it may not work as expected");
+                failed("panicked while loading".to_owned());
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                failed(format!("{e:#}"));
+                Err(e)
+            }
+            Ok(Ok(loaded)) => {
+                event::emit(HostEvent::NativeLoaded {
+                    path: path.display().to_string(),
+                });
+                self.native_modules.lock().unwrap().push(loaded);
+                Ok(())
+            }
+        }
+    }
+
+    /// Finalizes and unloads the native with the given id, then loads a fresh copy of it in its
+    /// place.
+    ///
+    /// Only possible when natives are shadow copied, since the originals are locked otherwise.
+    pub fn reload_native(&self, id: &str, timeout: Duration) -> eyre::Result<()> {
+        if self.shadow_copies.is_none() {
+            bail!("natives can only be reloaded when shadow_copy_natives is enabled");
+        }
+
+        let (index, mut loaded) = {
+            let mut natives = self.native_modules.lock().unwrap();
+
+            let index = natives
+                .iter()
+                .position(|loaded| loaded.native.id() == id)
+                .ok_or_else(|| eyre!("no native with id {id} is loaded"))?;
+
+            (index, natives.remove(index))
+        };
+
+        let native = loaded.native.clone();
+        let path = native.path.display().to_string();
+
+        if native.finalizer.is_none() {
+            warn!(
+                path,
+                "native has no finalizer, hooks and threads it started will outlive it"
+            );
+        }
+
+        let result = match loaded.finalize(timeout) {
+            Some(Err(e)) => {
+                // Its code may still be running.
+                mem::forget(loaded);
+                Err(eyre!("{e}, the old module was left loaded"))
+            }
+            // Frees the old module, so the new copy isn't just a reference to it.
+            _ => match self.unload(loaded) {
+                Err(e) => Err(eyre!(
+                    "failed to remove its hooks: {e}, the old module was left loaded"
+                )),
+                Ok(()) => match self.load_module(&native) {
+                    Err(_) => Err(eyre!("panicked while loading")),
+                    Ok(result) => result,
+                },
+            },
+        };
+
+        match result {
+            Ok(loaded) => {
+                let mut natives = self.native_modules.lock().unwrap();
+                let index = index.min(natives.len());
+                natives.insert(index, loaded);
+
+                info!(path, "native reloaded");
+                event::emit(HostEvent::NativeReloaded { path, error: None });
+
+                Ok(())
+            }
+            Err(e) => {
+                warn!(error = &*e, path, "failed to reload native");
+                event::emit(HostEvent::NativeReloaded {
+                    path,
+                    error: Some(format!("{e:#}")),
+                });
+
+                Err(e)
+            }
+        }
+    }
+
+    fn load_module(&self, native: &Native) -> thread::Result<eyre::Result<LoadedNative>> {
+        let path = &*native.path;
+        let shadow_copies = self.shadow_copies.as_ref();
+
+        panic::catch_unwind(|| {
+            let module = match shadow_copies {
+                Some(shadow_copies) => unsafe { Library::new(shadow_copies.copy(path)?)? },
+                None => unsafe { Library::new(path)? },
+            };
+
+            let handle = libloading::os::windows::Library::from(module).into_raw();
+            let module =
+                Library::from(unsafe { libloading::os::windows::Library::from_raw(handle) });
+
+            if let Some(config) = &native.config {
+                configure_native(path, &module, config)?;
+            }
+
+            ext::init_native(path, &module);
 
-            match &condition {
+            let mut me2_extension = None;
+
+            match &native.initializer {
                 Some(NativeInitializerCondition::Delay { ms }) => {
                     std::thread::sleep(Duration::from_millis(*ms as u64))
                 }
@@ -86,27 +285,132 @@ impl ModHost {
                     let me2_initializer: Option<Symbol<ModEngineInitializer>> =
                         unsafe { module.get(b"modengine_ext_init\0").ok() };
 
-                    let mut extension_ptr: *mut ModEngineExtension = std::ptr::null_mut();
                     if let Some(initializer) = me2_initializer {
-                        unsafe { initializer(&ModEngineConnectorShim, &mut extension_ptr) };
+                        me2_extension = unsafe { Me2Extension::attach(*initializer, native)? };
 
-                        info!(?path, "loaded native with me2 compatibility shim");
+                        info!(
+                            ?path,
+                            id = me2_extension.as_ref().map(Me2Extension::id),
+                            "loaded native with me2 compatibility shim"
+                        );
                     }
                 }
             }
 
-            Ok(module)
-        });
+            Ok(LoadedNative {
+                native: native.clone(),
+                module,
+                base: handle as usize,
+                me2_extension,
+            })
+        })
+    }
 
-        match result {
-            Err(exception) => {
-                warn!("an error occurred while loading {path:?}, it may not work as expected");
-                Ok(())
+    /// Calls the finalizers of the loaded natives in reverse load order, detaching Mod Engine 2
+    /// extensions first.
+    ///
+    /// Natives with a finalizer that fails or doesn't return within `timeout` are leaked, since
+    /// their code may still be running. The others stay loaded until [`ModHost::unload_natives`].
+    pub fn finalize_natives(&self, timeout: Duration) {
+        let mut natives = mem::take(&mut *self.native_modules.lock().unwrap());
+        let mut finalized = Vec::with_capacity(natives.len());
+
+        while let Some(mut loaded) = natives.pop() {
+            let path = loaded.native.path.display().to_string();
+
+            match loaded.finalize(timeout) {
+                Some(Err(error)) => {
+                    mem::forget(loaded);
+
+                    event::emit(HostEvent::NativeFinalized {
+                        path,
+                        error: Some(error),
+                    });
+                }
+                result => {
+                    if result.is_some() {
+                        event::emit(HostEvent::NativeFinalized { path, error: None });
+                    }
+
+                    finalized.push(loaded);
+                }
+            }
+        }
+
+        finalized.reverse();
+        *self.native_modules.lock().unwrap() = finalized;
+    }
+
+    /// Unloads the natives that [`ModHost::finalize_natives`] didn't leak, in reverse load order.
+    pub fn unload_natives(&self) {
+        let natives = mem::take(&mut *self.native_modules.lock().unwrap());
+
+        for loaded in natives.into_iter().rev() {
+            let path = loaded.native.path.display().to_string();
+
+            match self.unload(loaded) {
+                Ok(()) => info!(path, "native unloaded"),
+                Err(e) => warn!(error = %e, path, "failed to remove hooks, native left loaded"),
+            }
+        }
+    }
+
+    /// Removes the hooks a native installed through the extension API and frees its module.
+    ///
+    /// The module is leaked instead if any of its hooks can't be disabled, since the game could
+    /// still call into it.
+    fn unload(&self, loaded: LoadedNative) -> Result<(), DetourError> {
+        let removed = {
+            let mut ext_hooks = self.ext_hooks.lock().unwrap();
+
+            let (removed, kept) = mem::take(&mut *ext_hooks)
+                .into_iter()
+                .partition::<Vec<_>, _>(|hook| hook.module == loaded.base);
+
+            *ext_hooks = kept;
+            removed
+        };
+
+        for hook in removed {
+            if let Err(e) = unsafe { hook.detour.disable() } {
+                mem::forget(loaded);
+                return Err(e);
             }
-            Ok(result) => result.map(|module| {
-                self.native_modules.lock().unwrap().push(module);
-            }),
+
+            self.hooks
+                .lock()
+                .unwrap()
+                .retain(|other| !Arc::ptr_eq(other, &hook.detour));
+        }
+
+        drop(loaded);
+
+        Ok(())
+    }
+
+    /// Records a hook installed through the extension API, so it's removed before the native
+    /// whose module contains `detour` is unloaded.
+    pub(crate) fn track_ext_hook(&self, detour: *const c_void, hook: Arc<UntypedDetour>) {
+        let mut module = HMODULE::default();
+
+        let found = unsafe {
+            GetModuleHandleExW(
+                GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS
+                    | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                PCWSTR(detour.cast()),
+                &mut module,
+            )
+        };
+
+        if let Err(e) = found {
+            warn!(error = %e, ?detour, "detour isn't in a module, the hook can't be removed");
+            return;
         }
+
+        self.ext_hooks.lock().unwrap().push(ExtHook {
+            module: module.0 as usize,
+            detour: hook,
+        });
     }
 
     #[inline]
@@ -134,3 +438,67 @@ impl ModHost {
             .insert(property.as_ref().encode_utf16().collect(), state);
     }
 }
+
+/// Passes the `config` table of a native to its `me3_configure` export as JSON.
+fn configure_native(
+    path: &Path,
+    module: &Library,
+    config: &Map<String, Value>,
+) -> eyre::Result<()> {
+    let configure: Option<Symbol<unsafe extern "C" fn(*const c_char, usize)>> =
+        unsafe { module.get(b"me3_configure\0").ok() };
+
+    let Some(configure) = configure else {
+        warn!(
+            ?path,
+            "native has a config, but doesn't export me3_configure"
+        );
+        return Ok(());
+    };
+
+    let json = CString::new(serde_json::to_string(config)?)?;
+
+    unsafe { configure(json.as_ptr(), json.as_bytes().len()) };
+
+    info!(?path, "passed config to native");
+
+    Ok(())
+}
+
+/// How a call to a native's finalizer ended.
+enum Finalized {
+    Ok,
+    Failed(eyre::Error),
+    TimedOut,
+}
+
+fn call_finalizer(module: &Library, symbol: &str, timeout: Duration) -> Finalized {
+    let finalizer = CString::new(symbol.as_bytes())
+        .map_err(eyre::Error::from)
+        .and_then(|sym_name| unsafe {
+            let finalizer: Symbol<unsafe extern "C" fn() -> bool> =
+                module.get(sym_name.as_bytes_with_nul())?;
+
+            Ok(*finalizer)
+        });
+
+    let finalizer = match finalizer {
+        Ok(finalizer) => finalizer,
+        Err(e) => return Finalized::Failed(e),
+    };
+
+    // Run the finalizer on its own thread so a native that hangs can't hold up the game's exit.
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(unsafe { finalizer() });
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(true) => Finalized::Ok,
+        Ok(false) => Finalized::Failed(eyre::eyre!("{symbol} returned false")),
+        Err(RecvTimeoutError::Timeout) => Finalized::TimedOut,
+        Err(RecvTimeoutError::Disconnected) => {
+            Finalized::Failed(eyre::eyre!("{symbol} did not return"))
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, mem, slice, sync::Arc};
 
 use eyre::OptionExt;
+use me3_binary_analysis::patterns;
 use me3_launcher_attach_protocol::AttachConfig;
 use me3_mod_host_types::string::DlUtf16String;
 use me3_mod_protocol::Game;
@@ -76,23 +77,10 @@ fn bool_property_getter(
     exe: Executable,
 ) -> Result<GetBoolProperty, eyre::Error> {
     // Matches callsites for the boolean DLSystemProperty getter.
-    //
-    // In Dark Souls 3, Sekiro and ER, the getter takes in a reference to a DLString,
-    // in later games, it was changed to a nul terminated UTF-16 string pointer.
-    //
-    // The patterns match loading the pointer to the `std::map` containing the property names
-    // and their values loaded in RCX, the queried property name in RDX and true/false in R8B
-    // as the default value in the case of the property missing from the map.
     let function_call_re_str = match attach_config.game {
-        Game::DarkSouls3 => {
-            r"(?s-u)(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x88\x05.{4}\x48\x83\x7c\x24\x48\x08\x72.)|(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x0f\xb6\xd8\x48\x83\x7c\x24\x48\x08\x72.)"
-        }
-        Game::Sekiro | Game::EldenRing => {
-            r"(?s-u)(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x88\x05.{4}\x48\x83\x7c\x24\x50\x08\x72.)|(?:\x48\x8d\x54\x24\x30\x48\x8b\x0d.{4}\xe8(.{4})\x0f\xb6\xd8\x48\x83\x7c\x24\x50\x08\x72.)"
-        }
-        Game::ArmoredCore6 | Game::Nightreign => {
-            r"(?s-u)(?:(?:\x45\x33\xc0)|(?:\x41\xb0\x01))\x48\x8d\x15.{4}\x48\x8b\x0d.{4}\xe8(.{4})"
-        }
+        Game::DarkSouls3 => patterns::BOOL_PROPERTY_GETTER_DS3.regex,
+        Game::Sekiro | Game::EldenRing => patterns::BOOL_PROPERTY_GETTER_SEKIRO.regex,
+        Game::ArmoredCore6 | Game::Nightreign => patterns::BOOL_PROPERTY_GETTER.regex,
     };
 
     let function_call_re = Regex::new(function_call_re_str).unwrap();
@@ -4,11 +4,13 @@ use closure_ffi::{
     traits::{FnPtr, FnThunk},
     BareFn,
 };
+use me3_launcher_attach_protocol::event::HostEvent;
 use retour::Function;
 use tracing::Span;
 
 use crate::{
     detour::{install_detour, Detour, DetourError, UntypedDetour},
+    event,
     host::append::{Append, WithAppended},
 };
 
@@ -25,6 +27,7 @@ where
     on_install: Option<Box<dyn FnOnce(Arc<UntypedDetour>)>>,
     source: Option<HookSource<F>>,
     span: Span,
+    span_name: Option<&'static str>,
     target: F,
 }
 
@@ -38,6 +41,7 @@ where
             on_install: None,
             source: None,
             span: Span::none(),
+            span_name: None,
             target,
         }
     }
@@ -78,11 +82,36 @@ where
     }
 
     pub fn with_span(&mut self, span: Span) -> &mut Self {
+        self.span_name = span.metadata().map(|m| m.name());
         self.span = span;
         self
     }
 
     pub fn install(&mut self) -> Result<Arc<Detour<F>>, DetourError> {
+        let result = self.try_install();
+
+        let span_name = self
+            .span_name
+            .or_else(|| Span::current().metadata().map(|m| m.name()));
+
+        let name = format!(
+            "{} ({:?})",
+            span_name.unwrap_or("unknown"),
+            self.target.to_ptr()
+        );
+
+        match &result {
+            Ok(_) => event::emit(HostEvent::HookInstalled { name }),
+            Err(e) => event::emit(HostEvent::HookFailed {
+                name,
+                error: e.to_string(),
+            }),
+        }
+
+        result
+    }
+
+    fn try_install(&mut self) -> Result<Arc<Detour<F>>, DetourError> {
         let mut uninit_trampoline = None;
 
         let hook = match self.source.take().expect("no hook source") {
@@ -0,0 +1,149 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use tracing::{debug, info};
+use windows::Win32::{
+    Foundation::{CloseHandle, STILL_ACTIVE},
+    System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+};
+
+/// Copies natives into a per-session cache directory, so the originals aren't locked while the
+/// game is running.
+///
+/// Every copy goes into a new numbered subdirectory, since a previous copy of the same native may
+/// still be loaded.
+#[derive(Debug)]
+pub struct ShadowCopies {
+    dir: PathBuf,
+    generation: AtomicU32,
+}
+
+impl ShadowCopies {
+    /// Uses `<cache_path>/natives/<process id>`, removing the copies left behind by sessions whose
+    /// process has exited.
+    pub fn new(cache_path: &Path) -> Self {
+        let sessions_dir = cache_path.join("natives");
+        let session = std::process::id();
+
+        if let Ok(entries) = fs::read_dir(&sessions_dir) {
+            for entry in entries.flatten() {
+                let Some(pid) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.parse().ok())
+                else {
+                    continue;
+                };
+
+                if pid != session && !is_running(pid) {
+                    let _ = fs::remove_dir_all(entry.path());
+                }
+            }
+        }
+
+        Self {
+            dir: sessions_dir.join(session.to_string()),
+            generation: AtomicU32::new(0),
+        }
+    }
+
+    /// Copies the native at `path`, and its PDB if there is one next to it, returning the path of
+    /// the copied native.
+    pub fn copy(&self, path: &Path) -> io::Result<PathBuf> {
+        let file_name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "native has no file name")
+        })?;
+
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed);
+        let dir = self.dir.join(generation.to_string());
+
+        fs::create_dir_all(&dir)?;
+
+        let copy = dir.join(file_name);
+        fs::copy(path, &copy)?;
+
+        let pdb = path.with_extension("pdb");
+
+        if pdb.is_file() {
+            fs::copy(&pdb, copy.with_extension("pdb"))?;
+            debug!(?pdb, "copied native PDB");
+        }
+
+        info!(?path, ?copy, "copied native");
+
+        Ok(copy)
+    }
+}
+
+/// Is the process with this id still running?
+///
+/// Process ids are reused, so this may keep the copies of an exited session around until the
+/// unrelated process that took over its id exits too.
+fn is_running(pid: u32) -> bool {
+    let Ok(process) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) })
+    else {
+        return false;
+    };
+
+    let mut exit_code = 0;
+    let result = unsafe { GetExitCodeProcess(process, &mut exit_code) };
+
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+
+    result.is_ok() && exit_code == STILL_ACTIVE.0 as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs, io,
+        process::{Command, Stdio},
+    };
+
+    use super::ShadowCopies;
+
+    #[test]
+    fn copies_into_new_generations() -> io::Result<()> {
+        let cache_dir = tempfile::tempdir()?;
+        let mods_dir = tempfile::tempdir()?;
+
+        let stale_session = cache_dir.path().join("natives").join("0");
+        fs::create_dir_all(&stale_session)?;
+
+        let mut running = Command::new("cmd")
+            .args(["/c", "pause"])
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let running_session = cache_dir
+            .path()
+            .join("natives")
+            .join(running.id().to_string());
+        fs::create_dir_all(&running_session)?;
+
+        let native = mods_dir.path().join("mod.dll");
+        fs::write(&native, "v1")?;
+        fs::write(native.with_extension("pdb"), "pdb")?;
+
+        let shadow_copies = ShadowCopies::new(cache_dir.path());
+        running.kill()?;
+
+        assert!(!stale_session.exists());
+        assert!(running_session.exists());
+
+        let first = shadow_copies.copy(&native)?;
+        fs::write(&native, "v2")?;
+        let second = shadow_copies.copy(&native)?;
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first)?, "v1");
+        assert_eq!(fs::read_to_string(&second)?, "v2");
+        assert!(second.with_extension("pdb").is_file());
+
+        Ok(())
+    }
+}
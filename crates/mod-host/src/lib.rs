@@ -3,18 +3,31 @@
 #![feature(tuple_trait)]
 #![feature(unboxed_closures)]
 
-use std::sync::{Arc, Mutex, OnceLock};
+use std::{
+    env,
+    sync::{Arc, Mutex, OnceLock},
+};
 
-use eyre::OptionExt;
+use eyre::{eyre, OptionExt};
 use me3_binary_analysis::{fd4_step::Fd4StepTables, rtti};
 use me3_env::TelemetryVars;
 use me3_ipc::{
-    bridge::BridgeToParent,
+    bridge::{BridgeToParent, LogWriter, OverflowPolicy},
     message::MsgToChild,
     request::{Request, RequestId},
 };
-use me3_launcher_attach_protocol::{AttachConfig, AttachRequest, AttachResult, Attachment};
-use me3_mod_host_assets::mapping::VfsOverrideMapping;
+use me3_launcher_attach_protocol::{
+    event::HostEvent, AttachConfig, AttachRequest, AttachResult, Attachment, LogFilterError,
+    LogFilterRequest, LogFilterResult, ReloadNativeRequest, ReloadNativeResult,
+};
+use me3_mod_host_assets::{
+    bhd5::{Bhd5Format, CachedArchives},
+    conflict,
+    dcx::KrakenDecompressor,
+    mapping::{MergeReport, VfsOverrideMapping},
+    oodle::Oodle,
+};
+use me3_mod_protocol::native::{Native, NativeLoadStage};
 use me3_telemetry::TelemetryConfig;
 use tracing::{error, info, instrument, warn, Span};
 use windows::Win32::{
@@ -33,19 +46,27 @@ use crate::{
 
 mod alloc_hooks;
 mod asset_hooks;
+mod asset_trace;
 mod debugger;
 mod deferred;
 mod detour;
+mod event;
 mod executable;
+mod ext;
 mod filesystem;
 mod host;
 mod native;
 mod savefile;
+mod shutdown;
 mod skip_logos;
 
 static INSTANCE: OnceLock<usize> = OnceLock::new();
 static mut TELEMETRY_INSTANCE: OnceLock<me3_telemetry::Telemetry> = OnceLock::new();
 
+/// The console log writer, flushed on shutdown since the telemetry subscriber holding it is never
+/// dropped.
+static CONSOLE_LOG_WRITER: OnceLock<LogWriter> = OnceLock::new();
+
 fn me_attach(request: AttachRequest) -> AttachResult {
     if request.config.suspend {
         debugger::suspend_for_debugger();
@@ -54,6 +75,26 @@ fn me_attach(request: AttachRequest) -> AttachResult {
     on_attach(request)
 }
 
+fn me_set_log_filter(request: LogFilterRequest) -> LogFilterResult {
+    #[allow(static_mut_refs)]
+    let telemetry = unsafe { TELEMETRY_INSTANCE.get() }
+        .ok_or_else(|| LogFilterError("telemetry isn't installed yet".to_owned()))?;
+
+    telemetry.log_filter().set(&request.filter)?;
+
+    info!(filter = %request.filter, "changed log filter");
+
+    Ok(())
+}
+
+fn me_reload_native(request: ReloadNativeRequest) -> ReloadNativeResult {
+    info!(id = request.id, "reloading native");
+
+    ModHost::get_attached().reload_native(&request.id, shutdown::FINALIZER_TIMEOUT)?;
+
+    Ok(())
+}
+
 #[cfg(coverage)]
 #[unsafe(no_mangle)]
 #[allow(non_upper_case_globals)]
@@ -75,13 +116,28 @@ fn on_attach(request: AttachRequest) -> AttachResult {
 
     let bridge = me3_ipc::bridge::to_parent()?;
 
-    let console_writer = bridge.console_log_writer();
-    let file_writer = bridge.file_log_writer();
+    // Prefer losing console output over stalling the game when the launcher can't keep up, but
+    // keep everything meant for the log file.
+    let console_writer = bridge
+        .console_log_writer()
+        .with_overflow_policy(OverflowPolicy::DropOldest { backlog: 1024 });
+
+    let _ = CONSOLE_LOG_WRITER.set(console_writer.clone());
+
+    let file_writer = bridge
+        .file_log_writer()
+        .with_overflow_policy(OverflowPolicy::SpillToFile(
+            telemetry_vars.log_file_path.with_extension("overflow.log"),
+        ));
 
     let telemetry_config = TelemetryConfig::default()
         .enabled(telemetry_vars.enabled)
         .with_console_writer(move || console_writer.clone())
         .with_file_writer(move || file_writer.clone())
+        .with_file_format(telemetry_vars.log_format)
+        .with_process("host")
+        .with_trace_id(telemetry_vars.trace_id)
+        .with_log_filter(telemetry_vars.log_filter)
         .capture_panics(true);
 
     let telemetry_guard = me3_telemetry::install(telemetry_config);
@@ -95,13 +151,31 @@ fn on_attach(request: AttachRequest) -> AttachResult {
         // SAFETY: process is still suspended.
         let exe = unsafe { Executable::new() };
 
-        match exe.version() {
+        let version = exe.version();
+
+        match &version {
             Ok(ver) => info!("Attaching to {ver}"),
             Err(e) => warn!("error" = %e, "could not detect game version"),
         }
 
+        ext::attach_game(attach_config.game, version.ok());
+
         ModHost::new(&attach_config).attach();
 
+        load_natives(
+            attach_config
+                .early_natives
+                .iter()
+                .filter(|native| native.load_stage() == NativeLoadStage::BeforeArxan),
+        )?;
+
+        if let Err(e) = shutdown::attach_override() {
+            warn!(
+                "error" = &*e,
+                "natives won't be finalized when the game exits"
+            );
+        }
+
         dearxan(&attach_config)?;
 
         skip_logos::attach_override(attach_config.clone(), exe)?;
@@ -113,13 +187,58 @@ fn on_attach(request: AttachRequest) -> AttachResult {
         }
 
         let mut override_mapping = VfsOverrideMapping::new()?;
+
+        if let Some(cache_path) = &attach_config.cache_path {
+            override_mapping.use_scan_index(cache_path)?;
+        }
+
         override_mapping.scan_directories(attach_config.packages.iter())?;
+
+        if override_mapping.has_mergeable_overrides() {
+            match &attach_config.cache_path {
+                Some(cache_path) => {
+                    // The game may be started from any working directory, but it always loads
+                    // its files relative to the executable.
+                    let game_exe = env::current_exe()?;
+                    let game_dir = game_exe
+                        .parent()
+                        .ok_or_eyre("game executable has no parent directory")?;
+                    let oodle = Oodle::load(game_dir);
+
+                    // WEM patches are applied to soundbanks read through the boot boost cache.
+                    let archives = CachedArchives::load(
+                        game_dir,
+                        cache_path,
+                        Bhd5Format::for_game(attach_config.game),
+                    );
+
+                    let reports = override_mapping.merge_overrides(
+                        cache_path,
+                        Some(game_dir),
+                        archives.as_ref().ok(),
+                        oodle.as_ref().map(|oodle| oodle as &dyn KrakenDecompressor),
+                    )?;
+
+                    log_merge_reports(&reports);
+                }
+                None => {
+                    warn!("can't merge files overridden by several packages without a cache dir")
+                }
+            }
+        }
+
         savefile::attach_override(&attach_config, &mut override_mapping)?;
 
         let override_mapping = Arc::new(override_mapping);
 
+        asset_trace::attach(&attach_config, override_mapping.clone());
+
+        ext::attach_override_mapping(override_mapping.clone());
+
         filesystem::attach_override(override_mapping.clone())?;
 
+        event::spawn_override_reporter();
+
         info!("Host successfully attached");
 
         let before_main_result = Arc::new(Mutex::new(None));
@@ -139,9 +258,13 @@ fn on_attach(request: AttachRequest) -> AttachResult {
                     .ok_or_eyre("`before_game_main` did not run?")?
             });
 
-            if let Err(e) = result {
-                error!("error" = &*e, "deferred attach failed!")
+            if let Err(e) = &result {
+                error!("error" = &**e, "deferred attach failed!")
             }
+
+            event::emit(HostEvent::AttachFinished {
+                error: result.err().map(|e| format!("{e:#}")),
+            });
         })?;
 
         info!("Deferred me3 attach");
@@ -157,11 +280,12 @@ fn before_game_main(attach_config: Arc<AttachConfig>, exe: Executable) -> Result
         alloc_hooks::hook_system_allocator(&attach_config, exe)?;
     }
 
-    for native in &attach_config.early_natives {
-        ModHost::get_attached().load_native(&native.path, &native.initializer)?;
-    }
-
-    Ok(())
+    load_natives(
+        attach_config
+            .early_natives
+            .iter()
+            .filter(|native| native.load_stage() == NativeLoadStage::BeforeMain),
+    )
 }
 
 fn after_game_main<R: FnOnce() -> Result<(), eyre::Error>>(
@@ -186,61 +310,124 @@ fn after_game_main<R: FnOnce() -> Result<(), eyre::Error>>(
         override_mapping.clone(),
     )?;
 
-    let first_delayed_offset = attach_config
+    let (after_main, after_steps): (Vec<_>, Vec<_>) = attach_config
         .natives
         .iter()
-        .enumerate()
-        .filter_map(|(idx, native)| native.initializer.is_some().then_some(idx))
-        .next()
-        .unwrap_or(attach_config.natives.len());
+        .partition(|native| native.load_stage() == NativeLoadStage::AfterMain);
 
-    let (immediate, delayed) = attach_config.natives.split_at(first_delayed_offset);
+    let first_delayed_offset = after_main
+        .iter()
+        .position(|native| native.initializer.is_some())
+        .unwrap_or(after_main.len());
 
-    for native in immediate {
-        if let Err(e) = ModHost::get_attached().load_native(&native.path, &native.initializer) {
-            warn!(
-                error = &*e,
-                path = %native.path.display(),
-                "failed to load native mod",
-            );
+    let (immediate, delayed) = after_main.split_at(first_delayed_offset);
 
-            if !native.optional {
-                return Err(e);
-            }
-        }
-    }
+    load_natives(immediate.iter().copied())?;
 
-    let delayed = delayed.to_vec();
+    let delayed = delayed.iter().copied().cloned().collect::<Vec<_>>();
     std::thread::spawn(move || {
-        for native in delayed {
-            if let Err(e) = ModHost::get_attached().load_native(&native.path, &native.initializer) {
-                warn!(
-                    error = &*e,
-                    path = %native.path.display(),
-                    "failed to load native mod",
-                );
-
-                if !native.optional {
-                    panic!("{:#?}", e);
-                }
-            }
+        if let Err(e) = load_natives(&delayed) {
+            panic!("{:#?}", e);
         }
     });
 
-    asset_hooks::attach_override(
-        attach_config,
+    let asset_hooks_result = asset_hooks::attach_override(
+        attach_config.clone(),
         exe,
         class_map,
         &step_tables,
         override_mapping,
-    )
-    .map_err(|e| {
+    );
+
+    for native in after_steps {
+        defer_native_load(native, &step_tables)?;
+    }
+
+    asset_hooks_result.map_err(|e| {
         e.wrap_err("failed to attach asset override hooks; no files will be overridden")
     })?;
 
     Ok(())
 }
 
+/// Logs the files merged from several packages, and the entries packages conflict over.
+fn log_merge_reports(reports: &[MergeReport]) {
+    for report in reports {
+        let path = &report.path;
+        let packages = report.packages.join(" + ");
+
+        match &report.merged {
+            Ok(merged_path) => info!(%path, ?merged_path, %packages, "merged overrides"),
+            Err(error) => warn!(
+                %path,
+                %error,
+                %packages,
+                "failed to merge overrides, using the last package overriding it"
+            ),
+        }
+
+        for group in conflict::group(&report.conflicts) {
+            warn!(
+                %path,
+                file = group.file,
+                ids = ?group.ids,
+                packages = %group.packages.join(", "),
+                "entries changed by several packages, using the last"
+            );
+        }
+    }
+}
+
+/// Loads natives in order, failing on the first non-optional native that doesn't load.
+fn load_natives<'a>(natives: impl IntoIterator<Item = &'a Native>) -> Result<(), eyre::Error> {
+    for native in natives {
+        if let Err(e) = ModHost::get_attached().load_native(native) {
+            warn!(
+                error = &*e,
+                path = %native.path.display(),
+                "failed to load native mod",
+            );
+
+            if !native.optional {
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Defers loading a native until the FD4 step of its load stage has run.
+fn defer_native_load(native: &Native, step_tables: &Fd4StepTables) -> Result<(), eyre::Error> {
+    let stage = native.load_stage();
+
+    let step = match &stage {
+        NativeLoadStage::AfterFileInit => asset_hooks::file_init_step(step_tables),
+        NativeLoadStage::AfterStep(name) => step_tables.by_name(name),
+        _ => return Err(eyre!("{stage} is not an FD4 step")),
+    };
+
+    let Some(step) = step else {
+        let e = eyre!("no FD4 step found for {stage}");
+
+        warn!(
+            error = &*e,
+            path = %native.path.display(),
+            "failed to schedule native mod",
+        );
+
+        return if native.optional { Ok(()) } else { Err(e) };
+    };
+
+    let native = native.clone();
+
+    defer_init(Span::current(), Deferred::AfterStep(step), move || {
+        if let Err(e) = load_natives([&native]) {
+            error!("error" = &*e, "native failed to load after {stage}");
+        }
+    })
+}
+
 fn dearxan(attach_config: &AttachConfig) -> Result<(), eyre::Error> {
     if !ModHost::get_attached().disable_arxan {
         return Ok(());
@@ -282,6 +469,8 @@ fn spawn_msg_thread() {
 fn fulfill_request(bridge: &BridgeToParent, (id, req): (RequestId, Request)) {
     let result = match req {
         Request::Attach(_) => bridge.fulfill((id, req), me_attach),
+        Request::LogFilter(_) => bridge.fulfill((id, req), me_set_log_filter),
+        Request::ReloadNative(_) => bridge.fulfill((id, req), me_reload_native),
     };
 
     if let Err(error) = result {
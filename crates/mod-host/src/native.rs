@@ -1,13 +1,173 @@
-use std::ffi::c_char;
+//! Lifecycle support for Mod Engine 2 extensions, which export `modengine_ext_init`.
+//!
+//! Mod Engine 2 hands extensions a connector object and receives an extension object in return,
+//! both C++ classes with a virtual function table as their first field. me3 drives the extension
+//! through `on_attach`, `on_detach` and `id`, but doesn't implement the connector: its functions
+//! pass MSVC standard library types, such as strings and shared pointers, that me3 can't produce.
+//! Calling any of them stops the game with an error naming the extension.
+
+use std::{
+    ffi::{c_char, c_void, CStr},
+    ptr::{self, NonNull},
+};
+
+use me3_launcher_attach_protocol::event::HostEvent;
+use me3_mod_protocol::{dependency::Dependency, native::Native};
+use tracing::{error, info, warn};
+
+use crate::event;
 
 pub type ModEngineInitializer =
-    unsafe extern "C" fn(&ModEngineConnectorShim, &mut *mut ModEngineExtension) -> bool;
+    unsafe extern "C" fn(*mut ModEngineConnectorShim, *mut *mut ModEngineExtension) -> bool;
+
+/// Number of connector virtual functions an extension can call without reading past the table.
+const CONNECTOR_VTABLE_LEN: usize = 32;
+
+/// The connector handed to a Mod Engine 2 extension, scoped to the native it was loaded from.
+#[repr(C)]
+pub struct ModEngineConnectorShim {
+    /// Only read by the extension.
+    #[allow(dead_code)]
+    vtable: &'static [UnsupportedFn; CONNECTOR_VTABLE_LEN],
+    id: String,
+}
+
+/// Stands in for every connector function. It never returns, so the caller never sees a result
+/// it could mistake for one of the C++ objects the real function would have returned.
+type UnsupportedFn = unsafe extern "C" fn(&ModEngineConnectorShim) -> !;
 
-pub struct ModEngineConnectorShim;
+macro_rules! connector_vtable {
+    ($($slot:literal)*) => {
+        [$(unsupported::<$slot>),*]
+    };
+}
 
+static CONNECTOR_VTABLE: [UnsupportedFn; CONNECTOR_VTABLE_LEN] = connector_vtable!(
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+);
+
+#[repr(C)]
 pub struct ModEngineExtension {
-    _destructor: extern "C" fn(),
-    _on_attach: extern "C" fn(),
-    _on_detach: extern "C" fn(),
-    _id: extern "C" fn() -> *const c_char,
+    vtable: &'static ModEngineExtensionVTable,
+}
+
+#[repr(C)]
+struct ModEngineExtensionVTable {
+    /// MSVC scalar deleting destructor, which frees the object when the low bit of `flags` is set.
+    destructor: unsafe extern "C" fn(*mut ModEngineExtension, flags: u32) -> *mut c_void,
+    on_attach: unsafe extern "C" fn(*mut ModEngineExtension),
+    on_detach: unsafe extern "C" fn(*mut ModEngineExtension),
+    id: unsafe extern "C" fn(*mut ModEngineExtension) -> *const c_char,
+}
+
+/// An attached Mod Engine 2 extension, detached and destroyed by [`Me2Extension::detach`].
+pub struct Me2Extension {
+    extension: NonNull<ModEngineExtension>,
+    _connector: Box<ModEngineConnectorShim>,
+    id: String,
+}
+
+// SAFETY: extensions are only ever used from one thread at a time.
+unsafe impl Send for Me2Extension {}
+
+impl Me2Extension {
+    /// Calls `modengine_ext_init` and attaches the extension it returns.
+    ///
+    /// # Safety
+    ///
+    /// `initializer` must be the `modengine_ext_init` export of the DLL loaded for `native`.
+    pub unsafe fn attach(
+        initializer: ModEngineInitializer,
+        native: &Native,
+    ) -> Result<Option<Self>, eyre::Error> {
+        let mut connector = Box::new(ModEngineConnectorShim {
+            vtable: &CONNECTOR_VTABLE,
+            id: native.id(),
+        });
+
+        let mut extension = ptr::null_mut();
+
+        if !unsafe { initializer(&mut *connector, &mut extension) } {
+            return Err(eyre::eyre!("modengine_ext_init returned false"));
+        }
+
+        let Some(extension) = NonNull::new(extension) else {
+            return Ok(None);
+        };
+
+        let id = unsafe { (extension.as_ref().vtable.id)(extension.as_ptr()) };
+        let id = if id.is_null() {
+            native.id()
+        } else {
+            unsafe { CStr::from_ptr(id) }.to_string_lossy().into_owned()
+        };
+
+        // Profiles are ordered before any DLL is loaded, so other natives can only depend on the
+        // id from the profile.
+        if id != native.id() {
+            warn!(
+                profile_id = native.id(),
+                id,
+                "Mod Engine 2 extension reports a different id than its profile, set `id` to depend on it by its own id"
+            );
+        }
+
+        connector.id.clone_from(&id);
+
+        unsafe { (extension.as_ref().vtable.on_attach)(extension.as_ptr()) };
+
+        Ok(Some(Self {
+            extension,
+            _connector: connector,
+            id,
+        }))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Calls `on_detach` and destroys the extension.
+    pub fn detach(self) {
+        let vtable = unsafe { self.extension.as_ref().vtable };
+
+        unsafe {
+            (vtable.on_detach)(self.extension.as_ptr());
+            (vtable.destructor)(self.extension.as_ptr(), 1);
+        }
+
+        info!(id = self.id, "detached Mod Engine 2 extension");
+    }
+}
+
+/// Reports a call to the connector function in `SLOT` of its virtual function table and stops
+/// the game, since there is no value it could safely return.
+unsafe extern "C" fn unsupported<const SLOT: usize>(connector: &ModEngineConnectorShim) -> ! {
+    const ME2_FATAL_ERROR: &str =
+        "Mod Engine 2 extension called a connector function, which me3 doesn't support";
+
+    error!(id = connector.id, slot = SLOT, ME2_FATAL_ERROR);
+
+    event::emit(HostEvent::Fatal {
+        error: format!("{ME2_FATAL_ERROR}: {} called function {SLOT}", connector.id),
+    });
+
+    panic!("{ME2_FATAL_ERROR}");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::CONNECTOR_VTABLE;
+
+    #[test]
+    fn connector_slots_are_distinct() {
+        let slots = CONNECTOR_VTABLE
+            .iter()
+            .map(|function| *function as usize)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(slots.len(), CONNECTOR_VTABLE.len());
+    }
 }
@@ -8,8 +8,8 @@ use std::{
 
 use eyre::{eyre, OptionExt};
 use from_singleton::FromSingleton;
-use me3_binary_analysis::{fd4_step::Fd4StepTables, pe};
-use me3_launcher_attach_protocol::AttachConfig;
+use me3_binary_analysis::{fd4_step::Fd4StepTables, patterns, pe};
+use me3_launcher_attach_protocol::{event::HostEvent, AttachConfig};
 use me3_mod_host_assets::mapping::VfsOverrideMapping;
 use me3_mod_host_types::{alloc::DlStdAllocator, vector::DlVector};
 use me3_mod_protocol::Game;
@@ -17,7 +17,7 @@ use pelite::pe::{Pe, Va};
 use regex::bytes::Regex;
 use tracing::{error, info, instrument, warn, Span};
 
-use crate::{executable::Executable, host::ModHost};
+use crate::{event, executable::Executable, host::ModHost};
 
 const SL_FATAL_ERROR: &str = "could not load alternative savefile location";
 
@@ -40,11 +40,21 @@ pub fn attach_override(
 
             // Panic on failure instead of loading the user's primary savefile instead
             // of the alternative one they requested.
-            override_savefile_path(current_path, &override_name)
-                .inspect_err(
-                    |e| error!("error" = &**e, "savefile" = ?override_name, SL_FATAL_ERROR),
-                )
-                .expect(SL_FATAL_ERROR)
+            let override_path = override_savefile_path(current_path, &override_name)
+                .inspect_err(|e| {
+                    error!("error" = &**e, "savefile" = ?override_name, SL_FATAL_ERROR);
+
+                    event::emit(HostEvent::Fatal {
+                        error: format!("{SL_FATAL_ERROR}: {e:#}"),
+                    });
+                })
+                .expect(SL_FATAL_ERROR);
+
+            event::emit(HostEvent::SavefileRedirected {
+                path: override_path.display().to_string(),
+            });
+
+            override_path
         })?;
     }
 
@@ -137,12 +147,7 @@ fn oversized_regulation_fix_for_sdt(exe: Executable) -> Result<(), eyre::Error>
         pe::section(exe, ".text").map_err(|e| eyre!("PE section \"{e}\" is missing"))?;
     let text = exe.get_section_bytes(text_section)?;
 
-    // matches:
-    // lea    rcx,[rsp+0x30]
-    // call   ??
-    // test   al,al
-    // je     ??
-    let call_re = Regex::new(r"(s?-u)\x48\x8d\x4c\x24.\xe8(.{4})\x84\xc0\x74.").unwrap();
+    let call_re = Regex::new(patterns::REGULATION_WRITE_CALL.regex).unwrap();
 
     // matches:
     // mov    rdx,rcx
@@ -0,0 +1,73 @@
+use std::{io::Write, mem, sync::Once, time::Duration};
+
+use eyre::OptionExt;
+use tracing::{info, instrument, Level};
+use windows::{
+    core::{s, w},
+    Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+};
+
+use crate::{asset_trace, event, host::ModHost};
+
+/// How long each native's finalizer may take before it's abandoned.
+pub const FINALIZER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for pending crash reports and traces to be sent.
+const TELEMETRY_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Runs [`shutdown`] when the game calls `ExitProcess`.
+#[instrument(name = "shutdown", skip_all)]
+pub fn attach_override() -> Result<(), eyre::Error> {
+    ModHost::get_attached()
+        .hook(exit_process_fn()?)
+        .with_closure(|exit_code, trampoline| {
+            shutdown();
+
+            unsafe { trampoline(exit_code) }
+        })
+        .install()?;
+
+    Ok(())
+}
+
+/// Finalizes natives in reverse load order, writes the asset trace, reports the overrides served
+/// since the last report, flushes console logs and telemetry, then unloads the natives except
+/// those whose finalizer failed or timed out.
+///
+/// Only the first call does anything.
+pub fn shutdown() {
+    static SHUTDOWN: Once = Once::new();
+
+    SHUTDOWN.call_once(|| {
+        info!("game is exiting, finalizing natives");
+
+        ModHost::get_attached().finalize_natives(FINALIZER_TIMEOUT);
+
+        asset_trace::write();
+
+        event::report_overrides();
+
+        if let Some(writer) = crate::CONSOLE_LOG_WRITER.get() {
+            let _ = writer.clone().flush();
+        }
+
+        #[allow(static_mut_refs)]
+        if let Some(telemetry) = unsafe { crate::TELEMETRY_INSTANCE.get() } {
+            telemetry.flush(TELEMETRY_FLUSH_TIMEOUT);
+        }
+
+        ModHost::get_attached().unload_natives();
+    });
+}
+
+#[instrument(ret(level = Level::DEBUG))]
+fn exit_process_fn() -> Result<unsafe extern "system" fn(u32), eyre::Error> {
+    unsafe {
+        let kernel32 = GetModuleHandleW(w!("kernel32.dll"))?;
+
+        let exit_process =
+            GetProcAddress(kernel32, s!("ExitProcess")).ok_or_eyre("ExitProcess not found")?;
+
+        Ok(mem::transmute(exit_process))
+    }
+}
@@ -1,7 +1,7 @@
 use std::{mem, ptr, sync::Arc};
 
 use eyre::{eyre, OptionExt};
-use me3_binary_analysis::pe;
+use me3_binary_analysis::{patterns, pe};
 use me3_launcher_attach_protocol::AttachConfig;
 use me3_mod_protocol::Game;
 use pelite::pe::Pe;
@@ -61,10 +61,7 @@ fn skip_fd4_logos(exe: Executable) -> Result<(), eyre::Error> {
     let rdata = exe.get_section_bytes(rdata)?;
 
     // "TitleStep::STEP_BeginLogo" as a UTF-16 string.
-    let step_name_re = Regex::new(
-        r"(?s-u)T\x00i\x00t\x00l\x00e\x00S\x00t\x00e\x00p\x00:\x00:\x00S\x00T\x00E\x00P\x00_\x00B\x00e\x00g\x00i\x00n\x00L\x00o\x00g\x00o\x00",
-    )
-    .unwrap();
+    let step_name_re = Regex::new(patterns::TITLE_STEP_NAME.regex).unwrap();
 
     // Find the string in the .rdata section.
     let step_name_ptr = step_name_re
@@ -103,20 +100,7 @@ fn skip_sprj_logos(exe: Executable) -> Result<(), eyre::Error> {
     let text = exe.get_section_bytes(text)?;
     let data = exe.get_section_bytes(data)?;
 
-    // Matches:
-    // rex push rbp
-    // push   rsi
-    // push   rdi
-    // lea    rbp,[rsp+??]
-    // sub    rsp,??
-    // mov    QWORD PTR [rbp+??],-2
-    // mov    QWORD PTR [rsp+??],rbx
-    // mov    rdi,rcx
-    // mov    BYTE PTR [rip+??],0x1
-    let step_re = Regex::new(
-        r"(?s-u)\x40\x55\x56\x57\x48\x8d\x6c\x24.\x48\x81\xec.{4}\x48\xc7\x45.\xfe\xff\xff\xff\x48\x89\x9c\x24.{4}\x48\x8b\xf9\xc6\x05.{4}\x01",
-    )
-    .unwrap();
+    let step_re = Regex::new(patterns::SPRJ_TITLE_STEP.regex).unwrap();
 
     // Find the function in the .text section.
     let step_ptr = step_re
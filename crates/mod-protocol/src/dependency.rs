@@ -278,6 +278,10 @@ mod tests {
             path: ModFile(PathBuf::from(id)),
             load_after,
             load_before,
+            root: None,
+            mount: None,
+            include: vec![],
+            exclude: vec![],
         }
     }
 
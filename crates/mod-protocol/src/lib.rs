@@ -115,6 +115,18 @@ impl ModProfile {
             ModProfile::V1(v1) => v1.patch_mem,
         }
     }
+
+    pub fn shadow_copy_natives(&self) -> Option<bool> {
+        match self {
+            ModProfile::V1(v1) => v1.shadow_copy_natives,
+        }
+    }
+
+    pub fn log_filter(&self) -> Option<String> {
+        match self {
+            ModProfile::V1(v1) => v1.log_filter.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
@@ -149,6 +161,14 @@ pub struct ModProfileV1 {
     /// Patch memory limits for supported games to improve mod stability.
     #[serde(default)]
     patch_mem: Option<bool>,
+
+    /// Filter for the logs of the launcher and mod host (e.g. "info,me3_mod_host=debug").
+    #[serde(default)]
+    log_filter: Option<String>,
+
+    /// Load copies of native DLLs, so they can be rebuilt and reloaded while the game is running.
+    #[serde(default)]
+    shadow_copy_natives: Option<bool>,
 }
 
 #[cfg(test)]
@@ -182,4 +202,29 @@ mod tests {
     fn singular_packages_name() {
         check("singular_package.me3");
     }
+
+    #[test]
+    fn native_load_stages() {
+        check("load_stages.me3");
+    }
+
+    #[test]
+    fn native_config() {
+        check("native_config.me3");
+    }
+
+    #[test]
+    fn me2_extension() {
+        check("me2_extension.me3");
+    }
+
+    #[test]
+    fn package_filters() {
+        check("package_filters.me3");
+    }
+
+    #[test]
+    fn package_mounts() {
+        check("package_mounts.me3");
+    }
 }
@@ -1,7 +1,15 @@
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf};
 
+use rkyv::{
+    option::ArchivedOption,
+    rancor::{Fallible, Source},
+    string::ArchivedString,
+    with::{ArchiveWith, DeserializeWith, SerializeWith},
+    Archive, SerializeUnsized,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::{
     dependency::{Dependency, Dependent},
@@ -27,12 +35,46 @@ fn on() -> bool {
     rkyv::Deserialize,
 )]
 pub enum NativeInitializerCondition {
+    /// Wait before loading the next native. Prefer a `load_stage` instead.
     #[serde(rename = "delay")]
     Delay { ms: usize },
     #[serde(rename = "function")]
     Function(String),
 }
 
+/// Point during the game's startup at which a native is loaded.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NativeLoadStage {
+    /// As soon as me3 attaches to the game, before Arxan code protection is neutralised.
+    BeforeArxan,
+
+    /// Before the game's main function runs.
+    BeforeMain,
+
+    /// After the game's main function initializes Steam.
+    #[default]
+    AfterMain,
+
+    /// After the game's file system is initialized (`CSFileStep::STEP_Init`).
+    AfterFileInit,
+
+    /// After the first run of the named FD4 step (e.g. "CSRegulationStep::STEP_Idle").
+    AfterStep(String),
+}
+
 #[derive(
     Clone,
     Debug,
@@ -47,6 +89,14 @@ pub struct Native {
     /// Path to the DLL. Can be relative to the mod profile.
     pub path: ModFile,
 
+    /// Name other natives refer to this one by in `load_before` and `load_after`. Defaults to the
+    /// file name of the DLL.
+    ///
+    /// Mod Engine 2 extensions should use the id they report, so dependencies written for Mod
+    /// Engine 2 keep working.
+    #[serde(default)]
+    pub id: Option<String>,
+
     /// If this native fails to load and this value is false, treat it as a critical error.
     #[serde(default = "off")]
     pub optional: bool,
@@ -64,17 +114,34 @@ pub struct Native {
     /// An optional symbol to be called after this native successfully loads.
     pub initializer: Option<NativeInitializerCondition>,
 
-    /// An optional symbol to be called when this native successfully is queued for unload.
+    /// An optional symbol to be called before this native is unloaded when the game exits.
     pub finalizer: Option<String>,
 
+    /// Load this native before the game's main function. Same as `load_stage = "before_main"`.
     #[serde(default = "off")]
     pub load_early: bool,
+
+    /// When during the game's startup this native is loaded.
+    #[serde(default)]
+    pub load_stage: Option<NativeLoadStage>,
+
+    /// Configuration passed as JSON to the `me3_configure` export of the native before it's
+    /// initialized.
+    #[serde(default)]
+    #[rkyv(with = AsJsonString)]
+    pub config: Option<Map<String, Value>>,
+
+    /// Path to a JSON schema for `config`. Can be relative to the mod profile. Defaults to a
+    /// `.schema.json` file next to the DLL.
+    #[serde(default)]
+    pub config_schema: Option<ModFile>,
 }
 
 impl Native {
     pub fn new<P: Into<PathBuf>>(path: P) -> Self {
         Self {
             path: ModFile(path.into()),
+            id: None,
             optional: false,
             enabled: true,
             load_after: vec![],
@@ -82,6 +149,63 @@ impl Native {
             initializer: None,
             finalizer: None,
             load_early: false,
+            load_stage: None,
+            config: None,
+            config_schema: None,
+        }
+    }
+
+    /// Path to the JSON schema `config` should be validated against, if one is known.
+    pub fn config_schema_path(&self) -> Option<PathBuf> {
+        match &self.config_schema {
+            Some(schema) => Some(schema.to_path_buf()),
+            None => Some(self.path.with_extension("schema.json")).filter(|path| path.is_file()),
+        }
+    }
+
+    /// The stage this native is loaded at, taking `load_early` into account.
+    pub fn load_stage(&self) -> NativeLoadStage {
+        match &self.load_stage {
+            Some(stage) => stage.clone(),
+            None if self.load_early => NativeLoadStage::BeforeMain,
+            None => NativeLoadStage::AfterMain,
+        }
+    }
+}
+
+impl NativeLoadStage {
+    /// Position of this stage in the game's startup.
+    ///
+    /// FD4 steps all run after the game's main function, in an order that isn't known ahead of
+    /// time, so they share a position.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Self::BeforeArxan => 0,
+            Self::BeforeMain => 1,
+            Self::AfterMain => 2,
+            Self::AfterFileInit | Self::AfterStep(_) => 3,
+        }
+    }
+
+    /// Is this stage known to start no later than `other`?
+    pub fn precedes(&self, other: &Self) -> bool {
+        self == other || self.rank() < other.rank()
+    }
+
+    /// Does this stage run before the game's main function?
+    pub fn is_early(&self) -> bool {
+        self.rank() < Self::AfterMain.rank()
+    }
+}
+
+impl fmt::Display for NativeLoadStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BeforeArxan => f.write_str("before_arxan"),
+            Self::BeforeMain => f.write_str("before_main"),
+            Self::AfterMain => f.write_str("after_main"),
+            Self::AfterFileInit => f.write_str("after_file_init"),
+            Self::AfterStep(step) => write!(f, "after_step({step})"),
         }
     }
 }
@@ -100,6 +224,10 @@ impl Dependency for Native {
     type UniqueId = String;
 
     fn id(&self) -> Self::UniqueId {
+        if let Some(id) = &self.id {
+            return id.clone();
+        }
+
         self.path
             .0
             .file_name()
@@ -115,3 +243,81 @@ impl Dependency for Native {
         &self.load_before
     }
 }
+
+/// Archives a JSON object as its serialized text.
+struct AsJsonString;
+
+fn to_json_string(field: &Option<Map<String, Value>>) -> Option<String> {
+    // Serializing a map with string keys is infallible.
+    field
+        .as_ref()
+        .map(|map| serde_json::to_string(map).expect("failed to serialize JSON object"))
+}
+
+impl ArchiveWith<Option<Map<String, Value>>> for AsJsonString {
+    type Archived = ArchivedOption<ArchivedString>;
+    type Resolver = <Option<String> as Archive>::Resolver;
+
+    fn resolve_with(
+        field: &Option<Map<String, Value>>,
+        resolver: Self::Resolver,
+        out: rkyv::Place<Self::Archived>,
+    ) {
+        Option::<String>::resolve(&to_json_string(field), resolver, out);
+    }
+}
+
+impl<S: Fallible + ?Sized> SerializeWith<Option<Map<String, Value>>, S> for AsJsonString
+where
+    S::Error: Source,
+    str: SerializeUnsized<S>,
+{
+    fn serialize_with(
+        field: &Option<Map<String, Value>>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, <S as Fallible>::Error> {
+        rkyv::Serialize::serialize(&to_json_string(field), serializer)
+    }
+}
+
+impl<D> DeserializeWith<ArchivedOption<ArchivedString>, Option<Map<String, Value>>, D>
+    for AsJsonString
+where
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    fn deserialize_with(
+        field: &ArchivedOption<ArchivedString>,
+        _: &mut D,
+    ) -> Result<Option<Map<String, Value>>, D::Error> {
+        match field {
+            ArchivedOption::Some(field) => serde_json::from_str(field.as_str())
+                .map(Some)
+                .map_err(D::Error::new),
+            ArchivedOption::None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Native;
+
+    #[test]
+    fn config_survives_rkyv_roundtrip() -> Result<(), rkyv::rancor::Error> {
+        let mut native = Native::new("my_native.dll");
+        native.config = json!({ "fov": 90, "keybinds": { "toggle": "F1" } })
+            .as_object()
+            .cloned();
+
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&native)?;
+        let archived = rkyv::access::<super::ArchivedNative, rkyv::rancor::Error>(&bytes)?;
+        let native_copy = rkyv::deserialize::<Native, rkyv::rancor::Error>(archived)?;
+
+        assert_eq!(native.config, native_copy.config);
+
+        Ok(())
+    }
+}
@@ -86,6 +86,25 @@ pub struct Package {
     /// A list of packages that this package should load before.
     #[serde(default)]
     pub(crate) load_before: Vec<Dependent<String>>,
+
+    /// A subdirectory of `path` to treat as the root of the package's assets, for mods packaged
+    /// with an extra wrapper folder (e.g. "mod").
+    #[serde(default)]
+    pub root: Option<String>,
+
+    /// A virtual directory to serve the package's assets under (e.g. "sd" for a folder of loose
+    /// sound files).
+    #[serde(default)]
+    pub mount: Option<String>,
+
+    /// Globs selecting the files to load from this package, matched against the paths they're
+    /// served under (e.g. "parts/**" or "chr/c2010*"). Every file is loaded if empty.
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Globs selecting files of this package to skip, even if they match an `include` glob.
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 impl Package {
@@ -96,6 +115,10 @@ impl Package {
             enabled: true,
             load_after: vec![],
             load_before: vec![],
+            root: None,
+            mount: None,
+            include: vec![],
+            exclude: vec![],
         }
     }
 
@@ -136,10 +159,55 @@ impl Dependency for Package {
 
 pub trait AssetOverrideSource {
     fn asset_path(&self) -> &Path;
+
+    /// Subdirectory of [`AssetOverrideSource::asset_path`] containing the assets.
+    fn root(&self) -> Option<&str> {
+        None
+    }
+
+    /// Virtual directory the assets are served under.
+    fn mount(&self) -> Option<&str> {
+        None
+    }
+
+    /// Globs selecting the assets to override. Every asset is selected if empty.
+    fn include(&self) -> &[String] {
+        &[]
+    }
+
+    /// Globs selecting assets not to override.
+    fn exclude(&self) -> &[String] {
+        &[]
+    }
+
+    /// Name of the source shown in diagnostics, e.g. asset traces.
+    fn name(&self) -> String {
+        self.asset_path().to_string_lossy().into_owned()
+    }
 }
 
 impl AssetOverrideSource for &Package {
     fn asset_path(&self) -> &Path {
         self.path.0.as_path()
     }
+
+    fn root(&self) -> Option<&str> {
+        self.root.as_deref()
+    }
+
+    fn mount(&self) -> Option<&str> {
+        self.mount.as_deref()
+    }
+
+    fn include(&self) -> &[String] {
+        &self.include
+    }
+
+    fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    fn name(&self) -> String {
+        self.id()
+    }
 }
@@ -0,0 +1,204 @@
+use std::{fmt, io::Write};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    fmt::MakeWriter,
+    layer::{Context, Layer},
+    registry::LookupSpan,
+};
+
+/// A single line of a JSON-lines log file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub timestamp: DateTime<Utc>,
+
+    /// Name of the process that emitted the record (e.g. "cli", "launcher" or "host").
+    pub process: String,
+
+    pub level: String,
+
+    pub target: String,
+
+    /// Names of the spans the record was emitted in, outermost first.
+    #[serde(default)]
+    pub spans: Vec<String>,
+
+    #[serde(default)]
+    pub trace_id: Option<String>,
+
+    #[serde(default)]
+    pub message: String,
+
+    #[serde(default)]
+    pub fields: Map<String, Value>,
+}
+
+/// Layer writing each event as a [`LogRecord`] line.
+pub struct JsonLayer<W> {
+    process: &'static str,
+    trace_id: Option<String>,
+    writer: W,
+}
+
+impl<W> JsonLayer<W> {
+    pub fn new(process: &'static str, trace_id: Option<String>, writer: W) -> Self {
+        Self {
+            process,
+            trace_id,
+            writer,
+        }
+    }
+}
+
+impl<S, W> Layer<S> for JsonLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| span.name().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let record = LogRecord {
+            timestamp: Utc::now(),
+            process: self.process.to_owned(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_owned(),
+            spans,
+            trace_id: self.trace_id.clone().or_else(crate::trace_id),
+            message: visitor.message,
+            fields: visitor.fields,
+        };
+
+        if let Ok(mut line) = serde_json::to_string(&record) {
+            line.push('\n');
+
+            let _ = self
+                .writer
+                .make_writer_for(metadata)
+                .write_all(line.as_bytes());
+        }
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl JsonVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = match value {
+                Value::String(s) => s,
+                value => value.to_string(),
+            };
+        } else {
+            self.fields.insert(field.name().to_owned(), value);
+        }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, value.to_string().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::prelude::*;
+
+    use super::{JsonLayer, LogRecord};
+
+    #[test]
+    fn records_spans_and_fields() {
+        let buf = Arc::new(Mutex::new(Vec::new()));
+
+        let writer = {
+            let buf = buf.clone();
+            move || WriteToBuf(buf.clone())
+        };
+
+        let subscriber = tracing_subscriber::registry().with(JsonLayer::new(
+            "test",
+            Some("trace".to_owned()),
+            writer,
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _outer = tracing::info_span!("outer").entered();
+            let _inner = tracing::info_span!("inner").entered();
+
+            tracing::warn!(count = 3, "hello");
+        });
+
+        let output = String::from_utf8(buf.lock().unwrap().clone()).unwrap();
+        let record: LogRecord = serde_json::from_str(output.trim_end()).unwrap();
+
+        assert_eq!(record.process, "test");
+        assert_eq!(record.level, "WARN");
+        assert_eq!(record.spans, ["outer", "inner"]);
+        assert_eq!(record.trace_id.as_deref(), Some("trace"));
+        assert_eq!(record.message, "hello");
+        assert_eq!(record.fields["count"], 3);
+    }
+
+    struct WriteToBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for WriteToBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
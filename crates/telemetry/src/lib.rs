@@ -1,18 +1,31 @@
-use std::{fs::OpenOptions, io::stdout};
+use std::{fs::OpenOptions, io::stdout, sync::Arc, time::Duration};
 
+pub use me3_env::LogFormat;
 use me3_env::TelemetryVars;
-use tracing::Level;
+use tracing::{warn, Level};
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{
+    filter::{Directive, ParseError},
     fmt::{self, writer::BoxMakeWriter, MakeWriter},
     prelude::*,
-    EnvFilter,
+    reload, EnvFilter,
 };
 
+pub use crate::json::{JsonLayer, LogRecord};
+
+mod json;
+
 #[derive(Default)]
 pub struct Telemetry {
     #[cfg(feature = "sentry")]
     client: Option<sentry::ClientInitGuard>,
+    log_filter: LogFilterHandle,
+}
+
+/// Changes the log filter of an installed [`Telemetry`] at runtime.
+#[derive(Clone, Default)]
+pub struct LogFilterHandle {
+    reloaders: Arc<[Reloader]>,
 }
 
 pub fn with_root_span<T>(
@@ -72,11 +85,47 @@ pub fn with_root_span<T>(
     result
 }
 
+impl Telemetry {
+    pub fn log_filter(&self) -> &LogFilterHandle {
+        &self.log_filter
+    }
+
+    /// Sends any pending crash reports and traces, waiting at most `timeout`.
+    pub fn flush(&self, timeout: Duration) {
+        #[cfg(feature = "sentry")]
+        if let Some(client) = &self.client {
+            client.flush(Some(timeout));
+        }
+
+        #[cfg(not(feature = "sentry"))]
+        let _ = timeout;
+    }
+}
+
+impl LogFilterHandle {
+    /// Replaces the extra filter directives (in `EnvFilter` syntax) applied on top of the default
+    /// log levels.
+    pub fn set(&self, directives: &str) -> color_eyre::Result<()> {
+        // Validate first so an invalid filter doesn't leave the writers with different filters.
+        if let Err(e) = parse_directives(directives) {
+            return Err(color_eyre::eyre::eyre!(
+                "invalid log filter {directives:?}: {e}"
+            ));
+        }
+
+        for reload in self.reloaders.iter() {
+            reload(directives).map_err(color_eyre::eyre::Error::msg)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Drop for Telemetry {
     fn drop(&mut self) {
         #[cfg(feature = "sentry")]
         if let Some(client) = self.client.take() {
-            client.flush(Some(std::time::Duration::from_secs(10)));
+            client.flush(Some(Duration::from_secs(10)));
         }
     }
 }
@@ -94,6 +143,9 @@ impl TryFrom<TelemetryVars> for TelemetryConfig {
             .enabled(value.enabled)
             .with_console_writer(stdout)
             .with_file_writer(log)
+            .with_file_format(value.log_format)
+            .with_trace_id(value.trace_id)
+            .with_log_filter(value.log_filter)
             .capture_panics(true))
     }
 }
@@ -104,6 +156,10 @@ pub struct TelemetryConfig {
     capture_panics: bool,
     file_writer: Option<TelemetryWriter>,
     console_writer: Option<TelemetryWriter>,
+    file_format: LogFormat,
+    process: Option<&'static str>,
+    trace_id: Option<String>,
+    log_filter: Option<String>,
 }
 
 pub type TelemetryWriter = BoxMakeWriter;
@@ -179,6 +235,33 @@ impl TelemetryConfig {
         }
     }
 
+    /// Sets the format of the records written to the file writer.
+    pub fn with_file_format(self, file_format: LogFormat) -> Self {
+        Self {
+            file_format,
+            ..self
+        }
+    }
+
+    /// Sets the name of this process, as recorded in JSON log records.
+    pub fn with_process(self, process: &'static str) -> Self {
+        Self {
+            process: Some(process),
+            ..self
+        }
+    }
+
+    /// Sets the trace ID recorded in JSON log records, shared by all processes of a session.
+    pub fn with_trace_id(self, trace_id: Option<String>) -> Self {
+        Self { trace_id, ..self }
+    }
+
+    /// Sets extra filter directives (in `EnvFilter` syntax) applied on top of the default log
+    /// levels, which can be changed later through [`Telemetry::log_filter`].
+    pub fn with_log_filter(self, log_filter: Option<String>) -> Self {
+        Self { log_filter, ..self }
+    }
+
     pub fn capture_panics(self, capture_panics: bool) -> Self {
         Self {
             capture_panics,
@@ -190,11 +273,54 @@ impl TelemetryConfig {
     }
 }
 
-fn log_filter(env_var: &str, default_directive: Level) -> EnvFilter {
-    EnvFilter::builder()
+fn log_filter(
+    env_var: &str,
+    default_directive: Level,
+    directives: &str,
+) -> Result<EnvFilter, ParseError> {
+    let filter = EnvFilter::builder()
         .with_default_directive(default_directive.into())
         .with_env_var(env_var)
-        .from_env_lossy()
+        .from_env_lossy();
+
+    parse_directives(directives)?
+        .into_iter()
+        .try_fold(filter, |filter, directive| {
+            Ok(filter.add_directive(directive))
+        })
+}
+
+type Reloader = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// Builds a filter that can be replaced later through the [`Reloader`] pushed to `reloaders`.
+fn reloadable_filter<S: 'static>(
+    env_var: &'static str,
+    default_directive: Level,
+    directives: &str,
+    reloaders: &mut Vec<Reloader>,
+) -> reload::Layer<EnvFilter, S> {
+    let filter = log_filter(env_var, default_directive, directives)
+        .expect("directives were already validated");
+
+    let (filter, handle) = reload::Layer::new(filter);
+
+    reloaders.push(Box::new(move |directives| {
+        let filter =
+            log_filter(env_var, default_directive, directives).map_err(|e| e.to_string())?;
+
+        handle.reload(filter).map_err(|e| e.to_string())
+    }));
+
+    filter
+}
+
+fn parse_directives(directives: &str) -> Result<Vec<Directive>, ParseError> {
+    directives
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(str::parse)
+        .collect()
 }
 
 pub fn install_error_handler() {
@@ -207,18 +333,48 @@ pub fn install_error_handler() {
 }
 
 pub fn install(config: TelemetryConfig) -> Telemetry {
-    let file_layer = config.file_writer.map(|writer| {
-        let filter_layer = log_filter("ME3_FILE_LOG_LEVEL", Level::DEBUG);
+    let process = config.process.unwrap_or("unknown");
+    let trace_id = config.trace_id;
+
+    // Fall back to the default levels if the filter is invalid, and complain once installed.
+    let directives = config.log_filter.unwrap_or_default();
+    let invalid_directives = parse_directives(&directives).err();
+    let directives = if invalid_directives.is_some() {
+        ""
+    } else {
+        &directives
+    };
 
-        fmt::layer()
-            .with_ansi(false)
-            .without_time()
-            .with_writer(writer)
-            .with_filter(filter_layer)
+    let mut reloaders = Vec::new();
+
+    let file_layer = config.file_writer.map(|writer| {
+        let filter_layer = reloadable_filter(
+            "ME3_FILE_LOG_LEVEL",
+            Level::DEBUG,
+            directives,
+            &mut reloaders,
+        );
+
+        match config.file_format {
+            LogFormat::Text => fmt::layer()
+                .with_ansi(false)
+                .without_time()
+                .with_writer(writer)
+                .with_filter(filter_layer)
+                .boxed(),
+            LogFormat::Json => JsonLayer::new(process, trace_id, writer)
+                .with_filter(filter_layer)
+                .boxed(),
+        }
     });
 
     let console_layer = config.console_writer.map(|writer| {
-        let filter_layer = log_filter("ME3_CONSOLE_LOG_LEVEL", Level::INFO);
+        let filter_layer = reloadable_filter(
+            "ME3_CONSOLE_LOG_LEVEL",
+            Level::INFO,
+            directives,
+            &mut reloaders,
+        );
 
         fmt::layer()
             .without_time()
@@ -239,6 +395,10 @@ pub fn install(config: TelemetryConfig) -> Telemetry {
         .with(layer)
         .init();
 
+    if let Some(e) = invalid_directives {
+        warn!(error = %e, "ignoring invalid log filter");
+    }
+
     #[cfg(feature = "sentry")]
     let client = {
         use std::str::FromStr;
@@ -296,5 +456,62 @@ pub fn install(config: TelemetryConfig) -> Telemetry {
     Telemetry {
         #[cfg(feature = "sentry")]
         client,
+        log_filter: LogFilterHandle {
+            reloaders: reloaders.into(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::Level;
+    use tracing_subscriber::prelude::*;
+
+    use super::{reloadable_filter, JsonLayer};
+
+    #[derive(Clone, Default)]
+    struct Buf(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reloaded_filter_is_applied() {
+        let buf = Buf::default();
+        let mut reloaders = Vec::new();
+
+        let filter = reloadable_filter("ME3_TEST_LOG_LEVEL", Level::INFO, "", &mut reloaders);
+
+        let subscriber = tracing_subscriber::registry().with(
+            JsonLayer::new("test", None, {
+                let buf = buf.clone();
+                move || buf.clone()
+            })
+            .with_filter(filter),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "assets", "before reload");
+
+            reloaders[0]("assets=debug").unwrap();
+
+            tracing::debug!(target: "assets", "after reload");
+        });
+
+        let output = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+
+        assert!(!output.contains("before reload"));
+        assert!(output.contains("after reload"));
+        assert!(reloaders[0]("assets=bogus").is_err());
     }
 }
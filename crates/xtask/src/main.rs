@@ -1,3 +1,33 @@
-fn main() {
-    println!("Hello, world!");
+use std::{env, io, path::Path, process::ExitCode};
+
+const USAGE: &str = "usage: cargo run --package xtask -- <task>
+
+tasks:
+    ext-header    print the C header for the me3 extension API";
+
+fn main() -> ExitCode {
+    match env::args().nth(1).as_deref() {
+        Some("ext-header") => ext_header(),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Generates `crates/ext/include/me3.h` from the `me3-ext` crate.
+fn ext_header() -> ExitCode {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../ext");
+    let config = cbindgen::Config::from_root_or_default(&crate_dir);
+
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write(io::stdout());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("failed to generate the extension API header: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }